@@ -0,0 +1,141 @@
+/// Random scope trees for property testing, enabled with the `proptest` feature.
+///
+/// The strategy first generates a plain description of the tree, and only builds
+/// the actual scopes when a value is requested, so shrinking works on the shape.
+use ::proptest::collection::{hash_set, vec};
+use ::proptest::prelude::*;
+
+use crate::scope::{RootScope, Scope};
+
+/// Given names are generated from this pattern; anonymous prefixes too, or empty.
+const IDENTIFIER: &str = "[a-z][a-z0-9_]{0,7}";
+
+/// Size bounds for generated scope trees.
+#[derive(Debug, Clone)]
+pub struct TreeBounds {
+    /// Maximum nesting depth below the root.
+    pub max_depth: u32,
+    /// Target total number of scopes (not a hard limit).
+    pub max_scopes: u32,
+    /// Maximum number of children of a single scope.
+    pub max_children: usize,
+    /// Maximum number of given names in a single scope.
+    pub max_names: usize,
+    /// Maximum number of anonymous names in a single scope.
+    pub max_anonymous: usize,
+}
+
+impl Default for TreeBounds {
+    fn default() -> Self {
+        TreeBounds {
+            max_depth: 4,
+            max_scopes: 32,
+            max_children: 4,
+            max_names: 6,
+            max_anonymous: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TreeShape {
+    names: Vec<String>,
+    anonymous: Vec<String>,
+    children: Vec<TreeShape>,
+}
+
+impl TreeShape {
+    fn build(&self, scope: &Scope) {
+        for name in &self.names {
+            scope
+                .add_named(name)
+                .expect("generated names are unique per scope");
+        }
+        for prefix in &self.anonymous {
+            scope.add_prefixed(prefix);
+        }
+        for child in &self.children {
+            child.build(&scope.add_child());
+        }
+    }
+}
+
+fn shape_strategy(bounds: &TreeBounds) -> BoxedStrategy<TreeShape> {
+    let names = hash_set(IDENTIFIER, 0..=bounds.max_names).prop_map(|names| {
+        let mut names: Vec<String> = names.into_iter().collect();
+        names.sort();
+        names
+    });
+    let anonymous = vec(
+        prop_oneof![Just(String::new()), IDENTIFIER],
+        0..=bounds.max_anonymous,
+    );
+    let leaf = (names.clone(), anonymous.clone()).prop_map(|(names, anonymous)| TreeShape {
+        names,
+        anonymous,
+        children: vec![],
+    });
+    let max_children = bounds.max_children;
+    leaf.prop_recursive(
+        bounds.max_depth,
+        bounds.max_scopes,
+        max_children as u32,
+        move |inner| {
+            (
+                names.clone(),
+                anonymous.clone(),
+                vec(inner, 0..=max_children),
+            )
+                .prop_map(|(names, anonymous, children)| TreeShape {
+                    names,
+                    anonymous,
+                    children,
+                })
+        },
+    )
+    .boxed()
+}
+
+/// Strategy for random scope trees, yielding the scope of a new root each time.
+pub fn scope_tree(bounds: TreeBounds) -> impl Strategy<Value = Scope> {
+    shape_strategy(&bounds).prop_map(|shape| {
+        let root = RootScope::new_root();
+        shape.build(&root);
+        root
+    })
+}
+
+impl Arbitrary for Scope {
+    type Parameters = TreeBounds;
+    type Strategy = BoxedStrategy<Scope>;
+
+    fn arbitrary_with(bounds: Self::Parameters) -> Self::Strategy {
+        scope_tree(bounds).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(scope: &Scope) -> u32 {
+        scope
+            .children()
+            .map(|child| 1 + depth(&child))
+            .max()
+            .unwrap_or(0)
+    }
+
+    proptest! {
+        #[test]
+        fn respects_bounds(root in scope_tree(TreeBounds { max_depth: 2, max_names: 3, ..TreeBounds::default() })) {
+            prop_assert!(depth(&root) <= 2);
+            prop_assert!(root.given_names().len() <= 3);
+        }
+
+        #[test]
+        fn arbitrary_root(root in any::<Scope>()) {
+            prop_assert!(root.children().count() <= TreeBounds::default().max_children);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+/// Names whose identity includes an arity, like `foo/1` and `foo/2` in Erlang or Prolog.
+///
+/// These are distinct names, so they can be declared in the same scope, and they do not
+/// collide with the ordinary name `foo` either. Generation only makes their outputs differ
+/// on collision, unless the arity is made part of them, see `OutputPolicy::arity_separator`.
+use ::ustr::Ustr;
+
+use crate::limit::RegisterError;
+use crate::name::{GivenName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+impl Scope {
+    /// Register `name/arity` in this scope, failing if that combination is already registered.
+    pub fn add_with_arity(&self, name: &str, arity: u32) -> Result<Name, RegisterError> {
+        self.add_given(GivenName {
+            arity: Some(arity),
+            ..self.root.given_name(name)
+        })
+    }
+
+    /// Find the nearest declaration of `name/arity`, starting from this scope and moving outwards.
+    pub fn resolve_with_arity(&self, name: &str, arity: u32) -> Option<Name> {
+        let given_name = GivenName {
+            arity: Some(arity),
+            ..self.root.given_name(name)
+        };
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            if let Some(name) = scope.find_given(&given_name) {
+                return Some(name);
+            }
+            current = scope.parent();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::explain::RenameReason;
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn distinct_arities() {
+        let root = RootScope::new_root();
+        let foo1 = root.add_with_arity("foo", 1).unwrap();
+        let foo2 = root.add_with_arity("foo", 2).unwrap();
+        root.add_named("foo").unwrap();
+        root.add_with_arity("foo", 1).unwrap_err();
+        assert_ne!(foo1, foo2);
+        assert_eq!(foo1.unwrap_given().arity(), Some(1));
+    }
+
+    #[test]
+    fn resolve_arity() {
+        let root = RootScope::new_root();
+        root.add_with_arity("foo", 1).unwrap();
+        let child = root.add_child();
+        let inner = child.add_with_arity("foo", 2).unwrap();
+        assert_eq!(child.resolve_with_arity("foo", 2), Some(inner));
+        assert_eq!(child.resolve_with_arity("foo", 1).unwrap().scope(), root);
+        assert!(child.resolve_with_arity("foo", 3).is_none());
+    }
+
+    #[test]
+    fn mangle_arity() {
+        let root = RootScope::new_root();
+        let foo = root.add_named("foo").unwrap();
+        let foo1 = root.add_with_arity("foo", 1).unwrap();
+        let foo2 = root.add_with_arity("foo", 2).unwrap();
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&foo).unwrap().as_str(), "foo");
+        assert_eq!(map.get(&foo1).unwrap().as_str(), "foo2");
+        assert_eq!(map.get(&foo2).unwrap().as_str(), "foo3");
+        let policy = OutputPolicy {
+            arity_separator: Some('_'),
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&foo).unwrap().as_str(), "foo");
+        assert_eq!(map.get(&foo1).unwrap().as_str(), "foo_1");
+        assert_eq!(map.get(&foo2).unwrap().as_str(), "foo_2");
+        assert_eq!(map.renames()[0].reasons, vec![RenameReason::Formatted]);
+    }
+}
@@ -0,0 +1,148 @@
+/// Storage used for the arenas of scopes, names and references.
+///
+/// By default these are plain vectors. With the `persistent` feature, they are persistent
+/// vectors with structural sharing, so `RootScope::snapshot` is cheap, and a changed scope
+/// copies only itself rather than the whole tree. With `sync` as well, they are the
+/// thread-safe persistent vectors of `im`.
+#[cfg(not(feature = "persistent"))]
+pub(crate) type Arena<T> = Vec<T>;
+
+#[cfg(all(feature = "persistent", not(feature = "sync")))]
+pub(crate) type Arena<T> = ::im_rc::Vector<T>;
+
+#[cfg(all(feature = "persistent", feature = "sync"))]
+pub(crate) type Arena<T> = ::im::Vector<T>;
+
+pub(crate) fn new_arena<T: Clone>() -> Arena<T> {
+    Arena::new()
+}
+
+#[cfg(not(feature = "persistent"))]
+pub(crate) fn push<T: Clone>(arena: &mut Arena<T>, item: T) {
+    arena.push(item)
+}
+
+#[cfg(feature = "persistent")]
+pub(crate) fn push<T: Clone>(arena: &mut Arena<T>, item: T) {
+    arena.push_back(item)
+}
+
+#[cfg(not(feature = "persistent"))]
+pub(crate) fn reserve<T: Clone>(arena: &mut Arena<T>, additional: usize) {
+    arena.reserve(additional)
+}
+
+// Persistent vectors allocate in chunks, so there is nothing to reserve.
+#[cfg(feature = "persistent")]
+pub(crate) fn reserve<T: Clone>(_arena: &mut Arena<T>, _additional: usize) {}
+
+/// Sharing and interior mutability of the root's data.
+///
+/// By default the root is reference-counted with `Rc`, and its fields are `RefCell`s and
+/// `Cell`s. With the `sync` feature, these are `Arc` and locks with the same methods, so
+/// trees are `Send` and `Sync`. Shared borrows are recursive, so nested reads on one thread
+/// do not block each other even while another thread waits to write.
+///
+/// Reads take a shared lock rather than being lock-free: the arenas are not append-only,
+/// since entries change in place after they are pushed (outputs, removal, parents and
+/// children when scopes move), so readers must not see an entry while it is written.
+#[cfg(not(feature = "sync"))]
+pub(crate) use ::std::cell::{Cell as Flag, RefCell as Lock};
+#[cfg(not(feature = "sync"))]
+pub(crate) use ::std::rc::Rc as Shared;
+
+#[cfg(feature = "sync")]
+pub(crate) use self::sync::{Flag, Lock};
+#[cfg(feature = "sync")]
+pub(crate) use ::std::sync::Arc as Shared;
+
+/// A lock held for the whole of an operation that must appear atomic to other threads,
+/// like checking that a name is new and then registering it. Without the `sync` feature,
+/// there are no other threads, so this does nothing.
+#[cfg(not(feature = "sync"))]
+#[derive(Debug)]
+pub(crate) struct Exclusive;
+
+#[cfg(not(feature = "sync"))]
+pub(crate) struct ExclusiveGuard;
+
+#[cfg(not(feature = "sync"))]
+impl Exclusive {
+    pub(crate) fn lock(&self) -> ExclusiveGuard {
+        ExclusiveGuard
+    }
+}
+
+#[cfg(feature = "sync")]
+pub(crate) type Exclusive = ::parking_lot::ReentrantMutex<()>;
+
+#[cfg(not(feature = "sync"))]
+pub(crate) fn new_exclusive() -> Exclusive {
+    Exclusive
+}
+
+#[cfg(feature = "sync")]
+pub(crate) fn new_exclusive() -> Exclusive {
+    Exclusive::new(())
+}
+
+#[cfg(feature = "sync")]
+mod sync {
+    use ::parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+    /// Like `RefCell`, but blocking instead of panicking when the value is borrowed.
+    #[derive(Debug, Default)]
+    pub(crate) struct Lock<T>(RwLock<T>);
+
+    impl<T> Lock<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Lock(RwLock::new(value))
+        }
+
+        pub(crate) fn borrow(&self) -> RwLockReadGuard<'_, T> {
+            self.0.read_recursive()
+        }
+
+        pub(crate) fn borrow_mut(&self) -> RwLockWriteGuard<'_, T> {
+            self.0.write()
+        }
+
+        pub(crate) fn replace(&self, value: T) -> T {
+            ::std::mem::replace(&mut *self.0.write(), value)
+        }
+
+        pub(crate) fn get_mut(&mut self) -> &mut T {
+            self.0.get_mut()
+        }
+    }
+
+    impl<T: Clone> Clone for Lock<T> {
+        fn clone(&self) -> Self {
+            Lock::new(self.borrow().clone())
+        }
+    }
+
+    /// Like `Cell`, for small copyable values.
+    #[derive(Debug, Default)]
+    pub(crate) struct Flag<T>(Mutex<T>);
+
+    impl<T: Copy> Flag<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Flag(Mutex::new(value))
+        }
+
+        pub(crate) fn get(&self) -> T {
+            *self.0.lock()
+        }
+
+        pub(crate) fn set(&self, value: T) {
+            *self.0.lock() = value;
+        }
+    }
+
+    impl<T: Copy> Clone for Flag<T> {
+        fn clone(&self) -> Self {
+            Flag::new(self.get())
+        }
+    }
+}
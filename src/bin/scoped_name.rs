@@ -0,0 +1,155 @@
+/// Command line tool for inspecting scope trees, enabled with the `cli` feature.
+///
+/// It reads a scope tree from a JSON file (or stdin when the path is `-`), in the format
+/// of `ScopeSpec`, like:
+///
+///     {"names": ["x", "y"], "anonymous": ["tmp", ""], "children": [{"names": ["x"]}]}
+///
+/// where anonymous entries are prefixes, and an empty prefix means no prefix.
+///
+/// The `tree` command prints the tree, and the `names` command prints the rename table
+/// from generating output names, with the default policy unless a profile is chosen with
+/// `--profile` (`javascript`, `filesystem` or `sql`), and its strategy unless another is
+/// chosen with `--strategy` (`descriptive`, `minify`, `compress` or `preserve`).
+use ::std::env;
+use ::std::fs;
+use ::std::io::{self, Read};
+use ::std::process::exit;
+
+use ::scoped_name::{
+    DuplicateName, GenerateError, GenerationOrder, OutputPolicy, RootScope, Scope, ScopeSpec,
+    SpecError, SqlDialect, Strategy,
+};
+
+const USAGE: &str = "usage: scoped-name (tree | names [--profile <profile>] [--strategy <strategy>]) <file.json | ->";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(|arg| arg.as_str())
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["tree", path] => {
+            let root = match read_tree(path) {
+                Ok(root) => root,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    exit(1)
+                }
+            };
+            let mut out = String::new();
+            print_scope(&root, 0, &mut out);
+            print!("{}", out);
+        }
+        ["names", options @ .., path] => {
+            let policy = match read_policy(options) {
+                Ok(policy) => policy,
+                Err(err) => {
+                    eprintln!("{}\n{}", err, USAGE);
+                    exit(2)
+                }
+            };
+            let root = match read_tree(path) {
+                Ok(root) => root,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    exit(1)
+                }
+            };
+            let map = match root.root().generate_names(&policy) {
+                Ok(map) => map,
+                Err(GenerateError::NotUnifiable(err)) => {
+                    eprintln!(
+                        "cannot give {} and {} the same output",
+                        err.first(),
+                        err.second()
+                    );
+                    exit(1)
+                }
+                Err(GenerateError::Exhausted(name)) => {
+                    eprintln!("no free output left for {}", name);
+                    exit(1)
+                }
+            };
+            for name in root.root().names_in_order(GenerationOrder::Registration) {
+                if let Some(output) = map.get(&name) {
+                    println!("{} -> {}", name, output);
+                }
+            }
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2)
+        }
+    }
+}
+
+fn read_policy(options: &[&str]) -> Result<OutputPolicy, String> {
+    let mut policy = OutputPolicy::default();
+    let mut strategy = None;
+    for option in options.chunks(2) {
+        match option {
+            ["--profile", "javascript"] => policy = OutputPolicy::javascript(),
+            ["--profile", "filesystem"] => policy = OutputPolicy::filesystem(),
+            ["--profile", "sql"] => policy = OutputPolicy::sql(SqlDialect::Standard),
+            ["--strategy", "descriptive"] => strategy = Some(Strategy::Descriptive),
+            ["--strategy", "minify"] => strategy = Some(Strategy::Minify),
+            ["--strategy", "compress"] => strategy = Some(Strategy::Compress),
+            ["--strategy", "preserve"] => strategy = Some(Strategy::Preserve),
+            _ => return Err(format!("invalid option: {}", option.join(" "))),
+        }
+    }
+    if let Some(strategy) = strategy {
+        policy.strategy = strategy;
+    }
+    Ok(policy)
+}
+
+fn read_tree(path: &str) -> Result<Scope, String> {
+    let mut content = String::new();
+    if path == "-" {
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|err| format!("could not read stdin: {}", err))?;
+    } else {
+        content = fs::read_to_string(path)
+            .map_err(|err| format!("could not read '{}': {}", path, err))?;
+    }
+    let spec: ScopeSpec =
+        ::serde_json::from_str(&content).map_err(|err| format!("invalid scope tree: {}", err))?;
+    RootScope::from_spec(&spec).map_err(|err| match err {
+        SpecError::DuplicateName(DuplicateName(name)) => {
+            format!("duplicate name '{}' in the same scope", name)
+        }
+        SpecError::LimitExceeded(exceeded) => exceeded.to_string(),
+    })
+}
+
+fn print_scope(scope: &Scope, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    match scope.debug_label() {
+        Some(debug_label) => out.push_str(&format!("{}scope ({})\n", indent, debug_label)),
+        None => out.push_str(&format!("{}scope\n", indent)),
+    }
+    let mut given: Vec<String> = scope
+        .given_names()
+        .iter()
+        .map(|name| name.as_str().to_owned())
+        .collect();
+    given.sort();
+    for name in given {
+        out.push_str(&format!("{}  {}\n", indent, name));
+    }
+    for anon in scope.anonymous_names() {
+        out.push_str(&format!(
+            "{}  <{}>\n",
+            indent,
+            anon.prefix().unwrap_or("anonymous")
+        ));
+    }
+    for child in scope.children() {
+        print_scope(&child, depth + 1, out);
+    }
+}
@@ -0,0 +1,139 @@
+/// Building the scope tree from a front-end's own syntax tree, so that integrating the crate
+/// into an existing compiler is a trait implementation instead of a hand-written traversal.
+///
+/// The driver visits nodes depth-first. Each node first declares its names in the current
+/// scope, then, if it opens a scope, its children are visited in a new child scope. So a
+/// function node declares its own name outside, while its parameters, being child nodes,
+/// are declared inside.
+///
+/// Declaring and opening scopes can fail, e.g. on duplicates or when a limit is exceeded
+/// (see `RootScope::set_limits`), in which case the traversal stops; what was built before
+/// the failing node is kept. Nodes should use the `try_` adders, like `Scope::try_add_named`.
+use ::std::ops::Range;
+
+use crate::kind::ScopeKind;
+use crate::limit::RegisterError;
+use crate::scope::{RootScope, Scope};
+
+/// A syntax tree node that can be visited by `Scope::build_from`.
+pub trait BuildScopes {
+    /// Register the names that this node declares in the scope that contains it.
+    fn declare(&self, scope: &Scope) -> Result<(), RegisterError>;
+
+    /// The kind of scope that this node opens for its children, or None if its children
+    /// are part of the containing scope.
+    fn opens_scope(&self) -> Option<ScopeKind> {
+        None
+    }
+
+    /// The source range of the scope this node opens, see `Scope::set_span`.
+    fn span(&self) -> Option<Range<usize>> {
+        None
+    }
+
+    /// The nodes directly below this one, in source order.
+    fn children(&self) -> Vec<&Self>;
+}
+
+impl Scope {
+    /// Declare the names and scopes of a syntax tree inside this scope.
+    pub fn build_from<N: BuildScopes>(&self, node: &N) -> Result<(), RegisterError> {
+        node.declare(self)?;
+        let scope = match node.opens_scope() {
+            Some(kind) => {
+                let child = self.try_add_child()?;
+                child.set_kind(kind);
+                if let Some(span) = node.span() {
+                    child.set_span(span);
+                }
+                child
+            }
+            None => self.clone(),
+        };
+        for child in node.children() {
+            scope.build_from(child)?;
+        }
+        Ok(())
+    }
+}
+
+impl RootScope {
+    /// Create a new tree for a syntax tree; the root node's scope, if any, is a child of
+    /// the root scope.
+    pub fn build<N: BuildScopes>(node: &N) -> Result<Scope, RegisterError> {
+        let root = RootScope::new_root();
+        root.build_from(node)?;
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::build::BuildScopes;
+    use crate::kind::ScopeKind;
+    use crate::limit::{LimitExceeded, Limits, RegisterError};
+    use crate::scope::{RootScope, Scope};
+
+    enum Node {
+        Module(Vec<Node>),
+        Function(&'static str, Vec<Node>),
+        Let(&'static str),
+    }
+
+    impl BuildScopes for Node {
+        fn declare(&self, scope: &Scope) -> Result<(), RegisterError> {
+            match self {
+                Node::Module(_) => {}
+                Node::Function(name, _) | Node::Let(name) => {
+                    scope.try_add_named(name)?;
+                }
+            }
+            Ok(())
+        }
+
+        fn opens_scope(&self) -> Option<ScopeKind> {
+            match self {
+                Node::Function(..) => Some(ScopeKind::Function),
+                Node::Module(_) | Node::Let(_) => None,
+            }
+        }
+
+        fn children(&self) -> Vec<&Self> {
+            match self {
+                Node::Module(children) | Node::Function(_, children) => children.iter().collect(),
+                Node::Let(_) => vec![],
+            }
+        }
+    }
+
+    #[test]
+    fn build_from_tree() {
+        let tree = Node::Module(vec![
+            Node::Let("x"),
+            Node::Function("f", vec![Node::Let("x"), Node::Let("y")]),
+            Node::Function("g", vec![]),
+        ]);
+        let root = RootScope::build(&tree).unwrap();
+        assert_eq!(root.names().len(), 3);
+        let functions: Vec<Scope> = root.children().collect();
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].kind(), ScopeKind::Function);
+        assert_eq!(functions[0].resolve("x").unwrap().scope(), functions[0]);
+        assert_eq!(functions[0].resolve("f").unwrap().scope(), root);
+        assert_eq!(functions[1].resolve("x").unwrap().scope(), root);
+        let limited = RootScope::new_root();
+        limited.root().set_limits(Limits {
+            max_scopes: Some(2),
+            ..Limits::default()
+        });
+        assert_eq!(
+            limited.build_from(&tree),
+            Err(RegisterError::LimitExceeded(LimitExceeded::Scopes(2)))
+        );
+        let duplicate = Node::Module(vec![Node::Let("x"), Node::Let("x")]);
+        assert_eq!(
+            RootScope::build(&duplicate).unwrap_err(),
+            RegisterError::AlreadyExists
+        );
+    }
+}
@@ -0,0 +1,123 @@
+/// Configuring a new tree before it is created, instead of through setters afterwards, so
+/// that every scope and name sees the same configuration.
+use crate::backend;
+use crate::canonical::Canonicalization;
+use crate::limit::Limits;
+use crate::scope::{RootId, RootScope, Scope};
+
+/// Builder for a new tree, returned by `RootScope::builder`.
+#[derive(Debug, Clone, Default)]
+pub struct RootScopeBuilder {
+    id: Option<RootId>,
+    case_insensitive: bool,
+    merge_duplicates: bool,
+    canonicalization: Canonicalization,
+    provenance: Option<String>,
+    reserved: Vec<String>,
+    scope_capacity: usize,
+    name_capacity: usize,
+    limits: Limits,
+}
+
+impl RootScope {
+    pub fn builder() -> RootScopeBuilder {
+        RootScopeBuilder::default()
+    }
+}
+
+impl RootScopeBuilder {
+    /// Use a known id, see `RootScope::new_root_with_id`. By default a random one is used.
+    pub fn id(mut self, id: RootId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Make the whole tree case-insensitive, see `Scope::set_case_insensitive`.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// See `RootScope::set_merge_duplicates`.
+    pub fn merge_duplicates(mut self, merge: bool) -> Self {
+        self.merge_duplicates = merge;
+        self
+    }
+
+    /// See `RootScope::set_canonicalization`.
+    pub fn canonicalization(mut self, canonicalization: Canonicalization) -> Self {
+        self.canonicalization = canonicalization;
+        self
+    }
+
+    /// The label to record on names, see `RootScope::set_provenance`.
+    pub fn provenance(mut self, label: &str) -> Self {
+        self.provenance = Some(label.to_owned());
+        self
+    }
+
+    /// Outputs to avoid in the whole tree, typically the keywords of the target language,
+    /// see `Scope::reserve`.
+    pub fn reserve(mut self, texts: &[&str]) -> Self {
+        self.reserved
+            .extend(texts.iter().map(|text| (*text).to_owned()));
+        self
+    }
+
+    /// The expected number of scopes and names, to allocate room for them up front.
+    pub fn capacity(mut self, scopes: usize, names: usize) -> Self {
+        self.scope_capacity = scopes;
+        self.name_capacity = names;
+        self
+    }
+
+    /// See `RootScope::set_limits`.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Create the tree, and return its root scope.
+    pub fn build(self) -> Scope {
+        let root = RootScope::new_root_with_id(self.id.unwrap_or_else(RootId::random));
+        let root_data = &root.root.root_data;
+        backend::reserve(&mut root_data.scopes.borrow_mut(), self.scope_capacity);
+        backend::reserve(&mut root_data.names.borrow_mut(), self.name_capacity);
+        if self.case_insensitive {
+            root.set_case_insensitive(true);
+        }
+        root.root().set_merge_duplicates(self.merge_duplicates);
+        root.root().set_canonicalization(self.canonicalization);
+        root.root().set_limits(self.limits);
+        root.root().set_provenance(self.provenance.as_deref());
+        for text in &self.reserved {
+            root.reserve(text);
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::{RootId, RootScope};
+
+    #[test]
+    fn build_configured_root() {
+        let root = RootScope::builder()
+            .id(RootId(7))
+            .case_insensitive(true)
+            .merge_duplicates(true)
+            .provenance("parser")
+            .reserve(&["fn", "let"])
+            .capacity(16, 64)
+            .build();
+        assert_eq!(root.root().id(), RootId(7));
+        assert!(root.is_case_insensitive());
+        assert!(root.root().merges_duplicates());
+        assert!(root.is_reserved("fn"));
+        let x = root.add_named("x").unwrap();
+        assert_eq!(root.add_named("x").unwrap(), x);
+        assert_eq!(root.resolve("X"), Some(x.clone()));
+        assert_eq!(x.provenance().unwrap().as_str(), "parser");
+    }
+}
@@ -0,0 +1,135 @@
+/// Normalizing the spelling of given names, so that equivalent spellings, e.g. with stray
+/// whitespace from a template, are detected as duplicates instead of becoming two names
+/// that look the same.
+///
+/// The canonical form is used when names of any kind are registered and whenever they are
+/// looked up by text, including through namespaces, configuration views and frozen trees.
+/// Other normalizations, like Unicode normalization, can be plugged in as a custom
+/// function, so the crate does not depend on the Unicode tables.
+use ::std::borrow::Cow;
+
+use crate::name::GivenName;
+use crate::scope::RootScope;
+use crate::text::NameText;
+
+/// Rules to normalize given names, see `RootScope::set_canonicalization`. By default,
+/// names are used as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Canonicalization {
+    /// Remove whitespace at the start and end.
+    pub trim: bool,
+    /// Replace each run of whitespace by a single space.
+    pub collapse_whitespace: bool,
+    /// Applied after the other rules, e.g. for Unicode normalization.
+    pub custom: Option<fn(&str) -> String>,
+}
+
+impl Canonicalization {
+    fn is_identity(&self) -> bool {
+        !self.trim && !self.collapse_whitespace && self.custom.is_none()
+    }
+
+    /// The canonical form of a name.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.is_identity() {
+            return Cow::Borrowed(text);
+        }
+        let mut canonical = Cow::Borrowed(if self.trim { text.trim() } else { text });
+        if self.collapse_whitespace && canonical.contains(char::is_whitespace) {
+            let mut collapsed = String::with_capacity(canonical.len());
+            for character in canonical.chars() {
+                if !character.is_whitespace() {
+                    collapsed.push(character);
+                } else if !collapsed.ends_with(' ') {
+                    collapsed.push(' ');
+                }
+            }
+            canonical = Cow::Owned(collapsed);
+        }
+        match self.custom {
+            Some(custom) => Cow::Owned(custom(&canonical)),
+            None => canonical,
+        }
+    }
+}
+
+impl RootScope {
+    /// Set how given names are normalized from now on. Names registered earlier are not
+    /// changed, so this is typically set right after creating the root.
+    pub fn set_canonicalization(&self, canonicalization: Canonicalization) {
+        self.root_data.canonicalization.set(canonicalization);
+    }
+
+    pub fn canonicalization(&self) -> Canonicalization {
+        self.root_data.canonicalization.get()
+    }
+
+    pub(crate) fn canonical<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.root_data.canonicalization.get().apply(text)
+    }
+
+    /// The ordinary given name for the text in canonical form. Every registration and
+    /// lookup by text goes through this (or `Scope::add_given`), so that they agree.
+    pub(crate) fn given_name(&self, text: &str) -> GivenName {
+        GivenName::new(NameText::from(self.canonical(text).as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canonical::Canonicalization;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn equivalent_spellings() {
+        let root = RootScope::new_root();
+        root.root().set_canonicalization(Canonicalization {
+            trim: true,
+            collapse_whitespace: true,
+            custom: None,
+        });
+        let name = root.add_named(" operator  plus ").unwrap();
+        assert_eq!(name.clone().unwrap_given().as_str(), "operator plus");
+        assert!(root.add_named("operator plus").is_err());
+        assert_eq!(root.resolve("operator\tplus"), Some(name));
+    }
+
+    #[test]
+    fn canonical_on_every_path() {
+        let root = RootScope::new_root();
+        root.root().set_canonicalization(Canonicalization {
+            trim: true,
+            ..Canonicalization::default()
+        });
+        let class = root.namespace("css").add_named(" main ").unwrap();
+        assert!(root.namespace("css").add_named("main").is_err());
+        assert_eq!(root.namespace("css").resolve("main "), Some(class));
+        let arity = root.add_with_arity(" f", 1).unwrap();
+        assert_eq!(root.resolve_with_arity("f ", 1), Some(arity));
+        let flagged = root.add_named("debug ").unwrap();
+        flagged.set_condition("debug");
+        assert_eq!(
+            root.root().view(&["debug"]).resolve(&root, " debug"),
+            Some(flagged)
+        );
+        let frozen = root.root().freeze();
+        let x = root.add_named("x").unwrap();
+        assert!(frozen.resolve(0, " f").is_none());
+        assert_eq!(
+            frozen.resolve(0, " debug ").map(|name| frozen.text(name)),
+            Some("debug")
+        );
+        assert_eq!(root.declare(" x").unwrap().name(), x);
+    }
+
+    #[test]
+    fn custom_rule() {
+        let canonicalization = Canonicalization {
+            collapse_whitespace: true,
+            custom: Some(|text| text.replace('\u{212B}', "\u{00C5}")),
+            ..Canonicalization::default()
+        };
+        assert_eq!(canonicalization.apply("a  b\u{212B} "), "a b\u{00C5} ");
+        assert_eq!(Canonicalization::default().apply(" x "), " x ");
+    }
+}
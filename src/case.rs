@@ -0,0 +1,108 @@
+/// Case-insensitive subtrees, e.g. an embedded SQL or BASIC block inside an otherwise
+/// case-sensitive program.
+///
+/// In a case-insensitive scope, names that differ only in case are duplicates, lookups of
+/// names declared there ignore case, and `RootScope::check_injective` treats outputs that
+/// differ only in case as conflicts. The setting is inherited by nested scopes, unless they
+/// override it. Names keep the case they were registered with.
+use crate::name::GivenName;
+use crate::scope::Scope;
+
+impl Scope {
+    /// Make this scope and the scopes nested in it case-insensitive, or case-sensitive again.
+    pub fn set_case_insensitive(&self, case_insensitive: bool) {
+        if case_insensitive {
+            self.root.root_data.has_case_insensitive.set(true);
+        }
+        self.root.scope_data_at(self.index, |data| {
+            data.case_insensitive = Some(case_insensitive)
+        });
+    }
+
+    /// Whether names in this scope ignore case, as set on it or the nearest ancestor.
+    pub fn is_case_insensitive(&self) -> bool {
+        if !self.root.root_data.has_case_insensitive.get() {
+            return false;
+        }
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            let setting = scope
+                .root
+                .read_scope_data_at(scope.index, |data| data.case_insensitive);
+            if let Some(case_insensitive) = setting {
+                return case_insensitive;
+            }
+            current = scope.parent();
+        }
+        false
+    }
+
+    /// The arena index of a name in this scope that equals the given name except for case,
+    /// if this scope is case-insensitive.
+    pub(crate) fn find_given_ignoring_case(&self, given_name: &GivenName) -> Option<usize> {
+        if !self.is_case_insensitive() {
+            return None;
+        }
+        let folded = given_name.as_str().to_lowercase();
+        self.root.scope_names_at(self.index, |data| {
+            data.given_names
+                .iter()
+                .find(|(other, _)| {
+                    other.as_str().to_lowercase() == folded
+                        && GivenName {
+                            name: given_name.name.clone(),
+                            ..(*other).clone()
+                        } == *given_name
+                })
+                .map(|(_, index)| *index)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn case_insensitive_subtree() {
+        let root = RootScope::new_root();
+        let program = root.add_named("Total").unwrap();
+        root.add_named("total").unwrap();
+        let sql = root.add_child();
+        sql.set_case_insensitive(true);
+        let query = sql.add_child();
+        let column = sql.add_named("Amount").unwrap();
+        sql.add_named("AMOUNT").unwrap_err();
+        assert_eq!(query.resolve("amount"), Some(column));
+        assert_eq!(query.resolve("Total"), Some(program));
+        assert!(query.resolve("TOTAL").is_none());
+        let nested = query.add_child();
+        nested.set_case_insensitive(false);
+        nested.add_named("x").unwrap();
+        nested.add_named("X").unwrap();
+        assert!(query.is_case_insensitive());
+        assert!(!root.is_case_insensitive());
+    }
+
+    #[test]
+    fn output_conflicts_ignore_case() {
+        let root = RootScope::new_root();
+        root.add_named("a").unwrap();
+        root.add_named("A").unwrap();
+        assert!(root.root().check_injective(|_| None).is_empty());
+        let block = root.add_child();
+        block.set_case_insensitive(true);
+        block.add_named("b").unwrap();
+        let c = block.add_named("c").unwrap();
+        let conflicts = root.root().check_injective(|name| {
+            if *name == c {
+                Some("B".to_owned())
+            } else {
+                None
+            }
+        });
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].scope, block);
+        assert_eq!(conflicts[0].output, "b");
+    }
+}
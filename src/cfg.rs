@@ -0,0 +1,178 @@
+/// Conditional names and scopes, which only exist when a configuration flag is active, like
+/// `#[cfg(feature = "x")]` in Rust or `#ifdef X` in C.
+///
+/// The tree contains everything; a `ConfigView` for a set of active flags then ignores the
+/// names and scopes whose flag is not active. Inside inactive scopes, nothing is active.
+/// Inactive names get no outputs from `ConfigView::generate_names`, so they leave their
+/// outputs free for active names.
+use ::std::collections::{HashMap, HashSet};
+
+use ::ustr::Ustr;
+
+use crate::generate::{GenerateError, NameMap, OutputPolicy};
+use crate::name::{GivenName, Name};
+use crate::scope::{RootScope, Scope};
+use crate::text::NameText;
+
+/// The tree as seen with a specific set of active configuration flags.
+#[derive(Debug, Clone)]
+pub struct ConfigView {
+    root: RootScope,
+    active: HashSet<Ustr>,
+}
+
+impl Name {
+    /// Only let this name exist when the flag is active.
+    pub fn set_condition(&self, flag: &str) {
+        self.root.root_data.names.borrow_mut()[self.index].condition = Some(Ustr::from(flag));
+    }
+
+    pub fn condition(&self) -> Option<Ustr> {
+        self.root.root_data.names.borrow()[self.index].condition
+    }
+}
+
+impl Scope {
+    /// Only let this scope, and everything in it, exist when the flag is active.
+    pub fn set_condition(&self, flag: &str) {
+        self.root
+            .scope_data_at(self.index, |data| data.condition = Some(Ustr::from(flag)));
+    }
+
+    pub fn condition(&self) -> Option<Ustr> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.condition)
+    }
+}
+
+impl RootScope {
+    /// A view of the tree in which only unconditional items, and those whose flag is one of
+    /// the given ones, exist.
+    pub fn view(&self, active: &[&str]) -> ConfigView {
+        ConfigView {
+            root: self.clone(),
+            active: active.iter().map(|flag| Ustr::from(flag)).collect(),
+        }
+    }
+}
+
+impl ConfigView {
+    fn is_flag_active(&self, condition: Option<Ustr>) -> bool {
+        condition.is_none_or(|flag| self.active.contains(&flag))
+    }
+
+    /// Whether the scope and all its ancestors are active.
+    pub fn is_scope_active(&self, scope: &Scope) -> bool {
+        assert!(scope.root == self.root, "scope is not part of this tree");
+        let mut current = Some(scope.clone());
+        while let Some(scope) = current {
+            if !self.is_flag_active(scope.condition()) {
+                return false;
+            }
+            current = scope.parent();
+        }
+        true
+    }
+
+    /// Whether the name and its scope are active.
+    pub fn is_active(&self, name: &Name) -> bool {
+        self.is_flag_active(name.condition()) && self.is_scope_active(&name.scope())
+    }
+
+    /// The active names registered directly in the scope, in registration order.
+    pub fn names(&self, scope: &Scope) -> Vec<Name> {
+        if !self.is_scope_active(scope) {
+            return vec![];
+        }
+        scope
+            .names()
+            .into_iter()
+            .filter(|name| self.is_flag_active(name.condition()))
+            .collect()
+    }
+
+    /// Like `Scope::resolve`, but skipping names that are not active. Returns None if the
+    /// scope itself is not active.
+    pub fn resolve(&self, scope: &Scope, name: &str) -> Option<Name> {
+        if !self.is_scope_active(scope) {
+            return None;
+        }
+        let given_name = self.root.given_name(name);
+        let mut current = Some(scope.clone());
+        while let Some(scope) = current {
+            let found = scope
+                .find_visible_given(&given_name)
+                .filter(|name| self.is_active(name));
+            if found.is_some() {
+                return found;
+            }
+            current = scope.parent();
+        }
+        None
+    }
+
+    /// Like `RootScope::generate_names`, but only for the active names; inactive names have
+    /// no output, also for `Name::output`, and do not keep other names from any output.
+    pub fn generate_names(&self, policy: &OutputPolicy) -> Result<NameMap, GenerateError> {
+        let map = self
+            .root
+            .generate_with_seeds(policy, HashMap::new(), Some(self.clone()))?;
+        self.root.cache_outputs(&map);
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn conditional_resolution() {
+        let root = RootScope::new_root();
+        let always = root.add_named("x").unwrap();
+        let module = root.add_child();
+        let debug_x = module.add_named("x").unwrap();
+        debug_x.set_condition("debug");
+        let gated = module.add_child();
+        gated.set_condition("unix");
+        let inner = gated.add_named("y").unwrap();
+        let release = root.root().view(&[]);
+        assert_eq!(release.resolve(&module, "x"), Some(always.clone()));
+        assert!(release.names(&module).is_empty());
+        assert!(!release.is_scope_active(&gated));
+        assert_eq!(release.resolve(&gated, "y"), None);
+        let debug = root.root().view(&["debug", "unix"]);
+        assert_eq!(debug.resolve(&gated, "x"), Some(debug_x.clone()));
+        assert_eq!(debug.resolve(&gated, "y"), Some(inner.clone()));
+        assert!(debug.is_active(&inner));
+        assert_eq!(debug_x.condition().unwrap().as_str(), "debug");
+    }
+
+    #[test]
+    fn generate_active_names() {
+        let root = RootScope::new_root();
+        let debug_x = root.add_named("x").unwrap();
+        debug_x.set_condition("debug");
+        let gated = root.add_child();
+        gated.set_condition("unix");
+        let inner = gated.add_named("y").unwrap();
+        let child = root.add_child();
+        let x = child.add_named("x").unwrap();
+        let map = root
+            .root()
+            .view(&[])
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&x).unwrap().as_str(), "x");
+        assert!(map.get(&debug_x).is_none());
+        assert!(map.get(&inner).is_none());
+        let map = root
+            .root()
+            .view(&["debug"])
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&debug_x).unwrap().as_str(), "x");
+        assert_eq!(map.get(&x).unwrap().as_str(), "x2");
+    }
+}
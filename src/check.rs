@@ -0,0 +1,214 @@
+/// Checks for rename tables, e.g. ones that are supplied externally or edited by hand,
+/// before they are applied to the code.
+///
+/// A table is injective if, in every scope, no two distinct names that are visible there
+/// get the same output. Visible means found by `Scope::resolve`, so this includes names
+/// imported into the scope or an ancestor, and the names of their mixins. Names that are
+/// shadowed are not visible, so they may share the output of the name that shadows them.
+/// Names with the same input text in one scope (like overloads) were already allowed to
+/// coexist, so they may keep sharing an output. Names in different namespaces never
+/// conflict. In case-insensitive scopes, outputs are compared in lowercase, which is also
+/// how they are reported.
+use ::std::collections::HashMap;
+
+use crate::name::{InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+/// Two names that are visible in the same scope, but have the same output.
+#[derive(Debug, Clone)]
+pub struct OutputConflict {
+    /// The scope where both names are visible.
+    pub scope: Scope,
+    pub first: Name,
+    pub second: Name,
+    pub output: String,
+}
+
+impl RootScope {
+    /// Check that the outputs are distinct for names visible in the same scope.
+    ///
+    /// The callback gives the output of a name, or None to keep it unchanged, in which case
+    /// given names keep their text, and anonymous names are not checked. Each conflict is
+    /// reported once, at the outermost scope where it occurs.
+    pub fn check_injective(&self, output: impl Fn(&Name) -> Option<String>) -> Vec<OutputConflict> {
+        let mut conflicts = vec![];
+        let mut pending = vec![self.root_scope()];
+        while let Some(scope) = pending.pop() {
+            check_scope(&scope, &output, &mut conflicts);
+            let mut children: Vec<Scope> = scope.children().collect();
+            children.reverse();
+            pending.extend(children);
+        }
+        conflicts
+    }
+}
+
+fn check_scope(
+    scope: &Scope,
+    output: &impl Fn(&Name) -> Option<String>,
+    conflicts: &mut Vec<OutputConflict>,
+) {
+    // Names visible from this scope, own names first; shadowed outer names are left out.
+    let mut visible = scope.visible_names();
+    let own = visible.len();
+    let mut current = scope.parent();
+    while let Some(ancestor) = current {
+        let mut shadowed_texts: Vec<(Option<&str>, &str)> = visible
+            .iter()
+            .filter_map(|name| Some((namespace(name), given_text(name)?)))
+            .collect();
+        shadowed_texts.sort_unstable();
+        let outer: Vec<Name> = ancestor
+            .visible_names()
+            .into_iter()
+            .filter(|name| {
+                given_text(name).is_none_or(|text| {
+                    shadowed_texts
+                        .binary_search(&(namespace(name), text))
+                        .is_err()
+                })
+            })
+            .collect();
+        visible.extend(outer);
+        current = ancestor.parent();
+    }
+    let is_case_insensitive = scope.is_case_insensitive();
+    let mut by_output: HashMap<(Option<&str>, String), Vec<usize>> = HashMap::new();
+    for (position, name) in visible.iter().enumerate() {
+        let name_output = match (output(name), given_text(name)) {
+            (Some(name_output), _) => name_output,
+            (None, Some(text)) => text.to_owned(),
+            (None, None) => continue,
+        };
+        let name_output = if is_case_insensitive {
+            name_output.to_lowercase()
+        } else {
+            name_output
+        };
+        by_output
+            .entry((namespace(name), name_output))
+            .or_default()
+            .push(position);
+    }
+    let mut outputs: Vec<_> = by_output.into_iter().collect();
+    outputs.sort_by(|first, second| first.0.cmp(&second.0));
+    for ((_, name_output), positions) in outputs {
+        for (nr, &first_position) in positions.iter().enumerate() {
+            for &second_position in &positions[nr + 1..] {
+                let (first, second) = (&visible[first_position], &visible[second_position]);
+                let is_own = first_position < own || second_position < own;
+                let is_same_input = first.scope() == second.scope()
+                    && given_text(first).is_some()
+                    && given_text(first) == given_text(second);
+                // Pairs that are both outer were already reported in an ancestor.
+                if is_own && !is_same_input {
+                    conflicts.push(OutputConflict {
+                        scope: scope.clone(),
+                        first: (*first).clone(),
+                        second: (*second).clone(),
+                        output: name_output.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Names in different namespaces never conflict.
+fn namespace(name: &Name) -> Option<&str> {
+    match &name.data {
+        InputName::Given(given) => given.namespace(),
+        InputName::Anonymous(_) => None,
+    }
+}
+
+fn given_text(name: &Name) -> Option<&str> {
+    match &name.data {
+        InputName::Given(given) => Some(given.as_str()),
+        InputName::Anonymous(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::collections::HashMap;
+
+    use crate::generate::OutputPolicy;
+    use crate::import::ImportConflict;
+    use crate::name::NameId;
+    use crate::overload::OverloadKey;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn injective_table() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let child = root.add_child();
+        let shadow = child.add_named("x").unwrap();
+        let y = child.add_named("y").unwrap();
+        root.add_overload("f", OverloadKey(1)).unwrap();
+        root.add_overload("f", OverloadKey(2)).unwrap();
+        let mut table: HashMap<NameId, String> = HashMap::new();
+        table.insert(x.id(), "a".to_owned());
+        table.insert(shadow.id(), "a".to_owned());
+        table.insert(y.id(), "b".to_owned());
+        let output = |name: &crate::Name| table.get(&name.id()).cloned();
+        assert!(root.root().check_injective(output).is_empty());
+    }
+
+    #[test]
+    fn conflicting_table() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let child = root.add_child();
+        let tmp = child.add_prefixed("tmp");
+        let sibling = root.add_child();
+        sibling.add_prefixed("tmp");
+        let mut table: HashMap<NameId, String> = HashMap::new();
+        // Conflicts with the visible outer `x`, but the sibling scope is fine.
+        table.insert(tmp.id(), "x".to_owned());
+        let conflicts = root
+            .root()
+            .check_injective(|name| table.get(&name.id()).cloned());
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].scope, child);
+        assert_eq!(conflicts[0].output, "x");
+        assert_eq!(conflicts[0].first, tmp);
+        assert_eq!(conflicts[0].second, x);
+    }
+
+    #[test]
+    fn imports_and_mixins_are_visible() {
+        let root = RootScope::new_root();
+        let module = root.add_child();
+        let f = module.add_named("f").unwrap();
+        let user = root.add_child();
+        user.import_all(&module, ImportConflict::Error).unwrap();
+        let tmp = user.add_child().add_prefixed("tmp");
+        let mixin = root.add_child();
+        let g = mixin.add_named("g").unwrap();
+        let class = root.add_child();
+        class.add_mixin(&mixin).unwrap();
+        let own_g = class.add_named("own_g").unwrap();
+        let mut table: HashMap<NameId, String> = HashMap::new();
+        table.insert(tmp.id(), "f".to_owned());
+        table.insert(own_g.id(), "g".to_owned());
+        let conflicts = root
+            .root()
+            .check_injective(|name| table.get(&name.id()).cloned());
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].scope, tmp.scope());
+        assert_eq!((&conflicts[0].first, &conflicts[0].second), (&tmp, &f));
+        assert_eq!(conflicts[1].scope, class);
+        assert_eq!((&conflicts[1].first, &conflicts[1].second), (&own_g, &g));
+        // Generated outputs take the same names into account.
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert!(root
+            .root()
+            .check_injective(|name| map.get(name).map(|output| output.to_string()))
+            .is_empty());
+    }
+}
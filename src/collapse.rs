@@ -0,0 +1,111 @@
+/// Collapsing empty wrapper scopes, which desugaring tends to introduce in large numbers,
+/// e.g. a block around a single loop.
+///
+/// A scope is collapsed if it has exactly one child, and nothing that would make a
+/// difference to the names in its region: no names, imports, reservations, mixins,
+/// friends, condition, case setting, anonymous prefix, label or recorded references. Its
+/// child takes its place among the siblings, and the collapsed scope is detached. Sealed
+/// scopes, and scopes whose parent is sealed, are kept.
+use crate::scope::{RootScope, Scope};
+
+impl RootScope {
+    /// Collapse all chains of empty single-child scopes into their parents. Returns the
+    /// number of scopes that were removed from the tree.
+    pub fn collapse_empty_scopes(&self) -> usize {
+        let _exclusive = self.root_data.exclusive.lock();
+        let referenced = self.referencing_scopes();
+        let mut collapsed = 0;
+        let mut pending: Vec<usize> = self.child_indices(0);
+        while let Some(index) = pending.pop() {
+            let children = self.child_indices(index);
+            let parent = self.read_scope_data_at(index, |data| data.parent).unwrap();
+            let is_collapsible = children.len() == 1
+                && !referenced.contains(&index)
+                && self.is_empty_wrapper(index)
+                && !self.read_scope_data_at(parent, |data| data.sealed);
+            if !is_collapsible {
+                pending.extend(children);
+                continue;
+            }
+            let child = children[0];
+            // During this method, the state is not consistent.
+            // Step 1: detach the child from the wrapper.
+            self.unlink_child(index, child);
+            // Step 2: put the child in the place of the wrapper.
+            self.link_child(parent, child, Some(index));
+            self.scope_data_at(child, |data| data.parent = Some(parent));
+            // Step 3: detach the wrapper.
+            self.unlink_child(parent, index);
+            self.scope_data_at(index, |data| data.parent = None);
+            self.mark_dirty(parent);
+            self.mark_dirty(index);
+            collapsed += 1;
+            // The child may be a wrapper itself.
+            pending.push(child);
+        }
+        if collapsed > 0 {
+            self.shape_changed();
+        }
+        collapsed
+    }
+
+    fn is_empty_wrapper(&self, index: usize) -> bool {
+        let has_no_names = self.read_scope_names_at(index, |names| {
+            names.given_names.is_empty()
+                && names.anon_names.is_empty()
+                && names.undefined.is_empty()
+                && names.imports.is_empty()
+                && names.reserved.is_empty()
+        });
+        has_no_names
+            && self.read_scope_data_at(index, |data| {
+                !data.sealed
+                    && data.label.is_none()
+                    && data.mixins.is_empty()
+                    && data.grants.is_empty()
+                    && data.condition.is_none()
+                    && data.case_insensitive.is_none()
+                    && data.anonymous_prefix.is_none()
+            })
+    }
+}
+
+impl Scope {
+    /// Whether this scope has no names or other settings of its own, so that it could be
+    /// collapsed into its parent if it has a single child, see `collapse_empty_scopes`.
+    pub fn is_empty_wrapper(&self) -> bool {
+        self.root.is_empty_wrapper(self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn collapse_wrapper_chains() {
+        let root = RootScope::new_root();
+        let before = root.add_child();
+        let outer = root.add_child();
+        let inner = outer.add_child();
+        let body = inner.add_child();
+        let x = body.add_named("x").unwrap();
+        body.add_child();
+        body.add_child();
+        let after = root.add_child();
+        after.add_named("y").unwrap();
+        let labelled = after.add_child();
+        labelled.set_label("f");
+        labelled.add_child();
+        assert_eq!(root.root().collapse_empty_scopes(), 2);
+        let children: Vec<_> = root.children().collect();
+        assert_eq!(children, vec![before, body.clone(), after]);
+        assert_eq!(body.parent(), Some(root.clone()));
+        assert_eq!(outer.parent(), None);
+        assert_eq!(inner.parent(), None);
+        assert_eq!(body.children().count(), 2);
+        assert!(!labelled.is_empty_wrapper());
+        assert_eq!(labelled.children().count(), 1);
+        assert_eq!(body.resolve("x"), Some(x));
+    }
+}
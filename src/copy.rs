@@ -0,0 +1,296 @@
+/// Copying subtrees, e.g. to specialize a generic function, with the given names renamed
+/// on the way, like appending a suffix for the specialization.
+///
+/// The copy gets the kind, labels, span, case setting, condition, target and anonymous
+/// prefix of the original scopes, and its names keep their overload key, arity, version,
+/// namespace, visibility, deprecation and condition. Imports, mixins, friends and emit edges
+/// point outside the copied scope's identity, so they are not copied.
+use ::std::collections::HashSet;
+
+use crate::limit::LimitExceeded;
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::Scope;
+use crate::spec::DuplicateName;
+use crate::text::NameText;
+
+/// Why a subtree could not be copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyError {
+    /// Two names of a copied scope get the same spelling.
+    DuplicateName(DuplicateName),
+    /// The copy would exceed a limit of the target tree, see `RootScope::set_limits`.
+    LimitExceeded(LimitExceeded),
+}
+
+impl From<LimitExceeded> for CopyError {
+    fn from(exceeded: LimitExceeded) -> Self {
+        CopyError::LimitExceeded(exceeded)
+    }
+}
+
+impl Scope {
+    /// Copy this scope and its descendants into a new child of the target, which may be in
+    /// another tree. Given names are spelled as the callback returns; anonymous names keep
+    /// their prefix.
+    ///
+    /// Fails, without changing anything, if two names of a copied scope get the same
+    /// spelling (or an overloaded and an ordinary name do, or, in case-insensitive scopes,
+    /// two spellings that differ only in case), or if the copy would exceed a limit of the
+    /// target tree. Panics if the target is sealed.
+    pub fn clone_subtree_with(
+        &self,
+        target: &Scope,
+        mut rename: impl FnMut(&Name) -> String,
+    ) -> Result<Scope, CopyError> {
+        // Only the target tree changes, so only it is locked.
+        let _exclusive = target.root.root_data.exclusive.lock();
+        // Rename and check everything first, so that nothing is created on failure.
+        target.root.assert_unsealed(target.index);
+        let mut sources = vec![self.clone()];
+        sources.extend(self.descendants());
+        check_copy_limits(&sources, target)?;
+        // Copies take the case setting of their source, or else inherit it from where
+        // they end up.
+        let mut case_insensitive: Vec<bool> = vec![];
+        for source in &sources {
+            let inherited = match source.parent() {
+                Some(parent) if *source != *self => {
+                    case_insensitive[sources.iter().position(|other| *other == parent).unwrap()]
+                }
+                _ => target.is_case_insensitive(),
+            };
+            let setting = source
+                .root
+                .read_scope_data_at(source.index, |data| data.case_insensitive);
+            case_insensitive.push(setting.unwrap_or(inherited));
+        }
+        let mut renamed: Vec<Vec<(Name, Option<GivenName>)>> = vec![];
+        for (source, &ignore_case) in sources.iter().zip(&case_insensitive) {
+            let mut seen: HashSet<GivenName> = HashSet::new();
+            let mut overloaded: HashSet<NameText> = HashSet::new();
+            let mut names = vec![];
+            for name in source.names() {
+                let given = match &name.data {
+                    InputName::Given(given) => given,
+                    InputName::Anonymous(_) => {
+                        names.push((name, None));
+                        continue;
+                    }
+                };
+                let text = rename(&name);
+                let new_given = GivenName {
+                    name: NameText::from(target.root.canonical(&text).as_ref()),
+                    ..given.clone()
+                };
+                // Compare the spellings as the copied scope would.
+                let key = if ignore_case {
+                    GivenName {
+                        name: NameText::from(new_given.as_str().to_lowercase().as_str()),
+                        ..new_given.clone()
+                    }
+                } else {
+                    new_given.clone()
+                };
+                let ordinary = GivenName::new(key.name.clone());
+                let is_duplicate = seen.contains(&key)
+                    || (key.overload.is_some() && seen.contains(&ordinary))
+                    || (key == ordinary && overloaded.contains(&key.name));
+                if is_duplicate {
+                    return Err(CopyError::DuplicateName(DuplicateName(text)));
+                }
+                if key.overload.is_some() {
+                    overloaded.insert(key.name.clone());
+                }
+                seen.insert(key);
+                names.push((name, Some(new_given)));
+            }
+            renamed.push(names);
+        }
+        // Create the copies, parents before children.
+        let mut copies: Vec<Scope> = vec![];
+        for (source, names) in sources.iter().zip(renamed) {
+            let parent = match source.parent() {
+                Some(parent) if *source != *self => {
+                    let position = sources.iter().position(|other| *other == parent).unwrap();
+                    copies[position].clone()
+                }
+                _ => target.clone(),
+            };
+            let copy = parent.add_child();
+            copy_scope_settings(source, &copy);
+            for (name, new_given) in names {
+                let copied = match (new_given, &name.data) {
+                    (Some(new_given), _) => copy.register_any(new_given),
+                    (None, InputName::Anonymous(anon)) => copy.add_prefixed(anon.name.as_str()),
+                    (None, InputName::Given(_)) => unreachable!(),
+                };
+                copy_name_metadata(&name, &copied);
+            }
+            copies.push(copy);
+        }
+        Ok(copies.swap_remove(0))
+    }
+}
+
+/// Check that copying the sources (a scope and its descendants) into the target stays
+/// within the limits of the target tree.
+fn check_copy_limits(sources: &[Scope], target: &Scope) -> Result<(), LimitExceeded> {
+    let limits = target.root.limits();
+    if let Some(max_scopes) = limits.max_scopes {
+        if target.root.root_data.scopes.borrow().len() + sources.len() > max_scopes {
+            return Err(LimitExceeded::Scopes(max_scopes));
+        }
+    }
+    if let Some(max_depth) = limits.max_depth {
+        let top_depth = sources[0].depth();
+        let deepest = sources
+            .iter()
+            .map(|source| source.depth() - top_depth)
+            .max();
+        if target.depth() + 1 + deepest.unwrap_or(0) > max_depth {
+            return Err(LimitExceeded::Depth(max_depth));
+        }
+    }
+    if let Some(max_names) = limits.max_names_per_scope {
+        if sources
+            .iter()
+            .any(|source| source.names().len() > max_names)
+        {
+            return Err(LimitExceeded::NamesPerScope(max_names));
+        }
+    }
+    Ok(())
+}
+
+/// Give the copy the visibility, deprecation and condition of the original name.
+pub(crate) fn copy_name_metadata(original: &Name, copy: &Name) {
+    let (visibility, deprecated, condition) = {
+        let data = &original.root.root_data.names.borrow()[original.index];
+        (data.visibility, data.deprecated, data.condition)
+    };
+    let mut names = copy.root.root_data.names.borrow_mut();
+    names[copy.index].visibility = visibility;
+    names[copy.index].deprecated = deprecated;
+    names[copy.index].condition = condition;
+}
+
+fn copy_scope_settings(source: &Scope, copy: &Scope) {
+    copy.set_kind(source.kind());
+    if let Some(label) = source.label() {
+        copy.set_label(label.as_str());
+    }
+    if let Some(debug_label) = source.debug_label() {
+        copy.set_debug_label(debug_label.as_str());
+    }
+    if let Some(span) = source.span() {
+        copy.set_span(span);
+    }
+    let (case_insensitive, condition, target, anonymous_prefix) =
+        source.root.read_scope_data_at(source.index, |data| {
+            (
+                data.case_insensitive,
+                data.condition,
+                data.target,
+                data.anonymous_prefix,
+            )
+        });
+    if let Some(case_insensitive) = case_insensitive {
+        copy.set_case_insensitive(case_insensitive);
+    }
+    if let Some(condition) = condition {
+        copy.set_condition(condition.as_str());
+    }
+    if let Some(target) = target {
+        copy.set_target(target.as_str());
+    }
+    if let Some(anonymous_prefix) = anonymous_prefix {
+        copy.set_anonymous_prefix(anonymous_prefix.as_str());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::copy::CopyError;
+    use crate::kind::ScopeKind;
+    use crate::limit::{LimitExceeded, Limits};
+    use crate::overload::OverloadKey;
+    use crate::scope::RootScope;
+    use crate::spec::DuplicateName;
+
+    #[test]
+    fn copy_with_suffix() {
+        let root = RootScope::new_root();
+        let generic = root.add_child_with(ScopeKind::Function, "map");
+        generic.add_named("item").unwrap();
+        generic.add_prefixed("tmp");
+        let body = generic.add_child();
+        body.add_overload("f", OverloadKey(1)).unwrap();
+        body.add_overload("f", OverloadKey(2)).unwrap();
+        let specialized = generic
+            .clone_subtree_with(&root, |name| format!("{}_i32", name.local_text()))
+            .unwrap();
+        assert_eq!(specialized.parent(), Some(root.clone()));
+        assert_eq!(specialized.kind(), ScopeKind::Function);
+        assert_eq!(specialized.label().unwrap().as_str(), "map");
+        assert!(specialized.resolve("item_i32").is_some());
+        assert!(specialized.resolve("item").is_none());
+        assert_eq!(specialized.anon_names_with_prefix("tmp").len(), 1);
+        let copied_body = specialized.children().next().unwrap();
+        assert_eq!(copied_body.overloads("f_i32").len(), 2);
+        assert_eq!(generic.children().count(), 1);
+    }
+
+    #[test]
+    fn copy_with_collision() {
+        let root = RootScope::new_root();
+        let source = root.add_child();
+        source.add_named("a").unwrap();
+        source.add_named("b").unwrap();
+        let target = RootScope::new_root();
+        assert_eq!(
+            source.clone_subtree_with(&target, |_| "same".to_owned()),
+            Err(CopyError::DuplicateName(DuplicateName("same".to_owned())))
+        );
+        assert_eq!(target.children().count(), 0);
+        target.root().set_limits(Limits {
+            max_names_per_scope: Some(1),
+            ..Limits::default()
+        });
+        assert_eq!(
+            source.clone_subtree_with(&target, |name| name.local_text().into_owned()),
+            Err(CopyError::LimitExceeded(LimitExceeded::NamesPerScope(1)))
+        );
+        assert_eq!(target.children().count(), 0);
+        target.root().set_limits(Limits {
+            max_depth: Some(1),
+            ..Limits::default()
+        });
+        source.add_child();
+        assert_eq!(
+            source.clone_subtree_with(&target, |name| name.local_text().into_owned()),
+            Err(CopyError::LimitExceeded(LimitExceeded::Depth(1)))
+        );
+        assert_eq!(target.children().count(), 0);
+    }
+
+    #[test]
+    fn copy_into_case_insensitive_scope() {
+        let root = RootScope::new_root();
+        let source = root.add_child();
+        source.add_named("x").unwrap();
+        source.add_named("y").unwrap();
+        let target = RootScope::new_root();
+        target.set_case_insensitive(true);
+        assert_eq!(
+            source.clone_subtree_with(&target, |name| {
+                if name.local_text() == "x" {
+                    "X".to_owned()
+                } else {
+                    "x".to_owned()
+                }
+            }),
+            Err(CopyError::DuplicateName(DuplicateName("x".to_owned())))
+        );
+        assert_eq!(target.children().count(), 0);
+    }
+}
@@ -0,0 +1,41 @@
+/// Free-form labels on scopes for debug output, e.g. to trace scopes created by desugaring
+/// passes back to the construct they came from.
+///
+/// Unlike `Scope::set_label`, these are not part of paths, and are not used for lookups.
+/// They appear in `Debug` output and in the tree printed by the `scoped-name` tool.
+use ::ustr::Ustr;
+
+use crate::scope::Scope;
+
+impl Scope {
+    pub fn set_debug_label(&self, debug_label: &str) {
+        self.root.scope_data_at(self.index, |data| {
+            data.debug_label = Some(Ustr::from(debug_label))
+        });
+    }
+
+    pub fn debug_label(&self) -> Option<Ustr> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.debug_label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn debug_label_not_in_path() {
+        let root = RootScope::new_root();
+        let desugared = root.add_child();
+        desugared.set_debug_label("for-loop at line 3");
+        let x = desugared.add_named("x").unwrap();
+        assert_eq!(
+            desugared.debug_label().unwrap().as_str(),
+            "for-loop at line 3"
+        );
+        assert_eq!(x.to_string(), "{0}::x");
+        assert!(format!("{:?}", desugared).contains("for-loop at line 3"));
+        assert_eq!(root.debug_label(), None);
+    }
+}
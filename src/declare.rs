@@ -0,0 +1,126 @@
+/// Forward declarations: a given name can be declared any number of times, but defined
+/// only once, like functions in C-like languages.
+///
+/// A declared name occupies its slot in the scope, so `add_named` with the same text
+/// fails, whether or not the declaration has been defined yet.
+use ::std::collections::HashSet;
+
+use ::ustr::Ustr;
+
+use crate::limit::RegisterError;
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+/// A declared name, that can be defined once.
+#[derive(Debug, Clone)]
+pub struct PendingName {
+    name: Name,
+}
+
+#[derive(Debug)]
+pub struct AlreadyDefined();
+
+impl Scope {
+    /// Declare a given name in this scope. Repeated declarations of the same name are
+    /// allowed, also after it has been defined.
+    ///
+    /// This only returns a name to define. If the name was already registered without
+    /// declaring it, it is considered defined. Fails like `add_named` if the name conflicts
+    /// with another one, e.g. because it has overloads here.
+    pub fn declare(&self, name: &str) -> Result<PendingName, RegisterError> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        let given_name = self.root.given_name(name);
+        if let Some(name) = self.find_given(&given_name) {
+            return Ok(PendingName { name });
+        }
+        let name = self.add_given(given_name)?;
+        self.root.scope_names_at(self.index, |data| {
+            data.undefined.insert(name.clone().unwrap_given())
+        });
+        Ok(PendingName { name })
+    }
+
+    /// The names declared in this scope that have not been defined yet, in no particular order.
+    pub fn undefined_names(&self) -> Vec<GivenName> {
+        self.root
+            .read_scope_names_at(self.index, |data| data.undefined.iter().cloned().collect())
+    }
+}
+
+impl PendingName {
+    /// Define the declared name, failing if it was already defined.
+    pub fn define(&self) -> Result<Name, AlreadyDefined> {
+        let was_undefined = self.with_undefined(|undefined, given| undefined.remove(given));
+        if !was_undefined {
+            return Err(AlreadyDefined());
+        }
+        Ok(self.name())
+    }
+
+    pub fn is_defined(&self) -> bool {
+        !self.with_undefined(|undefined, given| undefined.contains(given))
+    }
+
+    /// The declared name, whether or not it has been defined.
+    pub fn name(&self) -> Name {
+        self.name.clone()
+    }
+
+    // Access the undefined names of the scope the name is currently registered in.
+    fn with_undefined<T>(&self, f: impl FnOnce(&mut HashSet<GivenName>, &GivenName) -> T) -> T {
+        let given = match &self.name.data {
+            InputName::Given(given) => given,
+            InputName::Anonymous(_) => unreachable!("declared names are given names"),
+        };
+        let scope = self.name.scope();
+        scope
+            .root
+            .scope_names_at(scope.index, |data| f(&mut data.undefined, given))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canonical::Canonicalization;
+    use crate::overload::OverloadKey;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn declare_then_define() {
+        let root = RootScope::new_root();
+        let first = root.declare("f").unwrap();
+        let second = root.declare("f").unwrap();
+        assert!(!first.is_defined());
+        assert_eq!(root.undefined_names().len(), 1);
+        let name = second.define().unwrap();
+        assert!(first.is_defined());
+        first.define().unwrap_err();
+        assert_eq!(root.declare("f").unwrap().name(), name);
+        assert!(root.undefined_names().is_empty());
+    }
+
+    #[test]
+    fn declared_name_is_registered() {
+        let root = RootScope::new_root();
+        root.declare("f").unwrap();
+        root.add_named("f").unwrap_err();
+        root.add_named("g").unwrap();
+        root.declare("g").unwrap().define().unwrap_err();
+    }
+
+    #[test]
+    fn declare_checks_conflicts() {
+        let root = RootScope::new_root();
+        root.root().set_canonicalization(Canonicalization {
+            trim: true,
+            ..Canonicalization::default()
+        });
+        root.add_overload("f", OverloadKey(1)).unwrap();
+        assert!(root.declare("f").is_err());
+        assert_eq!(root.names().len(), 1);
+        let pending = root.declare(" g ").unwrap();
+        assert_eq!(root.declare("g").unwrap().name(), pending.name());
+        assert_eq!(root.resolve("g"), Some(pending.name()));
+    }
+}
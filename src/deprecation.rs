@@ -0,0 +1,44 @@
+/// Deprecated names, which still resolve, but whose uses are recorded as diagnostics.
+use ::ustr::Ustr;
+
+use crate::name::Name;
+
+impl Name {
+    /// Mark this name as deprecated, with a message explaining e.g. what to use instead.
+    pub fn deprecate(&self, message: &str) {
+        self.root.root_data.names.borrow_mut()[self.index].deprecated = Some(Ustr::from(message));
+    }
+
+    /// The deprecation message, or None if the name is not deprecated.
+    pub fn deprecation(&self) -> Option<Ustr> {
+        self.root.root_data.names.borrow()[self.index].deprecated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::Diagnostic;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn warn_on_deprecated_use() {
+        let root = RootScope::new_root();
+        root.set_span(0..50);
+        let old = root.add_named("old").unwrap();
+        root.add_named("new").unwrap();
+        old.deprecate("use `new` instead");
+        let child = root.add_child();
+        assert_eq!(child.resolve("new").unwrap().deprecation(), None);
+        assert_eq!(child.resolve("old"), Some(old.clone()));
+        assert_eq!(
+            root.root().take_diagnostics(),
+            vec![Diagnostic::DeprecatedUse {
+                name: old,
+                message: "use `new` instead".into(),
+                used_in: child,
+                declared_at: Some(0..50),
+            }]
+        );
+        assert!(root.root().diagnostics().is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+/// Derived names: a record that one name was generated from another, like a getter from a
+/// field or a backing field from a property, with a free-form relation like "getter".
+///
+/// Output generation uses the link to keep derived names textually related to their
+/// origin: a getter `get_foo` of a field `foo` whose output became `foo2` becomes `get_foo2`.
+use ::ustr::Ustr;
+
+use crate::name::Name;
+
+impl Name {
+    /// Record that this name was generated from the origin, replacing any earlier origin.
+    ///
+    /// Panics if the origin is this name, or is in another tree.
+    pub fn derive_from(&self, origin: &Name, relation: &str) {
+        assert!(
+            self.root == origin.root,
+            "cannot derive from a name of another tree"
+        );
+        assert!(self != origin, "cannot derive a name from itself");
+        self.root.root_data.names.borrow_mut()[self.index].derived_from =
+            Some((origin.index, Ustr::from(relation)));
+    }
+
+    /// The name this one was derived from, and the relation, if recorded.
+    pub fn origin(&self) -> Option<(Name, Ustr)> {
+        let derived_from = self.root.root_data.names.borrow()[self.index].derived_from;
+        derived_from.map(|(index, relation)| (self.root.name_at(index), relation))
+    }
+
+    /// The names derived from this one, with their relations, in registration order.
+    pub fn derived_names(&self) -> Vec<(Name, Ustr)> {
+        let derived: Vec<(usize, Ustr)> = self
+            .root
+            .root_data
+            .names
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, data)| match data.derived_from {
+                Some((origin, relation)) if origin == self.index && !data.removed => {
+                    Some((index, relation))
+                }
+                _ => None,
+            })
+            .collect();
+        derived
+            .into_iter()
+            .map(|(index, relation)| (self.root.name_at(index), relation))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn derived_names_follow_origin() {
+        let root = RootScope::new_root();
+        root.add_named("foo").unwrap();
+        let class = root.add_child();
+        let field = class.add_named("foo").unwrap();
+        let getter = class.add_named("get_foo").unwrap();
+        let setter = class.add_named("set_foo").unwrap();
+        getter.derive_from(&field, "getter");
+        setter.derive_from(&field, "setter");
+        assert_eq!(getter.origin().unwrap().0, field);
+        assert_eq!(getter.origin().unwrap().1.as_str(), "getter");
+        assert_eq!(
+            field
+                .derived_names()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            vec![getter.clone(), setter]
+        );
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&field).unwrap().as_str(), "foo2");
+        assert_eq!(map.get(&getter).unwrap().as_str(), "get_foo2");
+    }
+}
@@ -0,0 +1,76 @@
+/// Diagnostics that the crate records while the tree is used, like uses of deprecated names,
+/// so that front-ends can report them with their own formatting.
+///
+/// Diagnostics accumulate on the root until they are taken. They refer to scopes and names
+/// by index internally, since storing handles in the root would keep it alive forever.
+use ::std::ops::Range;
+
+use ::ustr::Ustr;
+
+use crate::name::Name;
+use crate::scope::{RootScope, Scope};
+
+/// Something a front-end may want to warn about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// A deprecated name was resolved.
+    DeprecatedUse {
+        name: Name,
+        message: Ustr,
+        /// The scope where the name was resolved from.
+        used_in: Scope,
+        /// The range of the scope that declares the name, if it was set.
+        declared_at: Option<Range<usize>>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum DiagnosticData {
+    DeprecatedUse { name: usize, used_in: usize },
+}
+
+impl RootScope {
+    /// Record the diagnostics for a name being resolved from a scope, if there are any.
+    pub(crate) fn note_use(&self, name: &Name, used_in: &Scope) {
+        if name.deprecation().is_some() {
+            self.root_data
+                .diagnostics
+                .borrow_mut()
+                .push(DiagnosticData::DeprecatedUse {
+                    name: name.index,
+                    used_in: used_in.index,
+                });
+        }
+    }
+
+    /// The diagnostics recorded so far, in the order they happened.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.root_data
+            .diagnostics
+            .borrow()
+            .iter()
+            .map(|data| self.diagnostic(data))
+            .collect()
+    }
+
+    /// Return the diagnostics recorded so far, and start again without any.
+    pub fn take_diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostics = self.diagnostics();
+        self.root_data.diagnostics.borrow_mut().clear();
+        diagnostics
+    }
+
+    fn diagnostic(&self, data: &DiagnosticData) -> Diagnostic {
+        match *data {
+            DiagnosticData::DeprecatedUse { name, used_in } => {
+                let name = self.name_at(name);
+                Diagnostic::DeprecatedUse {
+                    message: name.deprecation().expect("deprecation was removed"),
+                    used_in: self.scope_at_index(used_in),
+                    declared_at: name.scope().span(),
+                    name,
+                }
+            }
+        }
+    }
+}
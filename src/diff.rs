@@ -0,0 +1,213 @@
+/// Differences between two scope trees, e.g. the same program before and after an edit.
+///
+/// Children of matched scopes are matched by label first; the remaining children are
+/// matched by position, and reported as relabeled if their labels differ. Names within
+/// matched scopes are matched by text (given names) or by position (anonymous names).
+use ::std::collections::HashSet;
+
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+/// The result of `RootScope::diff`. Removed items refer to the old tree, added items to
+/// the new one. Renames contain the old and the new name.
+#[derive(Debug, Default)]
+pub struct TreeDiff {
+    /// Top-most scopes that only exist in the new tree (their subtree is not listed separately).
+    pub added_scopes: Vec<Scope>,
+    /// Top-most scopes that only exist in the old tree (their subtree is not listed separately).
+    pub removed_scopes: Vec<Scope>,
+    /// Matched scopes whose label changed, with the old and the new scope.
+    pub relabeled_scopes: Vec<(Scope, Scope)>,
+    pub added_names: Vec<Name>,
+    pub removed_names: Vec<Name>,
+    /// A name is considered renamed if it is the only removed given name in a scope, and there
+    /// is exactly one added given name. For anonymous names, it means the prefix changed.
+    pub renamed_names: Vec<(Name, Name)>,
+}
+
+impl TreeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_scopes.is_empty()
+            && self.removed_scopes.is_empty()
+            && self.relabeled_scopes.is_empty()
+            && self.added_names.is_empty()
+            && self.removed_names.is_empty()
+            && self.renamed_names.is_empty()
+    }
+}
+
+impl RootScope {
+    /// Report what changed going from this tree to the other one.
+    pub fn diff(&self, other: &RootScope) -> TreeDiff {
+        let mut diff = TreeDiff::default();
+        diff_scope(&self.root_scope(), &other.root_scope(), &mut diff);
+        diff
+    }
+}
+
+fn diff_scope(old: &Scope, new: &Scope, diff: &mut TreeDiff) {
+    diff_given(old, new, diff);
+    diff_anonymous(old, new, diff);
+    let mut old_children: Vec<Scope> = old.children().collect();
+    let mut new_children: Vec<Scope> = new.children().collect();
+    let mut by_label = vec![];
+    old_children.retain(|old_child| {
+        let position = old_child.label().and_then(|label| {
+            new_children
+                .iter()
+                .position(|new_child| new_child.label() == Some(label))
+        });
+        match position {
+            Some(position) => {
+                by_label.push((old_child.clone(), new_children.remove(position)));
+                false
+            }
+            None => true,
+        }
+    });
+    for (old_child, new_child) in &by_label {
+        diff_scope(old_child, new_child, diff);
+    }
+    for (old_child, new_child) in old_children.iter().zip(&new_children) {
+        if old_child.label() != new_child.label() {
+            diff.relabeled_scopes
+                .push((old_child.clone(), new_child.clone()));
+        }
+        diff_scope(old_child, new_child, diff);
+    }
+    diff.removed_scopes
+        .extend(old_children.iter().skip(new_children.len()).cloned());
+    diff.added_scopes
+        .extend(new_children.iter().skip(old_children.len()).cloned());
+}
+
+fn diff_given(old: &Scope, new: &Scope, diff: &mut TreeDiff) {
+    let old_names: HashSet<GivenName> = old.given_names().into_iter().collect();
+    let new_names: HashSet<GivenName> = new.given_names().into_iter().collect();
+    let mut removed: Vec<&GivenName> = old_names.difference(&new_names).collect();
+    let mut added: Vec<&GivenName> = new_names.difference(&old_names).collect();
+    removed.sort_by(|first, second| first.as_str().cmp(second.as_str()));
+    added.sort_by(|first, second| first.as_str().cmp(second.as_str()));
+    if removed.len() == 1 && added.len() == 1 {
+        diff.renamed_names
+            .push((given(old, removed[0]), given(new, added[0])));
+        return;
+    }
+    diff.removed_names
+        .extend(removed.into_iter().map(|name| given(old, name)));
+    diff.added_names
+        .extend(added.into_iter().map(|name| given(new, name)));
+}
+
+fn diff_anonymous(old: &Scope, new: &Scope, diff: &mut TreeDiff) {
+    let old_names = anonymous(old);
+    let new_names = anonymous(new);
+    for (old_name, new_name) in old_names.iter().zip(&new_names) {
+        if prefix(old_name) != prefix(new_name) {
+            diff.renamed_names
+                .push((old_name.clone(), new_name.clone()));
+        }
+    }
+    diff.removed_names
+        .extend(old_names.iter().skip(new_names.len()).cloned());
+    diff.added_names
+        .extend(new_names.iter().skip(old_names.len()).cloned());
+}
+
+fn given(scope: &Scope, name: &GivenName) -> Name {
+    scope
+        .find_given(name)
+        .expect("given name listed but not registered")
+}
+
+fn anonymous(scope: &Scope) -> Vec<Name> {
+    scope
+        .names()
+        .into_iter()
+        .filter(|name| matches!(name.data, InputName::Anonymous(_)))
+        .collect()
+}
+
+fn prefix(name: &Name) -> Option<&str> {
+    match &name.data {
+        InputName::Anonymous(anon) => anon.prefix(),
+        InputName::Given(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_trees::build_tree;
+
+    #[test]
+    fn unchanged() {
+        let old = build_tree();
+        let new = build_tree();
+        assert!(old.root().diff(new.root()).is_empty());
+    }
+
+    #[test]
+    fn added_and_removed() {
+        let old = build_tree();
+        old.add_child();
+        let new = build_tree();
+        new.add_named("extra1").unwrap();
+        new.add_named("extra2").unwrap();
+        new.add_anonymous();
+        let diff = old.root().diff(new.root());
+        assert_eq!(diff.removed_scopes.len(), 1);
+        assert!(diff.added_scopes.is_empty());
+        assert_eq!(diff.added_names.len(), 3);
+        assert_eq!(
+            diff.added_names[0].clone().unwrap_given().as_str(),
+            "extra1"
+        );
+        assert!(diff.removed_names.is_empty());
+        assert!(diff.renamed_names.is_empty());
+    }
+
+    #[test]
+    fn renamed() {
+        let old = build_tree();
+        let new = RootScope::new_root();
+        new.add_named("hello").unwrap();
+        new.add_named("earth").unwrap();
+        new.add_prefixed("temp");
+        let child = new.add_child();
+        child.add_named("bye").unwrap();
+        child.add_anonymous();
+        new.add_child();
+        let diff = old.root().diff(new.root());
+        assert_eq!(diff.renamed_names.len(), 2);
+        let (old_name, new_name) = diff.renamed_names[0].clone();
+        assert_eq!(old_name.unwrap_given().as_str(), "world");
+        assert_eq!(new_name.unwrap_given().as_str(), "earth");
+        assert!(diff.added_names.is_empty());
+        assert!(diff.removed_names.is_empty());
+    }
+
+    #[test]
+    fn match_scopes_by_label() {
+        let old = RootScope::new_root();
+        old.add_child().set_label("first");
+        let second = old.add_child();
+        second.set_label("second");
+        second.add_named("x").unwrap();
+        let renamed = old.add_child();
+        renamed.set_label("old");
+        let new = RootScope::new_root();
+        let moved = new.add_child();
+        moved.set_label("second");
+        moved.add_named("x").unwrap();
+        let relabeled = new.add_child();
+        relabeled.set_label("new");
+        new.add_child().set_label("first");
+        let diff = old.root().diff(new.root());
+        assert_eq!(diff.relabeled_scopes, vec![(renamed, relabeled)]);
+        assert!(diff.added_names.is_empty());
+        assert!(diff.removed_names.is_empty());
+        assert!(diff.added_scopes.is_empty());
+        assert!(diff.removed_scopes.is_empty());
+    }
+}
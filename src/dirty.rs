@@ -0,0 +1,109 @@
+/// Tracking which scopes changed, so that incremental users (e.g. an IDE or a watch mode)
+/// only need to revisit those.
+///
+/// A scope is dirty if names were added to or removed from it, or if its children changed.
+/// The set grows until the user calls `RootScope::mark_clean`, e.g. after the first
+/// `RootScope::generate_names`, after which `NameMap::regenerate_dirty` keeps outputs up to
+/// date.
+use crate::generate::{GenerateError, NameMap};
+use crate::scope::{RootScope, Scope};
+
+impl RootScope {
+    pub(crate) fn mark_dirty(&self, index: usize) {
+        self.root_data.dirty.borrow_mut().insert(index);
+    }
+
+    /// The scopes that changed since the last `mark_clean` (or since creation), in arena order.
+    pub fn dirty_scopes(&self) -> Vec<Scope> {
+        let mut indices: Vec<usize> = self.root_data.dirty.borrow().iter().cloned().collect();
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            .map(|index| self.scope_at_index(index))
+            .collect()
+    }
+
+    /// Forget all changes, typically after the dirty scopes have been processed.
+    pub fn mark_clean(&self) {
+        self.root_data.dirty.borrow_mut().clear();
+    }
+}
+
+impl NameMap {
+    /// Generate new outputs for the names in the subtrees of the scopes that changed, where
+    /// their names are visible, and keep all other outputs, see `NameMap::generate_subtree`.
+    /// The tree is marked clean afterwards.
+    pub fn regenerate_dirty(&mut self) -> Result<(), GenerateError> {
+        let root = self.root.clone();
+        let dirty: Vec<Scope> = root
+            .dirty_scopes()
+            .into_iter()
+            .filter(|scope| scope.preorder_index().is_some())
+            .collect();
+        for scope in &dirty {
+            let is_covered = dirty
+                .iter()
+                .any(|other| other != scope && scope.is_in_subtree_of(other));
+            if !is_covered {
+                self.generate_subtree(scope)?;
+            }
+        }
+        root.mark_clean();
+        Ok(())
+    }
+}
+
+impl Scope {
+    /// Whether this scope changed since the last `RootScope::mark_clean`.
+    pub fn is_dirty(&self) -> bool {
+        self.root.root_data.dirty.borrow().contains(&self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn track_changes() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let grandchild = child.add_child();
+        root.root().mark_clean();
+        assert!(root.root().dirty_scopes().is_empty());
+        let x = grandchild.add_named("x").unwrap();
+        assert_eq!(root.root().dirty_scopes(), vec![grandchild.clone()]);
+        root.root().mark_clean();
+        x.move_to(&child).unwrap();
+        assert_eq!(
+            root.root().dirty_scopes(),
+            vec![child.clone(), grandchild.clone()]
+        );
+        root.root().mark_clean();
+        root.add_child();
+        assert!(root.is_dirty());
+        assert!(!child.is_dirty());
+    }
+
+    #[test]
+    fn regenerate_dirty_subtrees() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let clean = root.add_child();
+        let kept = clean.add_named("kept").unwrap();
+        let changed = root.add_child();
+        let mut map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        root.root().mark_clean();
+        map.override_output(&kept, "custom").unwrap();
+        let added = changed.add_named("x").unwrap();
+        map.regenerate_dirty().unwrap();
+        assert_eq!(map.get(&kept).unwrap().as_str(), "custom");
+        assert_eq!(map.get(&x).unwrap().as_str(), "x");
+        assert_eq!(map.get(&added).unwrap().as_str(), "x2");
+        assert!(root.root().dirty_scopes().is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+/// Dynamic scoping, where a name is looked up in the scopes of the active calls rather than
+/// the enclosing scopes, like special variables in Lisp or `local` in Perl and shell.
+///
+/// The tree still records where names are declared; only the lookup order differs.
+use ::ustr::Ustr;
+
+use crate::name::{GivenName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+impl Scope {
+    /// Find a given name in this scope, or else in the scopes of the dynamic chain, in the
+    /// order given (typically the innermost active call first). Only the names registered
+    /// directly in each scope are considered, not those of its lexical parents.
+    pub fn resolve_dynamic<'a>(
+        &self,
+        name: &str,
+        chain: impl IntoIterator<Item = &'a Scope>,
+    ) -> Option<Name> {
+        let given_name = self.root.given_name(name);
+        self.find_given(&given_name).or_else(|| {
+            chain
+                .into_iter()
+                .find_map(|scope| scope.find_given(&given_name))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn follow_call_chain() {
+        let root = RootScope::new_root();
+        let global_x = root.add_named("x").unwrap();
+        let caller = root.add_child();
+        let caller_x = caller.add_named("x").unwrap();
+        let callee = root.add_child();
+        assert_eq!(callee.resolve("x"), Some(global_x.clone()));
+        assert_eq!(
+            callee.resolve_dynamic("x", [&caller, &root]),
+            Some(caller_x)
+        );
+        assert_eq!(callee.resolve_dynamic("x", [&root]), Some(global_x));
+        assert_eq!(callee.resolve_dynamic("x", []), None);
+    }
+}
@@ -0,0 +1,178 @@
+/// Emission order: "must be emitted before" edges between scopes or between names, e.g.
+/// a type definition that must precede the functions using it, so that code generators
+/// can derive the order in which to emit things from the same tree.
+///
+/// Scopes and names without edges between them keep their documented order, see
+/// `RootScope::names_in_order`, so the result only deviates from it where it must.
+/// Detached scopes and removed names are left out, and edges through them are ignored.
+use ::std::cmp::Reverse;
+use ::std::collections::{BinaryHeap, HashMap};
+use ::std::iter::once;
+
+use crate::name::Name;
+use crate::order::GenerationOrder;
+use crate::scope::{RootScope, Scope};
+
+/// Scopes or names that could not be ordered, because their edges form a cycle. Contains
+/// the members of the cycle, and anything that must come after one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmitCycle<T>(pub Vec<T>);
+
+impl Scope {
+    /// Require that this scope is emitted before the other one.
+    ///
+    /// Panics if the other scope is in another tree.
+    pub fn emit_before(&self, later: &Scope) {
+        assert!(
+            self.root == later.root,
+            "cannot order scopes of different trees"
+        );
+        self.root
+            .scope_data_at(self.index, |data| data.emit_before.push(later.index));
+    }
+}
+
+impl Name {
+    /// Require that this name is emitted before the other one.
+    ///
+    /// Panics if the other name is in another tree.
+    pub fn emit_before(&self, later: &Name) {
+        assert!(
+            self.root == later.root,
+            "cannot order names of different trees"
+        );
+        self.root.root_data.names.borrow_mut()[self.index]
+            .emit_before
+            .push(later.index);
+    }
+}
+
+impl RootScope {
+    /// All attached scopes, such that each comes before the scopes it must be emitted
+    /// before, and before its children; otherwise in preorder.
+    pub fn scopes_in_emit_order(&self) -> Result<Vec<Scope>, EmitCycle<Scope>> {
+        let root = self.root_scope();
+        let scopes: Vec<usize> = once(root.clone())
+            .chain(root.descendants())
+            .map(|scope| scope.index)
+            .collect();
+        // Scopes contain their children, so a parent always comes before its children.
+        let edges = |index: usize| {
+            let mut later = self.read_scope_data_at(index, |data| data.emit_before.clone());
+            later.extend(self.child_indices(index));
+            later
+        };
+        topological(&scopes, edges)
+            .map(|order| {
+                order
+                    .into_iter()
+                    .map(|index| self.scope_at_index(index))
+                    .collect()
+            })
+            .map_err(|rest| {
+                EmitCycle(
+                    rest.into_iter()
+                        .map(|index| self.scope_at_index(index))
+                        .collect(),
+                )
+            })
+    }
+
+    /// All names in attached scopes, such that each comes before the names it must be
+    /// emitted before; otherwise in registration order within preorder scopes.
+    pub fn names_in_emit_order(&self) -> Result<Vec<Name>, EmitCycle<Name>> {
+        let names: Vec<usize> = self
+            .names_in_order(GenerationOrder::Registration)
+            .iter()
+            .map(|name| name.index)
+            .collect();
+        let edges = |index: usize| self.root_data.names.borrow()[index].emit_before.clone();
+        topological(&names, edges)
+            .map(|order| order.into_iter().map(|index| self.name_at(index)).collect())
+            .map_err(|rest| EmitCycle(rest.into_iter().map(|index| self.name_at(index)).collect()))
+    }
+}
+
+// Order the items so that edges point forward, picking the earliest ready item each time.
+// On a cycle, returns the items that could not be placed, in their original order.
+fn topological(
+    items: &[usize],
+    edges: impl Fn(usize) -> Vec<usize>,
+) -> Result<Vec<usize>, Vec<usize>> {
+    let position: HashMap<usize, usize> = items
+        .iter()
+        .enumerate()
+        .map(|(position, &item)| (item, position))
+        .collect();
+    // Successors as positions, ignoring items that are not being ordered.
+    let successors: Vec<Vec<usize>> = items
+        .iter()
+        .map(|&item| {
+            edges(item)
+                .iter()
+                .filter_map(|later| position.get(later).cloned())
+                .collect()
+        })
+        .collect();
+    let mut incoming = vec![0; items.len()];
+    for later in successors.iter().flatten() {
+        incoming[*later] += 1;
+    }
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..items.len())
+        .filter(|&position| incoming[position] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(items.len());
+    while let Some(Reverse(current)) = ready.pop() {
+        order.push(items[current]);
+        for &later in &successors[current] {
+            incoming[later] -= 1;
+            if incoming[later] == 0 {
+                ready.push(Reverse(later));
+            }
+        }
+    }
+    if order.len() < items.len() {
+        return Err((0..items.len())
+            .filter(|&position| incoming[position] > 0)
+            .map(|position| items[position])
+            .collect());
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emit::EmitCycle;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn scopes_respect_edges() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        let block = function.add_child();
+        let types = root.add_child();
+        let unrelated = root.add_child();
+        types.emit_before(&function);
+        let order = root.root().scopes_in_emit_order().unwrap();
+        assert_eq!(order, vec![root.clone(), types, function, block, unrelated]);
+    }
+
+    #[test]
+    fn names_with_cycle() {
+        let root = RootScope::new_root();
+        let first = root.add_named("first").unwrap();
+        let second = root.add_named("second").unwrap();
+        let third = root.add_named("third").unwrap();
+        third.emit_before(&first);
+        assert_eq!(
+            root.root().names_in_emit_order().unwrap(),
+            vec![second.clone(), third.clone(), first.clone()]
+        );
+        first.emit_before(&third);
+        assert_eq!(
+            root.root().names_in_emit_order(),
+            Err(EmitCycle(vec![first, third]))
+        );
+    }
+}
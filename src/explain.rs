@@ -0,0 +1,238 @@
+/// Explaining generated outputs: for each given name whose output differs from its text,
+/// the reasons why, so that surprising renames can be audited.
+///
+/// The reasons can also be counted per subtree, to find the modules that cause the most
+/// renames.
+///
+/// The reasons are worked out from the finished `NameMap`, by replaying the steps that
+/// generation took for the name: relating it to its origin, sanitizing, shortening and
+/// quoting, and then checking why the first candidate was not used. Overridden outputs are
+/// explained the same way, against the outputs they were checked against.
+use ::std::collections::HashMap;
+use ::std::iter::{once, successors};
+
+use crate::generate::{check_output, InvalidOverride, NameMap, OutputName};
+use crate::name::{InputName, Name};
+use crate::scope::Scope;
+use crate::strategy::Strategy;
+
+/// A given name whose output differs from its text, see `NameMap::renames`.
+#[derive(Debug, Clone)]
+pub struct Renamed {
+    pub name: Name,
+    pub output: OutputName,
+    /// Why, in the order in which generation applied them; never empty.
+    pub reasons: Vec<RenameReason>,
+}
+
+/// One step that changed the output of a name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameReason {
+    /// The name contains the text of its origin (see `Name::derive_from`), which was renamed.
+    Derived(Name),
+    /// The text has characters that the policy does not allow.
+    Sanitized,
+    /// The strategy does not base outputs on the text, see `Strategy::Minify`, or puts a
+    /// prefix in front.
+    Strategy,
+    /// The formatter of the policy changed the output, see `OutputPolicy::formatter`.
+    Formatted,
+    /// The output was longer than `OutputPolicy::max_length`.
+    Shortened,
+    /// The output is a keyword of the policy, so it was quoted or avoided.
+    Keyword,
+    /// The output is reserved where the name is visible, see `Scope::reserve`.
+    Reserved,
+    /// The output is used by this name, declared where it is visible or vice versa.
+    Conflict(Name),
+    /// The output was taken where the name is visible through imports or mixins, or where
+    /// a name unified with it is visible.
+    Elsewhere,
+}
+
+/// How often each reason occurs for the renamed names of a subtree, see
+/// `NameMap::rename_stats`. A renamed name can count towards several reasons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenameStats {
+    /// Number of renamed names.
+    pub renamed: usize,
+    pub derived: usize,
+    pub sanitized: usize,
+    pub strategy: usize,
+    pub formatted: usize,
+    pub shortened: usize,
+    pub keyword: usize,
+    pub reserved: usize,
+    pub conflict: usize,
+    pub elsewhere: usize,
+}
+
+impl RenameStats {
+    fn add(&mut self, reasons: &[RenameReason]) {
+        self.renamed += 1;
+        for reason in reasons {
+            *match reason {
+                RenameReason::Derived(_) => &mut self.derived,
+                RenameReason::Sanitized => &mut self.sanitized,
+                RenameReason::Strategy => &mut self.strategy,
+                RenameReason::Formatted => &mut self.formatted,
+                RenameReason::Shortened => &mut self.shortened,
+                RenameReason::Keyword => &mut self.keyword,
+                RenameReason::Reserved => &mut self.reserved,
+                RenameReason::Conflict(_) => &mut self.conflict,
+                RenameReason::Elsewhere => &mut self.elsewhere,
+            } += 1;
+        }
+    }
+}
+
+impl NameMap {
+    /// For every scope in preorder, the reasons of the renames in its subtree, so the root
+    /// has the totals. Detached scopes are left out.
+    pub fn rename_stats(&self) -> Vec<(Scope, RenameStats)> {
+        let mut stats: HashMap<usize, RenameStats> = HashMap::new();
+        for renamed in self.renames() {
+            for scope in successors(Some(renamed.name.scope()), Scope::parent) {
+                stats.entry(scope.index).or_default().add(&renamed.reasons);
+            }
+        }
+        let root = self.root.root_scope();
+        once(root.clone())
+            .chain(root.descendants())
+            .map(|scope| {
+                let scope_stats = stats.get(&scope.index).cloned().unwrap_or_default();
+                (scope, scope_stats)
+            })
+            .collect()
+    }
+
+    /// The given names whose output differs from their text, in registration order, with
+    /// the reasons why. Anonymous names have no text to keep, so they are not included.
+    pub fn renames(&self) -> Vec<Renamed> {
+        self.iter()
+            .filter_map(|(name, output)| {
+                let text = match &name.data {
+                    InputName::Given(given) if given.as_str() != output.as_str() => given.as_str(),
+                    _ => return None,
+                };
+                let reasons = self.rename_reasons(&name, text, output);
+                Some(Renamed {
+                    name,
+                    output: output.clone(),
+                    reasons,
+                })
+            })
+            .collect()
+    }
+
+    fn rename_reasons(&self, name: &Name, text: &str, output: &OutputName) -> Vec<RenameReason> {
+        let policy = self.policy.for_name(name);
+        let mut reasons = vec![];
+        let related = policy.related(name, text, |origin| self.get(origin).cloned());
+        if related != text {
+            let (origin, _) = name.origin().expect("related text without origin");
+            reasons.push(RenameReason::Derived(origin));
+        }
+        let mangled = policy.with_arity(name, related.clone());
+        let sanitized = policy.sanitize(&mangled);
+        if sanitized != mangled {
+            reasons.push(RenameReason::Sanitized);
+        }
+        let (strategy, prefix) = policy.strategy.for_name(name);
+        if matches!(strategy, Strategy::Minify | Strategy::Compress) || !prefix.is_empty() {
+            reasons.push(RenameReason::Strategy);
+        }
+        if matches!(strategy, Strategy::Minify | Strategy::Compress) {
+            return reasons;
+        }
+        let prefixed = format!("{}{}", prefix, sanitized);
+        let formatted = policy.format(name, prefixed.clone());
+        if formatted != prefixed || mangled != related {
+            reasons.push(RenameReason::Formatted);
+        }
+        let fitted = policy.fit(formatted.clone());
+        if fitted != formatted {
+            reasons.push(RenameReason::Shortened);
+        }
+        let first = policy.quote_keyword(fitted.clone());
+        if first != fitted {
+            reasons.push(RenameReason::Keyword);
+        }
+        if first == output.as_str() {
+            return reasons;
+        }
+        let scope = name.scope();
+        let is_keyword = scope
+            .descendants()
+            .chain([scope.clone()])
+            .any(|visible| policy.is_keyword(&first, visible.is_case_insensitive()));
+        let unified = name.unified_names();
+        reasons.push(if is_keyword {
+            RenameReason::Keyword
+        } else {
+            match check_output(&self.policy, name, &first, &unified, |other| {
+                self.get(other).cloned()
+            }) {
+                Err(InvalidOverride::Conflict(other)) => RenameReason::Conflict(other),
+                Err(_) => RenameReason::Reserved,
+                Ok(()) => RenameReason::Elsewhere,
+            }
+        });
+        reasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::explain::{RenameReason, RenameStats};
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn explain_renames() {
+        let root = RootScope::new_root();
+        root.reserve("print");
+        let x = root.add_named("x").unwrap();
+        let kept = root.add_named("kept").unwrap();
+        let print = root.add_named("print").unwrap();
+        let keyword = root.add_named("fn").unwrap();
+        let function = root.add_child();
+        let inner = function.add_named("x").unwrap();
+        let dashed = function.add_named("a-b").unwrap();
+        let getter = function.add_named("get_x").unwrap();
+        getter.derive_from(&inner, "getter");
+        function.add_anonymous();
+        let policy = OutputPolicy::default().with_keywords(&["fn"]);
+        let map = root.root().generate_names(&policy).unwrap();
+        let renames = map.renames();
+        let reasons: Vec<_> = renames
+            .iter()
+            .map(|renamed| (renamed.name.clone(), renamed.reasons.clone()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (print, vec![RenameReason::Reserved]),
+                (keyword, vec![RenameReason::Keyword]),
+                (inner.clone(), vec![RenameReason::Conflict(x)]),
+                (dashed, vec![RenameReason::Sanitized]),
+                (getter, vec![RenameReason::Derived(inner)]),
+            ]
+        );
+        assert!(renames.iter().all(|renamed| renamed.name != kept));
+        assert_eq!(renames[2].output.as_str(), "x2");
+        let stats = map.rename_stats();
+        assert_eq!(stats[0].1.renamed, 5);
+        assert_eq!(stats[1].0, function);
+        assert_eq!(
+            stats[1].1,
+            RenameStats {
+                renamed: 3,
+                derived: 1,
+                sanitized: 1,
+                conflict: 1,
+                ..RenameStats::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,94 @@
+/// Names bound to symbols of external libraries, e.g. functions imported through FFI, so
+/// that code generators can tell them apart from names they are free to rename.
+///
+/// Generation keeps the spelling of the symbol, or a thunk name based on it, see
+/// `OutputPolicy::external_thunk`.
+use ::ustr::Ustr;
+
+use crate::name::Name;
+use crate::scope::RootScope;
+
+/// A symbol in an external library, spelled as that library spells it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExternalSymbol {
+    pub library: Ustr,
+    pub symbol: Ustr,
+}
+
+impl Name {
+    /// Record that this name refers to the symbol in the library, replacing any earlier
+    /// binding.
+    pub fn bind_external(&self, library: &str, symbol: &str) {
+        self.root.root_data.names.borrow_mut()[self.index].external = Some(ExternalSymbol {
+            library: Ustr::from(library),
+            symbol: Ustr::from(symbol),
+        });
+    }
+
+    /// The external symbol this name refers to, or None for ordinary names.
+    pub fn external(&self) -> Option<ExternalSymbol> {
+        self.root.root_data.names.borrow()[self.index]
+            .external
+            .clone()
+    }
+}
+
+impl RootScope {
+    /// All names bound to external symbols, in registration order, e.g. to emit the imports.
+    pub fn external_names(&self) -> Vec<Name> {
+        let indices: Vec<usize> = self
+            .root_data
+            .names
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, data)| data.external.is_some() && !data.removed)
+            .map(|(index, _)| index)
+            .collect();
+        indices
+            .into_iter()
+            .map(|index| self.name_at(index))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn bind_to_library() {
+        let root = RootScope::new_root();
+        let local = root.add_named("local").unwrap();
+        let malloc = root.add_named("allocate").unwrap();
+        malloc.bind_external("libc", "malloc");
+        let external = malloc.external().unwrap();
+        assert_eq!(external.library.as_str(), "libc");
+        assert_eq!(external.symbol.as_str(), "malloc");
+        assert_eq!(local.external(), None);
+        assert_eq!(root.root().external_names(), vec![malloc]);
+    }
+
+    #[test]
+    fn generate_external_spelling() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let local = child.add_named("malloc").unwrap();
+        let allocate = root.add_named("allocate").unwrap();
+        allocate.bind_external("libc", "malloc");
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&allocate).unwrap().as_str(), "malloc");
+        assert_eq!(map.get(&local).unwrap().as_str(), "malloc2");
+        let policy = OutputPolicy {
+            external_thunk: Some("thunk_".to_owned()),
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&allocate).unwrap().as_str(), "thunk_malloc");
+        assert_eq!(map.get(&local).unwrap().as_str(), "malloc");
+    }
+}
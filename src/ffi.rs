@@ -0,0 +1,265 @@
+/// C ABI for non-Rust front-ends, enabled with the `ffi` feature.
+///
+/// Scopes and names are passed around as opaque heap-allocated handles. Every
+/// handle returned by this module is owned by the caller, and should be released
+/// with the matching `_free` function exactly once.
+///
+/// Functions never unwind into the caller: where Rust would fail, e.g. when a limit is
+/// exceeded (see `RootScope::set_limits`) or the scope is sealed, they return null.
+///
+/// Outputs are generated with the default policy by `scoped_name_generate`, which returns
+/// a name map handle; outputs looked up in it are strings owned by the caller, released
+/// with `scoped_name_string_free`.
+use ::std::ffi::{CStr, CString};
+use ::std::os::raw::c_char;
+use ::std::panic::{catch_unwind, AssertUnwindSafe};
+use ::std::ptr;
+
+use crate::generate::{NameMap, OutputPolicy};
+use crate::name::Name;
+use crate::scope::{RootScope, Scope};
+
+/// Box the result for the caller, or return null if there is none or if making it panics,
+/// since unwinding across the C ABI aborts the process.
+fn into_handle<T>(make: impl FnOnce() -> Option<T>) -> *mut T {
+    match catch_unwind(AssertUnwindSafe(make)) {
+        Ok(Some(value)) => Box::into_raw(Box::new(value)),
+        Ok(None) | Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Create a new root, returning a handle to its scope.
+#[no_mangle]
+pub extern "C" fn scoped_name_root_new() -> *mut Scope {
+    Box::into_raw(Box::new(RootScope::new_root()))
+}
+
+/// Create a child of the given scope, returning a handle to it. Returns null if the scope
+/// cannot have more children.
+///
+/// # Safety
+///
+/// `scope` must be a live handle returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn scope_add_child(scope: *const Scope) -> *mut Scope {
+    let scope = &*scope;
+    into_handle(|| scope.try_add_child().ok())
+}
+
+/// Register a given name in the scope. Returns null if the name already exists in
+/// this scope, if the scope cannot have more names, or if `name` is not valid UTF-8.
+///
+/// # Safety
+///
+/// `scope` must be a live handle returned by this module, and `name` must be a
+/// nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn scope_add_named(scope: *const Scope, name: *const c_char) -> *mut Name {
+    let scope = &*scope;
+    let text = match CStr::from_ptr(name).to_str() {
+        Ok(text) => text,
+        Err(_) => return ptr::null_mut(),
+    };
+    into_handle(|| scope.try_add_named(text).ok())
+}
+
+/// Register an anonymous name with a prefix in the scope. Returns null if the scope
+/// cannot have more names, or if `prefix` is not valid UTF-8.
+///
+/// # Safety
+///
+/// `scope` must be a live handle returned by this module, and `prefix` must be a
+/// nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn scope_add_prefixed(
+    scope: *const Scope,
+    prefix: *const c_char,
+) -> *mut Name {
+    let scope = &*scope;
+    match CStr::from_ptr(prefix).to_str() {
+        Ok(prefix) => into_handle(|| scope.try_add_prefixed(prefix).ok()),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Register an anonymous name without a prefix in the scope. Returns null if the scope
+/// cannot have more names.
+///
+/// # Safety
+///
+/// `scope` must be a live handle returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn scope_add_anonymous(scope: *const Scope) -> *mut Name {
+    let scope = &*scope;
+    into_handle(|| {
+        scope.check_name_limits().ok()?;
+        Some(scope.add_anonymous())
+    })
+}
+
+/// Release a scope handle. The scope tree itself stays alive as long as other
+/// handles into it exist. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `scope` must be null or a live handle returned by this module, and must not be
+/// used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn scope_free(scope: *mut Scope) {
+    if !scope.is_null() {
+        drop(Box::from_raw(scope));
+    }
+}
+
+/// Release a name handle. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `name` must be null or a live handle returned by this module, and must not be
+/// used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn name_free(name: *mut Name) {
+    if !name.is_null() {
+        drop(Box::from_raw(name));
+    }
+}
+
+/// Generate outputs for all names in the tree of the scope, with the default policy.
+/// Returns null if generation fails, e.g. because unified names cannot share an output.
+///
+/// # Safety
+///
+/// `scope` must be a live handle returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn scoped_name_generate(scope: *const Scope) -> *mut NameMap {
+    let scope = &*scope;
+    into_handle(|| scope.root().generate_names(&OutputPolicy::default()).ok())
+}
+
+/// The output of the name in the map, as a new nul-terminated string. Returns null if the
+/// name has no output in the map.
+///
+/// # Safety
+///
+/// `map` and `name` must be live handles returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn name_map_output(map: *const NameMap, name: *const Name) -> *mut c_char {
+    let (map, name) = (&*map, &*name);
+    match map
+        .get(name)
+        .and_then(|output| CString::new(output.as_str()).ok())
+    {
+        Some(output) => output.into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Release a name map handle. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `map` must be null or a live handle returned by this module, and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn name_map_free(map: *mut NameMap) {
+    if !map.is_null() {
+        drop(Box::from_raw(map));
+    }
+}
+
+/// Release a string returned by this module. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `text` must be null or a live string returned by this module, and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn scoped_name_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::ffi::CString;
+
+    use super::*;
+    use crate::limit::Limits;
+
+    #[test]
+    fn add_named_duplicate() {
+        let hello = CString::new("hello").unwrap();
+        unsafe {
+            let root = scoped_name_root_new();
+            let child = scope_add_child(root);
+            let name1 = scope_add_named(child, hello.as_ptr());
+            assert!(!name1.is_null());
+            let name2 = scope_add_named(child, hello.as_ptr());
+            assert!(name2.is_null());
+            name_free(name1);
+            scope_free(child);
+            scope_free(root);
+        }
+    }
+
+    #[test]
+    fn failures_return_null() {
+        let hello = CString::new("hello").unwrap();
+        unsafe {
+            let root = scoped_name_root_new();
+            (*root).root().set_limits(Limits {
+                max_scopes: Some(2),
+                max_names_per_scope: Some(1),
+                ..Limits::default()
+            });
+            let child = scope_add_child(root);
+            assert!(!child.is_null());
+            assert!(scope_add_child(root).is_null());
+            let name = scope_add_named(child, hello.as_ptr());
+            assert!(!name.is_null());
+            assert!(scope_add_anonymous(child).is_null());
+            (*root).seal();
+            assert!(scope_add_named(root, hello.as_ptr()).is_null());
+            name_free(name);
+            scope_free(child);
+            scope_free(root);
+        }
+    }
+
+    #[test]
+    fn add_anonymous() {
+        let prefix = CString::new("tmp").unwrap();
+        unsafe {
+            let root = scoped_name_root_new();
+            let name1 = scope_add_prefixed(root, prefix.as_ptr());
+            let name2 = scope_add_anonymous(root);
+            assert!(!name1.is_null());
+            assert!(!name2.is_null());
+            name_free(name1);
+            name_free(name2);
+            scope_free(root);
+        }
+    }
+
+    #[test]
+    fn generate_outputs() {
+        let hello = CString::new("hello").unwrap();
+        unsafe {
+            let root = scoped_name_root_new();
+            let child = scope_add_child(root);
+            let outer = scope_add_named(root, hello.as_ptr());
+            let inner = scope_add_named(child, hello.as_ptr());
+            let map = scoped_name_generate(child);
+            assert!(!map.is_null());
+            let output = name_map_output(map, inner);
+            assert_eq!(CStr::from_ptr(output).to_str(), Ok("hello2"));
+            scoped_name_string_free(output);
+            name_map_free(map);
+            name_free(outer);
+            name_free(inner);
+            scope_free(child);
+            scope_free(root);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+/// Finalizing a tree in one step, as the transition from the build phase to the emit phase:
+/// references are bound, outputs are generated, and the whole tree is sealed.
+///
+/// The result is a read-only `FinalizedScopes`, with resolution and outputs. Its scopes are
+/// `FinalizedScope` handles, which have no methods that change the tree, so adding names
+/// after generation through them does not compile. `Scope` handles kept from the build
+/// phase still exist, but since the tree is sealed, adding or removing names through them
+/// panics, so outputs cannot get out of date; labels, spans and other descriptive data can
+/// still be set.
+use ::ustr::Ustr;
+
+use crate::generate::{GenerateError, NameMap, OutputName, OutputPolicy};
+use crate::kind::ScopeKind;
+use crate::name::Name;
+use crate::reference::Unresolved;
+use crate::scope::{RootScope, Scope, ScopeId};
+
+/// Why a tree could not be finalized. The tree is left unsealed.
+#[derive(Debug, Clone)]
+pub enum FinalizeError {
+    /// Some references have no declaration, see `RootScope::finalize`.
+    Unresolved(Vec<Unresolved>),
+    /// No outputs could be generated, see `RootScope::generate_names`.
+    Generate(GenerateError),
+}
+
+impl From<GenerateError> for FinalizeError {
+    fn from(error: GenerateError) -> Self {
+        FinalizeError::Generate(error)
+    }
+}
+
+/// A sealed tree with generated outputs, see `RootScope::finalize_with`.
+#[derive(Debug, Clone)]
+pub struct FinalizedScopes {
+    root: RootScope,
+    names: NameMap,
+}
+
+impl RootScope {
+    /// Bind references, generate outputs with the policy and seal the tree. Nothing is
+    /// sealed if this fails, so the tree can be fixed and finalized again.
+    pub fn finalize_with(&self, policy: &OutputPolicy) -> Result<FinalizedScopes, FinalizeError> {
+        self.finalize().map_err(FinalizeError::Unresolved)?;
+        let names = self.generate_names(policy)?;
+        self.root_scope().seal_subtree();
+        Ok(FinalizedScopes {
+            root: self.clone(),
+            names,
+        })
+    }
+}
+
+/// A scope of a finalized tree. Cheap to create, and only has methods that read the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalizedScope {
+    scope: Scope,
+}
+
+impl FinalizedScopes {
+    pub fn root(&self) -> FinalizedScope {
+        FinalizedScope {
+            scope: self.root.root_scope(),
+        }
+    }
+
+    /// The scope with the id, or None if it is not in the tree.
+    pub fn scope(&self, id: ScopeId) -> Option<FinalizedScope> {
+        self.root.scope(id).map(|scope| FinalizedScope { scope })
+    }
+
+    /// The output of the name, or None if it is from another tree or was detached.
+    pub fn output(&self, name: &Name) -> Option<&OutputName> {
+        self.names.get(name)
+    }
+
+    /// All generated outputs.
+    pub fn names(&self) -> &NameMap {
+        &self.names
+    }
+}
+
+impl FinalizedScope {
+    pub fn id(&self) -> ScopeId {
+        self.scope.id()
+    }
+
+    pub fn parent(&self) -> Option<FinalizedScope> {
+        self.scope.parent().map(|scope| FinalizedScope { scope })
+    }
+
+    pub fn children(&self) -> Vec<FinalizedScope> {
+        self.scope
+            .children()
+            .map(|scope| FinalizedScope { scope })
+            .collect()
+    }
+
+    /// The names declared directly in this scope, in registration order.
+    pub fn names(&self) -> Vec<Name> {
+        self.scope.names()
+    }
+
+    pub fn kind(&self) -> ScopeKind {
+        self.scope.kind()
+    }
+
+    pub fn label(&self) -> Option<Ustr> {
+        self.scope.label()
+    }
+
+    /// The name that the text refers to in this scope, like `Scope::resolve`.
+    pub fn resolve(&self, text: &str) -> Option<Name> {
+        self.scope.resolve(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use crate::finalized::FinalizeError;
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn finalize_seals_and_generates() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let function = root.add_child();
+        let inner = function.add_named("x").unwrap();
+        function.reference("y");
+        let policy = OutputPolicy::default();
+        match root.root().finalize_with(&policy) {
+            Err(FinalizeError::Unresolved(unresolved)) => assert_eq!(unresolved.len(), 1),
+            other => panic!("expected unresolved references, got {:?}", other),
+        }
+        assert!(!function.is_sealed());
+        let y = root.add_named("y").unwrap();
+        let finalized = root.root().finalize_with(&policy).unwrap();
+        let finalized_function = finalized.scope(function.id()).unwrap();
+        assert_eq!(
+            finalized.root().children(),
+            vec![finalized_function.clone()]
+        );
+        assert_eq!(finalized_function.parent(), Some(finalized.root()));
+        assert_eq!(finalized_function.resolve("x"), Some(inner.clone()));
+        assert_eq!(finalized_function.resolve("y"), Some(y));
+        assert_eq!(finalized.output(&x).unwrap().as_str(), "x");
+        assert_eq!(finalized.output(&inner).unwrap().as_str(), "x2");
+        // Handles from the build phase can still try to change the tree, but that panics.
+        assert!(function.is_sealed());
+        assert!(catch_unwind(AssertUnwindSafe(|| function.add_named("z"))).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+/// Fingerprints of whole trees, which are the same in every run and on every platform, e.g.
+/// to use as a cache key and skip regeneration when nothing changed.
+///
+/// The fingerprint covers the shape of the tree, its names, and every scope setting that
+/// affects the outputs: kinds, labels, case sensitivity, conditions, reserved outputs and
+/// targets. It does not depend on the registration order of given names, root ids or hash
+/// seeds. It uses FNV-1a rather than `std::hash`, whose output may change between Rust
+/// versions.
+///
+/// Individual scopes and names have a `StableHash` based on their path, since their `Hash`
+/// depends on the root id, which differs between runs.
+use ::std::iter::once;
+
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+/// A hash that is the same in every run, e.g. to store in incremental compilation caches.
+pub trait StableHash {
+    fn stable_hash(&self) -> u64;
+}
+
+/// A 64-bit FNV-1a hasher, with length-prefixed strings so that concatenations differ.
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn write_str(&mut self, text: &str) {
+        self.write_u64(text.len() as u64);
+        self.write_bytes(text.as_bytes());
+    }
+
+    pub(crate) fn write_option(&mut self, value: Option<u64>) {
+        match value {
+            Some(value) => {
+                self.write_bytes(&[1]);
+                self.write_u64(value);
+            }
+            None => self.write_bytes(&[0]),
+        }
+    }
+
+    pub(crate) fn write_option_str(&mut self, text: Option<&str>) {
+        match text {
+            Some(text) => {
+                self.write_bytes(&[1]);
+                self.write_str(text);
+            }
+            None => self.write_bytes(&[0]),
+        }
+    }
+
+    pub(crate) fn write_given(&mut self, given: &GivenName) {
+        self.write_str(given.as_str());
+        self.write_option(given.overload.map(|key| key.0));
+        self.write_option(given.arity.map(u64::from));
+        self.write_option(given.version.map(u64::from));
+        self.write_option_str(given.namespace());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl RootScope {
+    /// A hash of the shape of the tree and its names, that is stable across runs.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        let root = self.root_scope();
+        // Scopes in preorder, each with its child count, which determines the shape.
+        for scope in once(root.clone()).chain(root.descendants()) {
+            write_scope(&mut hasher, &scope);
+        }
+        hasher.finish()
+    }
+}
+
+impl StableHash for Scope {
+    /// Hashes the path of the scope, see `Display` for `Name`, so scopes at the same path in
+    /// different runs have the same hash.
+    fn stable_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        write_path(&mut hasher, self);
+        hasher.finish()
+    }
+}
+
+impl StableHash for Name {
+    /// Hashes the path of the scope, and the identity of a given name, or the prefix and
+    /// ordinal of an anonymous one.
+    fn stable_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        let scope = self.scope();
+        write_path(&mut hasher, &scope);
+        match &self.data {
+            InputName::Given(given) => hasher.write_given(given),
+            InputName::Anonymous(anon) => {
+                let ordinal = scope
+                    .anon_names_with_prefix(anon.name.as_str())
+                    .iter()
+                    .position(|other| other == self)
+                    .unwrap_or(0);
+                hasher.write_str(anon.name.as_str());
+                hasher.write_u64(ordinal as u64);
+            }
+        }
+        hasher.finish()
+    }
+}
+
+fn write_path(hasher: &mut StableHasher, scope: &Scope) {
+    let segments = scope.path_segments();
+    hasher.write_u64(segments.len() as u64);
+    for segment in segments {
+        hasher.write_str(&segment);
+    }
+}
+
+fn write_scope(hasher: &mut StableHasher, scope: &Scope) {
+    let (kind, label, case_insensitive, condition, target) =
+        scope.root.read_scope_data_at(scope.index, |data| {
+            (
+                data.kind,
+                data.label,
+                data.case_insensitive,
+                data.condition,
+                data.target,
+            )
+        });
+    hasher.write_u64(kind as u64);
+    hasher.write_option_str(label.as_ref().map(|label| label.as_str()));
+    hasher.write_option(case_insensitive.map(u64::from));
+    hasher.write_option_str(condition.as_ref().map(|condition| condition.as_str()));
+    hasher.write_option_str(target.as_ref().map(|target| target.as_str()));
+    let reserved = scope.reserved();
+    hasher.write_u64(reserved.len() as u64);
+    for text in reserved {
+        hasher.write_str(&text);
+    }
+    // Given names have no order, so hash each separately and combine them sorted.
+    let mut given_hashes: Vec<u64> = scope
+        .given_names()
+        .iter()
+        .map(|given| {
+            let mut given_hasher = StableHasher::new();
+            given_hasher.write_given(given);
+            given_hasher.finish()
+        })
+        .collect();
+    given_hashes.sort_unstable();
+    hasher.write_u64(given_hashes.len() as u64);
+    for given_hash in given_hashes {
+        hasher.write_u64(given_hash);
+    }
+    let anonymous: Vec<_> = scope
+        .names()
+        .into_iter()
+        .filter_map(|name| match name.data {
+            InputName::Anonymous(anon) => Some(anon.name),
+            InputName::Given(_) => None,
+        })
+        .collect();
+    hasher.write_u64(anonymous.len() as u64);
+    for prefix in anonymous {
+        hasher.write_str(prefix.as_str());
+    }
+    hasher.write_u64(scope.children().count() as u64);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    use super::StableHash;
+
+    #[test]
+    fn stable_fingerprint() {
+        let build = |reversed: bool| {
+            let root = RootScope::new_root();
+            let mut names = vec!["a", "b"];
+            if reversed {
+                names.reverse();
+            }
+            for name in names {
+                root.add_named(name).unwrap();
+            }
+            root.add_child().add_prefixed("tmp");
+            root
+        };
+        let first = build(false);
+        let second = build(true);
+        assert_eq!(first.root().fingerprint(), second.root().fingerprint());
+        second.add_child();
+        assert_ne!(first.root().fingerprint(), second.root().fingerprint());
+        let nested = build(false);
+        nested.children().next().unwrap().add_child();
+        assert_ne!(first.root().fingerprint(), nested.root().fingerprint());
+        let relabeled = build(false);
+        relabeled.children().next().unwrap().set_label("block");
+        assert_ne!(first.root().fingerprint(), relabeled.root().fingerprint());
+        let reserving = build(false);
+        reserving.reserve("fn");
+        assert_ne!(first.root().fingerprint(), reserving.root().fingerprint());
+        // Pinned, because fingerprints may be stored between runs.
+        assert_eq!(
+            RootScope::new_root().root().fingerprint(),
+            0xc35a_1803_7bc2_c046
+        );
+    }
+
+    #[test]
+    fn stable_across_roots() {
+        let build = || {
+            let root = RootScope::new_root();
+            let module = root.add_child();
+            module.set_label("module");
+            let x = module.add_named("x").unwrap();
+            let tmp = module.add_prefixed("tmp");
+            (module, x, tmp)
+        };
+        let (first_module, first_x, first_tmp) = build();
+        let (second_module, second_x, second_tmp) = build();
+        assert_ne!(first_x, second_x);
+        assert_eq!(first_module.stable_hash(), second_module.stable_hash());
+        assert_eq!(first_x.stable_hash(), second_x.stable_hash());
+        assert_eq!(first_tmp.stable_hash(), second_tmp.stable_hash());
+        assert_ne!(first_x.stable_hash(), first_tmp.stable_hash());
+    }
+}
@@ -0,0 +1,364 @@
+/// A read-only copy of the tree in a flat, struct-of-arrays layout, for passes that only
+/// traverse it, like output generation, and that visit every scope many times.
+///
+/// Scopes are stored in preorder, so a subtree is a contiguous range of positions, and the
+/// names of each scope are a contiguous range in the name tables. Positions are not arena
+/// indices; use `position` and `scope_id` to convert. Detached scopes are not included.
+///
+/// Resolution results are cached, so repeated lookups, e.g. from emit phases, are cheap. The
+/// cache is behind a lock, so a frozen tree can still be shared between threads. The lock
+/// is only held inside each method, never while the caller runs.
+use ::std::collections::{HashMap, HashSet};
+use ::std::iter::once;
+use ::std::ops::Range;
+use ::std::sync::atomic::{AtomicUsize, Ordering};
+use ::std::sync::Mutex;
+
+use ::ustr::Ustr;
+
+use crate::canonical::Canonicalization;
+use crate::kind::ScopeKind;
+use crate::name::{InputName, NameId};
+use crate::scope::{RootScope, ScopeId};
+use crate::text::NameText;
+
+#[derive(Debug, Clone)]
+pub struct FrozenScopes {
+    // Per scope, by position.
+    scope_ids: Vec<ScopeId>,
+    parents: Vec<Option<usize>>,
+    subtree_ends: Vec<usize>,
+    kinds: Vec<ScopeKind>,
+    labels: Vec<Option<Ustr>>,
+    // The names of the scope at position i are at name_starts[i]..name_starts[i + 1].
+    name_starts: Vec<usize>,
+    // Per name. Texts are given names or anonymous prefixes, stored back to back.
+    name_ids: Vec<NameId>,
+    anonymous: Vec<bool>,
+    text_ends: Vec<usize>,
+    texts: String,
+    // Per scope, see `Scope::visible_name_count`.
+    visible_name_counts: Vec<usize>,
+    // Per scope, the positions of its ordinary given names sorted by text, for completion;
+    // those of the scope at position i are at completion_starts[i]..completion_starts[i + 1].
+    completion_starts: Vec<usize>,
+    completions: Vec<usize>,
+    // Position for each arena index, None for detached scopes.
+    positions: Vec<Option<usize>>,
+    // The rules of the tree when it was frozen, applied to looked up texts.
+    canonicalization: Canonicalization,
+    cache: ResolveCache,
+}
+
+/// Hit and miss counts of the resolution cache of `FrozenScopes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl CacheStats {
+    /// The fraction of lookups that were answered from the cache, or 0 without lookups.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ResolveCache {
+    // Name position found for a text looked up from a scope position.
+    entries: Mutex<HashMap<(usize, NameText), Option<usize>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl Clone for ResolveCache {
+    fn clone(&self) -> Self {
+        ResolveCache {
+            entries: Mutex::new(self.entries.lock().expect("cache lock poisoned").clone()),
+            hits: AtomicUsize::new(self.hits.load(Ordering::Relaxed)),
+            misses: AtomicUsize::new(self.misses.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl RootScope {
+    /// Copy the tree into a `FrozenScopes`. Later changes to the tree are not reflected.
+    pub fn freeze(&self) -> FrozenScopes {
+        let root = self.root_scope();
+        let mut frozen = FrozenScopes {
+            scope_ids: vec![],
+            parents: vec![],
+            subtree_ends: vec![],
+            kinds: vec![],
+            labels: vec![],
+            name_starts: vec![0],
+            name_ids: vec![],
+            anonymous: vec![],
+            text_ends: vec![],
+            texts: String::new(),
+            visible_name_counts: vec![],
+            completion_starts: vec![0],
+            completions: vec![],
+            positions: vec![None; self.root_data.scopes.borrow().len()],
+            canonicalization: self.canonicalization(),
+            cache: ResolveCache::default(),
+        };
+        for scope in once(root.clone()).chain(root.descendants()) {
+            let position = frozen.scope_ids.len();
+            frozen.positions[scope.index] = Some(position);
+            frozen.scope_ids.push(scope.id());
+            frozen.parents.push(
+                scope
+                    .parent()
+                    .and_then(|parent| frozen.positions[parent.index]),
+            );
+            frozen.subtree_ends.push(position);
+            frozen.kinds.push(scope.kind());
+            frozen.labels.push(scope.label());
+            for name in scope.names() {
+                let (text, is_anonymous) = match &name.data {
+                    InputName::Given(given) => (given.as_str(), false),
+                    InputName::Anonymous(anon) => (anon.name.as_str(), true),
+                };
+                if is_ordinary(&name.data) {
+                    frozen.completions.push(frozen.name_ids.len());
+                }
+                frozen.name_ids.push(name.id());
+                frozen.anonymous.push(is_anonymous);
+                frozen.texts.push_str(text);
+                frozen.text_ends.push(frozen.texts.len());
+            }
+            frozen.name_starts.push(frozen.name_ids.len());
+            let start = frozen.completion_starts[position];
+            let mut completions = frozen.completions.split_off(start);
+            completions.sort_by(|&first, &second| frozen.text(first).cmp(frozen.text(second)));
+            frozen.completions.extend(completions);
+            frozen.completion_starts.push(frozen.completions.len());
+            let inherited = frozen.parents[position].map_or(0, |parent| {
+                frozen.visible_name_counts[parent] - scope.shadowed_count()
+            });
+            frozen
+                .visible_name_counts
+                .push(frozen.names(position).len() + inherited);
+        }
+        // Children come after their parents, so this passes each subtree end upwards.
+        for position in (1..frozen.len()).rev() {
+            let parent = frozen.parents[position].expect("non-root scope without parent");
+            frozen.subtree_ends[parent] =
+                frozen.subtree_ends[parent].max(frozen.subtree_ends[position]);
+        }
+        frozen
+    }
+}
+
+impl FrozenScopes {
+    /// The number of scopes.
+    pub fn len(&self) -> usize {
+        self.scope_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scope_ids.is_empty()
+    }
+
+    /// The position of a scope, or None if it was detached or created after freezing.
+    pub fn position(&self, id: ScopeId) -> Option<usize> {
+        self.positions.get(id.0).cloned().flatten()
+    }
+
+    pub fn scope_id(&self, position: usize) -> ScopeId {
+        self.scope_ids[position]
+    }
+
+    /// The position of the parent, or None for the root.
+    pub fn parent(&self, position: usize) -> Option<usize> {
+        self.parents[position]
+    }
+
+    /// The positions of this scope and all scopes below it.
+    pub fn subtree(&self, position: usize) -> Range<usize> {
+        position..self.subtree_ends[position] + 1
+    }
+
+    pub fn kind(&self, position: usize) -> ScopeKind {
+        self.kinds[position]
+    }
+
+    pub fn label(&self, position: usize) -> Option<Ustr> {
+        self.labels[position]
+    }
+
+    /// The positions in the name tables of the names of a scope, in registration order.
+    pub fn names(&self, position: usize) -> Range<usize> {
+        self.name_starts[position]..self.name_starts[position + 1]
+    }
+
+    pub fn name_id(&self, name_position: usize) -> NameId {
+        self.name_ids[name_position]
+    }
+
+    pub fn is_anonymous(&self, name_position: usize) -> bool {
+        self.anonymous[name_position]
+    }
+
+    /// The number of names that can be used from a scope, see `Scope::visible_name_count`.
+    pub fn visible_name_count(&self, position: usize) -> usize {
+        self.visible_name_counts[position]
+    }
+
+    /// The positions of the ordinary given names visible from a scope that start with the
+    /// prefix, nearest scope first and by text within each scope, like `Scope::complete`.
+    pub fn complete(&self, position: usize, prefix: &str) -> Vec<usize> {
+        let mut seen = HashSet::new();
+        let mut completions = vec![];
+        let mut current = Some(position);
+        while let Some(position) = current {
+            let candidates = &self.completions
+                [self.completion_starts[position]..self.completion_starts[position + 1]];
+            let start = candidates.partition_point(|&name| self.text(name) < prefix);
+            completions.extend(
+                candidates[start..]
+                    .iter()
+                    .take_while(|&&name| self.text(name).starts_with(prefix))
+                    .filter(|&&name| seen.insert(self.text(name))),
+            );
+            current = self.parents[position];
+        }
+        completions
+    }
+
+    /// The position of the ordinary given name that the text refers to in a scope, like
+    /// `Scope::resolve`. Mixins and imports are not part of the frozen view, so only names
+    /// declared in the scope and its ancestors are found. Results are cached.
+    pub fn resolve(&self, position: usize, text: &str) -> Option<usize> {
+        let text = self.canonicalization.apply(text);
+        let key = (position, NameText::from(text.as_ref()));
+        if let Some(&found) = self
+            .cache
+            .entries
+            .lock()
+            .expect("cache lock poisoned")
+            .get(&key)
+        {
+            self.cache.hits.fetch_add(1, Ordering::Relaxed);
+            return found;
+        }
+        self.cache.misses.fetch_add(1, Ordering::Relaxed);
+        let found = self.resolve_uncached(position, &text);
+        self.cache
+            .entries
+            .lock()
+            .expect("cache lock poisoned")
+            .insert(key, found);
+        found
+    }
+
+    fn resolve_uncached(&self, position: usize, text: &str) -> Option<usize> {
+        let mut current = Some(position);
+        while let Some(position) = current {
+            let candidates = &self.completions
+                [self.completion_starts[position]..self.completion_starts[position + 1]];
+            if let Ok(index) = candidates.binary_search_by(|&name| self.text(name).cmp(text)) {
+                return Some(candidates[index]);
+            }
+            current = self.parents[position];
+        }
+        None
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache.hits.load(Ordering::Relaxed),
+            misses: self.cache.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The text of a given name, or the prefix of an anonymous one.
+    pub fn text(&self, name_position: usize) -> &str {
+        let start = match name_position {
+            0 => 0,
+            _ => self.text_ends[name_position - 1],
+        };
+        &self.texts[start..self.text_ends[name_position]]
+    }
+}
+
+fn is_ordinary(data: &InputName) -> bool {
+    match data {
+        InputName::Given(given) => {
+            given.namespace().is_none()
+                && given.overload_key().is_none()
+                && given.arity().is_none()
+                && given.version().is_none()
+        }
+        InputName::Anonymous(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frozen::FrozenScopes;
+    use crate::kind::ScopeKind;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn frozen_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FrozenScopes>();
+    }
+
+    #[test]
+    fn frozen_layout() {
+        let root = RootScope::new_root();
+        root.add_named("main").unwrap();
+        let function = root.add_child();
+        function.set_kind(ScopeKind::Function);
+        function.add_named("x").unwrap();
+        let tmp = function.add_prefixed("tmp");
+        let block = function.add_child();
+        let detached = block.add_child();
+        block.clear_children();
+        let other = root.add_child();
+        let frozen = root.root().freeze();
+        let position = frozen.position(function.id()).unwrap();
+        assert_eq!(frozen.parent(position), Some(0));
+        assert_eq!(frozen.kind(position), ScopeKind::Function);
+        assert_eq!(frozen.subtree(position), 1..3);
+        assert_eq!(frozen.position(block.id()), Some(2));
+        assert_eq!(frozen.position(other.id()), Some(3));
+        assert_eq!(frozen.position(detached.id()), None);
+        assert_eq!(frozen.len(), 4);
+        let names = frozen.names(position);
+        assert_eq!(names, 1..3);
+        assert_eq!(frozen.text(1), "x");
+        assert_eq!(frozen.text(2), "tmp");
+        assert!(frozen.is_anonymous(2));
+        assert_eq!(frozen.name_id(2), tmp.id());
+        assert_eq!(frozen.text(0), "main");
+    }
+
+    #[test]
+    fn cached_resolution() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let function = root.add_child();
+        let y = function.add_named("y").unwrap();
+        function.add_prefixed("x");
+        let frozen = root.root().freeze();
+        let position = frozen.position(function.id()).unwrap();
+        let found = frozen.resolve(position, "x").unwrap();
+        assert_eq!(frozen.name_id(found), x.id());
+        assert_eq!(frozen.resolve(position, "x"), Some(found));
+        let found = frozen.resolve(position, "y").unwrap();
+        assert_eq!(frozen.name_id(found), y.id());
+        assert_eq!(frozen.resolve(0, "y"), None);
+        assert_eq!(frozen.resolve(0, "y"), None);
+        let stats = frozen.cache_stats();
+        assert_eq!((stats.hits, stats.misses), (2, 3));
+        assert_eq!(stats.hit_rate(), 0.4);
+    }
+}
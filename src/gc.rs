@@ -0,0 +1,139 @@
+/// Reclaiming the memory of scopes that are no longer used, e.g. subtrees that a pass
+/// detached, without the caller tracking exactly which subtrees it orphaned.
+///
+/// Scopes stay in the arena, so that ids and handles remain valid, but the names, children
+/// and other data of collected scopes are dropped. Their names are marked as removed.
+use ::std::collections::HashSet;
+
+use crate::scope::{RootScope, Scope, ScopeData, ScopeNames};
+
+impl RootScope {
+    /// Empty every scope that is not reachable from the given ones. Reachable are the given
+    /// scopes and their descendants, and the scopes whose names are visible in those: their
+    /// ancestors, and scopes reached through mixins or imports. Returns the number of scopes
+    /// collected.
+    pub fn collect_garbage(&self, roots: &[Scope]) -> usize {
+        let reachable = self.reachable_from(roots);
+        let count = self.root_data.scopes.borrow().len();
+        let mut collected = 0;
+        for index in (0..count).filter(|index| !reachable.contains(index)) {
+            if self.read_scope_data_at(index, |data| data.collected) {
+                continue;
+            }
+            // During this method, the state is not consistent.
+            // Step 1: detach the scope from a parent that is kept.
+            if let Some(parent) = self.read_scope_data_at(index, |data| data.parent) {
+                if reachable.contains(&parent) {
+                    self.unlink_child(parent, index);
+                }
+            }
+            // Step 2: mark the names as removed, and drop them from the scope.
+            let scope_names = self.scope_names_at(index, ::std::mem::take);
+            let mut names = self.root_data.names.borrow_mut();
+            for name in scope_names
+                .given_names
+                .values()
+                .chain(&scope_names.anon_names)
+            {
+                names[*name].removed = true;
+            }
+            drop(names);
+            // Step 3: drop the rest of the scope data.
+            self.scope_data_at(index, |data| {
+                *data = ScopeData {
+                    collected: true,
+                    ..ScopeData::new(None)
+                }
+            });
+            self.root_data.dirty.borrow_mut().remove(&index);
+            collected += 1;
+        }
+        if collected > 0 {
+            self.shape_changed();
+        }
+        collected
+    }
+
+    fn reachable_from(&self, roots: &[Scope]) -> HashSet<usize> {
+        let mut reachable = HashSet::new();
+        // Scopes with whether their children are reachable too.
+        let mut visited = HashSet::new();
+        let mut pending: Vec<(usize, bool)> = roots
+            .iter()
+            .inspect(|scope| assert!(scope.root == *self, "scope of another tree"))
+            .map(|scope| (scope.index, true))
+            .collect();
+        while let Some((index, with_children)) = pending.pop() {
+            if !visited.insert((index, with_children)) {
+                continue;
+            }
+            reachable.insert(index);
+            if with_children {
+                pending.extend(
+                    self.child_indices(index)
+                        .into_iter()
+                        .map(|child| (child, true)),
+                );
+            }
+            self.read_scope_data_at(index, |data| {
+                pending.extend(data.parent.map(|parent| (parent, false)));
+                pending.extend(data.mixins.iter().map(|&mixin| (mixin, false)));
+            });
+            let names = self.root_data.names.borrow();
+            self.read_scope_names_at(index, |data: &ScopeNames| {
+                pending.extend(
+                    data.imports
+                        .values()
+                        .map(|&name| (names[name].scope, false)),
+                )
+            });
+        }
+        reachable
+    }
+}
+
+impl Scope {
+    /// Whether this scope was emptied by `RootScope::collect_garbage`.
+    pub fn is_collected(&self) -> bool {
+        self.root
+            .read_scope_data_at(self.index, |data| data.collected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn collect_detached_subtrees() {
+        let root = RootScope::new_root();
+        let kept = root.add_child();
+        let x = kept.add_named("x").unwrap();
+        let holder = root.add_child();
+        let detached = holder.add_child();
+        let y = detached.add_named("y").unwrap();
+        let nested = detached.add_child();
+        let mixin = holder.add_child();
+        let z = mixin.add_named("z").unwrap();
+        kept.add_mixin(&mixin).unwrap();
+        holder.clear_children();
+        assert_eq!(
+            root.root().collect_garbage(::std::slice::from_ref(&root)),
+            2
+        );
+        assert!(detached.is_collected());
+        assert!(nested.is_collected());
+        assert!(!mixin.is_collected());
+        assert!(!root.is_collected());
+        assert!(y.is_removed());
+        assert!(!x.is_removed());
+        assert_eq!(detached.names(), vec![]);
+        assert_eq!(kept.resolve("z"), Some(z));
+        assert_eq!(
+            root.root().collect_garbage(::std::slice::from_ref(&kept)),
+            1
+        );
+        assert!(holder.is_collected());
+        assert_eq!(root.children().collect::<Vec<_>>(), vec![kept]);
+    }
+}
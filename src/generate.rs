@@ -0,0 +1,1884 @@
+/// Generating output names: identifiers for the generated code, which do not shadow names
+/// of parent scopes, are not unnecessarily long, and resemble the given name or prefix.
+///
+/// Scopes are visited in preorder. An output must differ from the other outputs of its
+/// scope and of the ancestors, but sibling scopes may reuse outputs. Within a scope, given
+/// names are handled before anonymous ones, so they are the most likely to keep their text.
+/// A collision is resolved by appending the smallest number that makes the output unique,
+/// with an underscore in between if the text already ends in a digit (`x2`, but `v1_2`).
+/// Policies can use letters (`x_b`) or a short hash (`x_9f3`) instead, see `SuffixScheme`.
+///
+/// Names are also visible in the scopes that import them or that have their scope as a
+/// mixin, and their outputs must differ from the outputs visible there too, the same set
+/// that `Scope::resolve` searches. Those names get their outputs before all others, since
+/// the scopes where they are visible may be reached before their own scope.
+///
+/// Names derived from another (see `Name::derive_from`) that contain the text of their origin
+/// get the origin's output in its place, if the origin was assigned first, i.e. is declared
+/// in an ancestor or earlier in the same scope.
+///
+/// The outputs of the latest generation are also kept in the tree, so that `Name::output`
+/// can look them up without a `NameMap` at hand, e.g. while emitting code.
+///
+/// Outputs longer than the policy's `max_length` keep their start, followed by an underscore
+/// and a hash of the whole output, like `a_very_long_na_3fa9c1`. The hash is the same in
+/// every run, and since numbered candidates have different hashes, collisions are resolved
+/// as usual.
+///
+/// The policy's `Strategy` decides which outputs are tried, e.g. minified ones instead of
+/// ones based on the text.
+///
+/// Anonymous names can prefer outputs from a pool for their prefix, like `i`, `j` and `k`
+/// for loop variables, see `OutputPolicy::with_pool`.
+///
+/// Outputs also avoid the keywords of the policy and texts reserved with `Scope::reserve`.
+/// Names in different namespaces never conflict, and in case-insensitive scopes outputs
+/// are compared in lowercase, the same rules as `RootScope::check_injective`. Policies can
+/// compare outputs more loosely, e.g. if the target language ignores case, see
+/// `OutputComparison`. Each namespace can have its own policy, e.g. for CSS classes next to
+/// variables, see `OutputPolicy::with_namespace`, and so can each subtree, e.g. to keep
+/// names readable in exported modules, see `OutputPolicy::with_subtree`. Outputs are unique
+/// across these boundaries all the same.
+use ::std::cmp::Reverse;
+use ::std::collections::hash_map::Entry;
+use ::std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use ::std::fmt;
+use ::std::iter::{once, successors};
+
+use crate::cfg::ConfigView;
+use crate::fingerprint::StableHasher;
+use crate::heat::HeatMap;
+use crate::name::{InputName, Name, NameId};
+use crate::order::GenerationOrder;
+use crate::scope::{RootScope, Scope, ScopeId};
+use crate::strategy::Strategy;
+use crate::unify::{check_unifiable, NotUnifiable};
+
+/// The identifier of a name in the generated code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OutputName(pub(crate) String);
+
+impl OutputName {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for OutputName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<OutputName> for String {
+    fn from(output: OutputName) -> Self {
+        output.0
+    }
+}
+
+/// What outputs are valid in the target language.
+///
+/// Disallowed characters are replaced by an underscore, and outputs that would start with
+/// a character that is not allowed there get an underscore prefix, so the underscore should
+/// be allowed in both places.
+#[derive(Debug, Clone)]
+pub struct OutputPolicy {
+    /// Whether a character may appear in an output.
+    pub allowed: fn(char) -> bool,
+    /// Whether a character may be the first of an output.
+    pub allowed_start: fn(char) -> bool,
+    /// Words that outputs must avoid, like the keywords of the target language.
+    pub keywords: HashSet<String>,
+    /// Text that outputs for anonymous names without prefix are based on.
+    pub anonymous_base: String,
+    /// The longest allowed name in characters: `Scope::add_validated` rejects longer given
+    /// names, and generation shortens longer outputs.
+    pub max_length: Option<usize>,
+    /// The order in which the names of each scope are assigned outputs.
+    pub order: GenerationOrder,
+    /// Policies for names in custom namespaces, by namespace; names in other namespaces
+    /// use this policy. The order, pools and subtree policies of these are not used.
+    pub namespaces: HashMap<String, OutputPolicy>,
+    /// Preferred outputs for anonymous names, by prefix, e.g. `i`, `j` and `k` for loop
+    /// variables. The first free one is used; when all are taken, the output is based on
+    /// the prefix as usual.
+    pub pools: HashMap<String, Vec<String>>,
+    /// Policies for subtrees, by their top scope; names in other scopes use this policy.
+    /// The policy of the nearest of these scopes at or above a name's scope is used, with
+    /// its own namespace policies; the subtree policies of subtree policies are ignored.
+    pub subtrees: HashMap<ScopeId, OutputPolicy>,
+    /// How outputs are picked.
+    pub strategy: Strategy,
+    /// The characters of minified outputs, except the first, see `Strategy::Minify`.
+    /// They should all be allowed.
+    pub alphabet: String,
+    /// The characters that minified outputs start with.
+    pub start_alphabet: String,
+    /// Weights of alphabet characters, e.g. for those that compress better or are cheaper in
+    /// the target encoding; others weigh 0. Minified outputs of each length try heavier
+    /// characters first, so the most used names get them, see `Strategy::Minify`.
+    pub alphabet_weights: HashMap<char, i32>,
+    /// What is appended to descriptive outputs to make them unique.
+    pub suffix: SuffixScheme,
+    /// When two outputs count as the same, for outputs and keywords.
+    pub comparison: OutputComparison,
+    /// If set, outputs that are keywords are put between these characters instead of being
+    /// avoided, like `"order"` in SQL.
+    pub quote: Option<(char, char)>,
+    /// Patterns that outputs must not match, like reserved prefixes of the target language.
+    /// Generation fails for names whose candidates all match, e.g. if a forbidden prefix is
+    /// part of the text.
+    pub forbidden: Vec<OutputPattern>,
+    /// The priority of a name: names with a higher priority get their outputs first, so they
+    /// get first pick of their preferred and shortest outputs, while names with a lower one,
+    /// like temporaries, are renamed instead. By default all names have priority 0.
+    pub priority: fn(&Name) -> i32,
+    /// If set, anonymous names are based on their prefix, the label of their scope and their
+    /// ordinal, separated by this character, like `tmp$fnFoo$3`, to make generated code
+    /// easier to debug; pools are not used then. The separator should be allowed.
+    pub decorate_anonymous: Option<char>,
+    /// If set, applied to every candidate output before it is shortened, quoted and checked,
+    /// e.g. to add a project-wide prefix, so that formatted outputs are still unique.
+    pub formatter: Option<NameFormatter>,
+    /// If set, outputs must differ within this many characters, for tools that ignore the
+    /// rest. Suffixes are put within this length; generation fails if it runs out of such
+    /// outputs. Only that of the policy passed to `RootScope::generate_names` is used.
+    pub significant_length: Option<usize>,
+    /// If set, names bound to external symbols (see `Name::bind_external`) get the symbol
+    /// with this prefix as output, for a thunk that wraps the symbol; otherwise they get the
+    /// symbol itself. Either way, other names avoid it where it is visible.
+    pub external_thunk: Option<String>,
+    /// If set, names with an arity (see `Scope::add_with_arity`) get it appended after this
+    /// separator, like `foo_2` for `foo/2`, instead of only a suffix when they collide. The
+    /// separator should be allowed.
+    pub arity_separator: Option<char>,
+}
+
+/// Turns a candidate output of a name into the output to use, see `OutputPolicy::formatter`.
+pub type NameFormatter = fn(&Name, String) -> String;
+
+/// A pattern of outputs to avoid, see `OutputPolicy::forbidden`.
+#[derive(Debug, Clone)]
+pub enum OutputPattern {
+    Prefix(String),
+    Suffix(String),
+    /// Outputs with a match anywhere; anchor the expression to match the whole output.
+    #[cfg(feature = "regex")]
+    Regex(::regex::Regex),
+}
+
+impl OutputPattern {
+    pub fn matches(&self, output: &str) -> bool {
+        match self {
+            OutputPattern::Prefix(prefix) => output.starts_with(prefix.as_str()),
+            OutputPattern::Suffix(suffix) => output.ends_with(suffix.as_str()),
+            #[cfg(feature = "regex")]
+            OutputPattern::Regex(regex) => regex.is_match(output),
+        }
+    }
+}
+
+/// When outputs count as the same, from strictest to loosest. Case-insensitive scopes use
+/// at least `IgnoreCase`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum OutputComparison {
+    /// Outputs are the same if they have the same characters.
+    #[default]
+    Exact,
+    /// Outputs are the same if they are in lowercase.
+    IgnoreCase,
+    /// Outputs are the same after Unicode compatibility normalization (NFKC) and in
+    /// lowercase, so e.g. `ﬁle` and `file`, or composed and decomposed `Å`, are the same.
+    #[cfg(feature = "normalization")]
+    Normalized,
+}
+
+impl OutputComparison {
+    /// The form of the text that is the same for outputs that count as the same.
+    pub(crate) fn key(self, text: &str) -> String {
+        match self {
+            OutputComparison::Exact => text.to_owned(),
+            OutputComparison::IgnoreCase => text.to_lowercase(),
+            #[cfg(feature = "normalization")]
+            OutputComparison::Normalized => {
+                use ::unicode_normalization::UnicodeNormalization;
+                text.nfkc().collect::<String>().to_lowercase()
+            }
+        }
+    }
+
+    /// This comparison, or `IgnoreCase` if that is looser and the scope ignores case.
+    pub(crate) fn in_scope(self, is_case_insensitive: bool) -> Self {
+        if is_case_insensitive {
+            self.max(OutputComparison::IgnoreCase)
+        } else {
+            self
+        }
+    }
+
+    // All comparisons, from strictest to loosest.
+    fn all() -> Vec<Self> {
+        #[allow(unused_mut)]
+        let mut all = vec![OutputComparison::Exact, OutputComparison::IgnoreCase];
+        #[cfg(feature = "normalization")]
+        all.push(OutputComparison::Normalized);
+        all
+    }
+}
+
+/// How descriptive outputs are made unique, if the text itself is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuffixScheme {
+    /// `x2`, `x3`, etc., or `v1_2` if the text ends in a digit.
+    #[default]
+    Numeric,
+    /// `x_b`, `x_c`, ... `x_z`, `x_ba`, etc.
+    Alphabetic,
+    /// An underscore and three hexadecimal digits that depend on the text, like `x_9f3`,
+    /// with more digits after the first 4096 attempts.
+    Hash,
+}
+
+impl SuffixScheme {
+    /// The base itself, then with increasing suffixes appended.
+    pub(crate) fn candidates(self, base: &str) -> Box<dyn Iterator<Item = String> + '_> {
+        let suffixed = move |number: u64| match self {
+            SuffixScheme::Numeric => unreachable!(),
+            SuffixScheme::Alphabetic => {
+                let mut letters = vec![];
+                let mut rest = number;
+                while letters.is_empty() || rest > 0 {
+                    letters.push((b'a' + (rest % 26) as u8) as char);
+                    rest /= 26;
+                }
+                letters.reverse();
+                format!("{}_{}", base, letters.into_iter().collect::<String>())
+            }
+            SuffixScheme::Hash => {
+                let mut hasher = StableHasher::new();
+                hasher.write_str(base);
+                hasher.write_u64(number);
+                let digits = (3 + (number >> 12) as usize).min(16);
+                format!(
+                    "{}_{}",
+                    base,
+                    &format!("{:016x}", hasher.finish())[..digits]
+                )
+            }
+        };
+        match self {
+            SuffixScheme::Numeric => Box::new(candidates(base)),
+            _ => Box::new(once(base.to_owned()).chain((1..).map(suffixed))),
+        }
+    }
+}
+
+impl Default for OutputPolicy {
+    /// Ascii letters, digits and underscores, not starting with a digit, without keywords,
+    /// with descriptive outputs.
+    fn default() -> Self {
+        OutputPolicy {
+            allowed: |c| c.is_ascii_alphanumeric() || c == '_',
+            allowed_start: |c| c.is_ascii_alphabetic() || c == '_',
+            keywords: HashSet::new(),
+            anonymous_base: "v".to_owned(),
+            max_length: None,
+            order: GenerationOrder::default(),
+            namespaces: HashMap::new(),
+            pools: HashMap::new(),
+            subtrees: HashMap::new(),
+            strategy: Strategy::default(),
+            alphabet: LETTERS.chars().chain('0'..='9').chain(once('_')).collect(),
+            start_alphabet: LETTERS.to_owned(),
+            alphabet_weights: HashMap::new(),
+            suffix: SuffixScheme::default(),
+            comparison: OutputComparison::default(),
+            quote: None,
+            forbidden: vec![],
+            priority: |_| 0,
+            decorate_anonymous: None,
+            formatter: None,
+            significant_length: None,
+            external_thunk: None,
+            arity_separator: None,
+        }
+    }
+}
+
+impl OutputPolicy {
+    pub fn with_keywords(mut self, keywords: &[&str]) -> Self {
+        self.keywords
+            .extend(keywords.iter().map(|keyword| (*keyword).to_owned()));
+        self
+    }
+
+    /// Use a different policy for names in the namespace, see `Scope::namespace`.
+    pub fn with_namespace(mut self, namespace: &str, policy: OutputPolicy) -> Self {
+        self.namespaces.insert(namespace.to_owned(), policy);
+        self
+    }
+
+    /// Prefer the outputs, in order, for anonymous names with the prefix.
+    pub fn with_pool(mut self, prefix: &str, outputs: &[&str]) -> Self {
+        self.pools.insert(
+            prefix.to_owned(),
+            outputs.iter().map(|output| (*output).to_owned()).collect(),
+        );
+        self
+    }
+
+    /// Avoid outputs that match the pattern.
+    pub fn with_forbidden(mut self, pattern: OutputPattern) -> Self {
+        self.forbidden.push(pattern);
+        self
+    }
+
+    /// Use a different policy for the scope and its descendants, unless a descendant has
+    /// its own. The scope's tree should be the one that outputs are generated for.
+    pub fn with_subtree(mut self, scope: &Scope, policy: OutputPolicy) -> Self {
+        self.subtrees.insert(scope.id(), policy);
+        self
+    }
+
+    /// The policy for names in the namespace, or in the value namespace for None.
+    pub fn for_namespace(&self, namespace: Option<&str>) -> &OutputPolicy {
+        namespace
+            .and_then(|namespace| self.namespaces.get(namespace))
+            .unwrap_or(self)
+    }
+
+    /// The policy for the subtree that the scope is in, ignoring namespaces.
+    pub fn for_scope(&self, scope: &Scope) -> &OutputPolicy {
+        if self.subtrees.is_empty() {
+            return self;
+        }
+        successors(Some(scope.clone()), Scope::parent)
+            .find_map(|ancestor| self.subtrees.get(&ancestor.id()))
+            .unwrap_or(self)
+    }
+
+    /// The policy for the name, based on its scope and namespace.
+    pub fn for_name(&self, name: &Name) -> &OutputPolicy {
+        self.for_scope(&name.scope())
+            .for_namespace(output_namespace(name))
+    }
+
+    /// The text with disallowed characters replaced, before making it unique.
+    pub(crate) fn sanitize(&self, text: &str) -> String {
+        let mut output: String = text
+            .chars()
+            .map(|c| if (self.allowed)(c) { c } else { '_' })
+            .collect();
+        if !output.chars().next().is_some_and(self.allowed_start) {
+            output.insert(0, '_');
+        }
+        output
+    }
+
+    /// The text that the outputs of the name are based on: its sanitized text, or that of
+    /// its prefix, see `related_text` for derived names.
+    pub(crate) fn base_output(
+        &self,
+        name: &Name,
+        origin_output: impl Fn(&Name) -> Option<OutputName>,
+    ) -> String {
+        let policy = self.for_name(name);
+        match &name.data {
+            InputName::Given(given) => policy.sanitize(
+                &policy.with_arity(name, policy.related(name, given.as_str(), origin_output)),
+            ),
+            InputName::Anonymous(anon) => {
+                let prefix = match anon.prefix() {
+                    Some(prefix) if !prefix.is_empty() => prefix,
+                    _ => policy.anonymous_base.as_str(),
+                };
+                match policy.decorate_anonymous {
+                    Some(separator) => policy.sanitize(&format!(
+                        "{}{}{}{}{}",
+                        prefix,
+                        separator,
+                        name.scope().label().map_or("", |label| label.as_str()),
+                        separator,
+                        name.anonymous_ordinal().unwrap_or(0)
+                    )),
+                    None => policy.sanitize(prefix),
+                }
+            }
+        }
+    }
+
+    /// The text with the arity of the name appended, see `OutputPolicy::arity_separator`.
+    pub(crate) fn with_arity(&self, name: &Name, text: String) -> String {
+        let arity = match &name.data {
+            InputName::Given(given) => given.arity(),
+            InputName::Anonymous(_) => None,
+        };
+        match (self.arity_separator, arity) {
+            (Some(separator), Some(arity)) => format!("{}{}{}", text, separator, arity),
+            _ => text,
+        }
+    }
+
+    /// The text of a given name, with the output of its origin in place of the origin's text,
+    /// see `related_text`, unless the name keeps its text.
+    pub(crate) fn related(
+        &self,
+        name: &Name,
+        text: &str,
+        origin_output: impl Fn(&Name) -> Option<OutputName>,
+    ) -> String {
+        match self.strategy.for_name(name) {
+            (Strategy::Preserve, _) => text.to_owned(),
+            _ => related_text(name, text, origin_output),
+        }
+    }
+
+    /// The outputs to try for the name, in order: those from its pool, then those of the
+    /// strategy, with suffixes within the significant length, formatted, and shortened and
+    /// quoted where needed. Ends when no more outputs fit in the significant length.
+    pub(crate) fn candidates_for<'a>(
+        &'a self,
+        name: &Name,
+        base: &'a str,
+    ) -> impl Iterator<Item = String> + 'a {
+        let policy = self.for_name(name);
+        let name = name.clone();
+        let pool = match &name.data {
+            InputName::Anonymous(_) if policy.decorate_anonymous.is_some() => None,
+            InputName::Anonymous(anon) => {
+                self.for_scope(&name.scope()).pools.get(anon.name.as_str())
+            }
+            InputName::Given(_) => None,
+        };
+        let (strategy, prefix) = policy.strategy.for_name(&name);
+        let pool = pool.into_iter().flatten().cloned().map(Some);
+        let prefixed = strategy
+            .candidates(base, policy)
+            .map(move |candidate| self.within_significant(&prefix, candidate, base));
+        pool.chain(prefixed)
+            .map_while(|candidate| candidate)
+            .map(move |candidate| policy.quote_keyword(policy.fit(policy.format(&name, candidate))))
+    }
+
+    pub(crate) fn format(&self, name: &Name, output: String) -> String {
+        match self.formatter {
+            Some(formatter) => formatter(name, output),
+            None => output,
+        }
+    }
+
+    // The candidate after the prefix, with its suffix moved within the significant length by
+    // dropping the end of the base, or None if it does not fit. The base itself is kept whole.
+    fn within_significant(&self, prefix: &str, candidate: String, base: &str) -> Option<String> {
+        let prefix_length = prefix.chars().count();
+        let length = match self.significant_length {
+            Some(length)
+                if candidate != base && prefix_length + candidate.chars().count() > length =>
+            {
+                length.saturating_sub(prefix_length)
+            }
+            _ => return Some(format!("{}{}", prefix, candidate)),
+        };
+        let suffix = candidate.strip_prefix(base)?;
+        let kept = length.checked_sub(suffix.chars().count())?;
+        if kept == 0 {
+            return None;
+        }
+        Some(
+            prefix
+                .chars()
+                .chain(base.chars().take(kept))
+                .chain(suffix.chars())
+                .collect(),
+        )
+    }
+
+    // The part of the output that counts for uniqueness.
+    pub(crate) fn significant<'b>(&self, output: &'b str) -> &'b str {
+        match self.significant_length {
+            Some(length) => match output.char_indices().nth(length) {
+                Some((end, _)) => &output[..end],
+                None => output,
+            },
+            None => output,
+        }
+    }
+
+    /// The output shortened to the maximum length if it is too long, see the module docs.
+    pub(crate) fn fit(&self, output: String) -> String {
+        let max_length = match self.max_length {
+            Some(max_length) if output.chars().count() > max_length => max_length,
+            _ => return output,
+        };
+        let mut hasher = StableHasher::new();
+        hasher.write_str(&output);
+        let hash = format!("{:016x}", hasher.finish());
+        let kept: String = output
+            .chars()
+            .take(max_length.saturating_sub(HASH_LENGTH + 1))
+            .collect();
+        format!("{}_{}", kept, &hash[..HASH_LENGTH])
+            .chars()
+            .take(max_length)
+            .collect()
+    }
+
+    /// The output between the quote characters if it is a keyword and keywords are quoted.
+    pub(crate) fn quote_keyword(&self, output: String) -> String {
+        match self.quote {
+            Some((open, close)) if self.is_keyword(&output, false) => {
+                format!("{}{}{}", open, output, close)
+            }
+            _ => output,
+        }
+    }
+
+    pub(crate) fn is_forbidden(&self, output: &str) -> bool {
+        self.forbidden.iter().any(|pattern| pattern.matches(output))
+    }
+
+    pub(crate) fn is_keyword(&self, text: &str, is_case_insensitive: bool) -> bool {
+        let comparison = self.comparison.in_scope(is_case_insensitive);
+        if comparison == OutputComparison::Exact {
+            return self.keywords.contains(text);
+        }
+        let text = comparison.key(text);
+        self.keywords
+            .iter()
+            .any(|keyword| comparison.key(keyword) == text)
+    }
+}
+
+pub(crate) const LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+// The number of hexadecimal digits of the hash of shortened outputs.
+const HASH_LENGTH: usize = 6;
+
+// The number of forbidden candidates in a row after which a name is given up on, since
+// patterns like prefixes can match every candidate.
+const FORBIDDEN_LIMIT: usize = 100;
+
+/// The outputs of all names in a tree, created by `RootScope::generate_names`.
+#[derive(Debug, Clone)]
+pub struct NameMap {
+    pub(crate) root: RootScope,
+    pub(crate) policy: OutputPolicy,
+    // If set, only the names active in this view have outputs, see `ConfigView::generate_names`.
+    pub(crate) view: Option<ConfigView>,
+    // Keyed by arena index of the name, so that iteration is in registration order.
+    pub(crate) outputs: BTreeMap<usize, OutputName>,
+}
+
+impl NameMap {
+    /// The output of the name, or None if it is from another tree, or was not in the tree
+    /// (or was in a detached scope) during generation.
+    pub fn get(&self, name: &Name) -> Option<&OutputName> {
+        if name.root.id() != self.root.id() {
+            return None;
+        }
+        self.outputs.get(&name.index)
+    }
+
+    /// The names and their outputs, in registration order; removed names are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (Name, &OutputName)> + '_ {
+        self.outputs
+            .iter()
+            .filter_map(move |(index, output)| Some((self.root.name(NameId(*index))?, output)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Generate new outputs for the names in the subtree of the scope, e.g. after recompiling
+    /// one function, and keep those of all other names. Outputs outside the subtree are
+    /// fixed, so new outputs avoid them where they are visible. Names outside the subtree
+    /// that have no output, e.g. because they were added after generation, do not get one.
+    pub fn generate_subtree(&mut self, scope: &Scope) -> Result<(), GenerateError> {
+        let mut generator = Generator::new(&self.root, &self.policy)?;
+        generator.subtree = Some(scope.clone());
+        generator.view = self.view.clone();
+        generator.assign_external(&self.root);
+        for (name, output) in self.iter() {
+            if name.scope().preorder_index().is_none() || name.scope().is_in_subtree_of(scope) {
+                continue;
+            }
+            let key = generator.key(&name, &output.0);
+            let visible_in = generator.visible_in(&name);
+            if visible_in.len() > 1 {
+                generator.pinned.insert(&key, &visible_in);
+            }
+            if let Some(&class) = generator.class_of.get(&name.index) {
+                generator.class_outputs.insert(class, output.0.clone());
+            }
+            generator.outputs.insert(name.index, output.clone());
+        }
+        generator.assign_shared(&self.root)?;
+        generator.assign_passes(&self.root)?;
+        self.outputs = generator.outputs;
+        self.root.cache_outputs(self);
+        Ok(())
+    }
+
+    /// The outputs by path of the name, to seed a later run, see `RootScope::generate_seeded`.
+    pub fn path_table(&self) -> HashMap<String, String> {
+        self.iter()
+            .map(|(name, output)| (name.to_string(), output.0.clone()))
+            .collect()
+    }
+
+    /// Replace the output of a name by a hand-picked one, also for `Name::output`, after checking that it is valid
+    /// according to the policy, and does not collide in the scopes where the name is
+    /// visible, i.e. with names of its own scope, its ancestors and its descendants.
+    /// Names unified with it (see `RootScope::unify`) get the same output, so it must not
+    /// collide where those are visible either.
+    pub fn override_output(&mut self, name: &Name, output: &str) -> Result<(), InvalidOverride> {
+        if self.get(name).is_none() {
+            return Err(InvalidOverride::NotGenerated);
+        }
+        let policy = self.policy.for_name(name);
+        let mut chars = output.chars();
+        let is_valid = chars.next().is_some_and(policy.allowed_start) && chars.all(policy.allowed);
+        if !is_valid {
+            return Err(InvalidOverride::InvalidCharacters);
+        }
+        let unified: Vec<Name> = name
+            .unified_names()
+            .into_iter()
+            .filter(|member| self.get(member).is_some())
+            .collect();
+        for member in &unified {
+            check_output(&self.policy, member, output, &unified, |other| {
+                self.get(other).cloned()
+            })?;
+        }
+        for member in unified {
+            self.outputs
+                .insert(member.index, OutputName(output.to_owned()));
+            self.root.root_data.names.borrow_mut()[member.index].output =
+                Some(OutputName(output.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+// Check that the output is allowed for the name, given the outputs that other names have,
+// and ignoring the outputs of the names that will get the same output.
+pub(crate) fn check_output(
+    policy: &OutputPolicy,
+    name: &Name,
+    output: &str,
+    unified: &[Name],
+    output_of: impl Fn(&Name) -> Option<OutputName>,
+) -> Result<(), InvalidOverride> {
+    let root_policy = policy;
+    let scope = name.scope();
+    let descendants: Vec<Scope> = scope.descendants().collect();
+    let is_reserved = scope.is_reserved(output)
+        || descendants
+            .iter()
+            .any(|descendant| descendant.reserved().iter().any(|text| text == output));
+    let policy = policy.for_name(name);
+    let is_keyword = once(&scope)
+        .chain(&descendants)
+        .any(|visible| policy.is_keyword(output, visible.is_case_insensitive()));
+    if is_reserved || is_keyword || policy.is_forbidden(output) {
+        return Err(InvalidOverride::Reserved);
+    }
+    // Outer names are compared using the case rules of this scope, inner names using
+    // those of their own scope, since that is where both are visible.
+    let mut outer = vec![];
+    let mut current = Some(scope.clone());
+    while let Some(ancestor) = current {
+        outer.extend(ancestor.names());
+        current = ancestor.parent();
+    }
+    let comparison = policy.comparison;
+    let is_case_insensitive = scope.is_case_insensitive();
+    let outer = outer.into_iter().map(|other| (other, is_case_insensitive));
+    let inner = descendants.iter().flat_map(|descendant| {
+        let is_case_insensitive = descendant.is_case_insensitive();
+        descendant
+            .names()
+            .into_iter()
+            .map(move |other| (other, is_case_insensitive))
+    });
+    for (other, is_case_insensitive) in outer.chain(inner) {
+        if unified.contains(&other) || output_namespace(&other) != output_namespace(name) {
+            continue;
+        }
+        let other_output = match output_of(&other) {
+            Some(other_output) => other_output,
+            None => continue,
+        };
+        let comparison = comparison.in_scope(is_case_insensitive);
+        let other_output = root_policy.significant(other_output.as_str());
+        if comparison.key(other_output) == comparison.key(root_policy.significant(output)) {
+            return Err(InvalidOverride::Conflict(other));
+        }
+    }
+    Ok(())
+}
+
+impl Name {
+    /// The output of this name from the latest `RootScope::generate_names` on its tree, or
+    /// from `NameMap::override_output`, or None if it got none. This is a lookup, so it is
+    /// cheap to call often. Changes to the tree after generation do not update it.
+    ///
+    /// After `RootScope::generate_lazily`, a name without output gets one now.
+    pub fn output(&self) -> Option<OutputName> {
+        self.cached_output()
+            .or_else(|| self.root.assign_lazily(self))
+    }
+
+    pub(crate) fn cached_output(&self) -> Option<OutputName> {
+        self.root.root_data.names.borrow()[self.index]
+            .output
+            .clone()
+    }
+}
+
+/// Why `RootScope::generate_names` failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerateError {
+    /// Unified names can no longer share an output.
+    NotUnifiable(NotUnifiable),
+    /// None of the candidates for the name is free, e.g. because the significant length
+    /// or the alphabet leaves too few outputs.
+    Exhausted(Name),
+}
+
+impl From<NotUnifiable> for GenerateError {
+    fn from(error: NotUnifiable) -> Self {
+        GenerateError::NotUnifiable(error)
+    }
+}
+
+/// Why `NameMap::override_output` rejected an output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidOverride {
+    /// The name did not get an output during generation.
+    NotGenerated,
+    /// The output is empty or has characters that the policy does not allow.
+    InvalidCharacters,
+    /// The output is a keyword, matches a forbidden pattern, or is reserved where the name
+    /// is visible.
+    Reserved,
+    /// Another name has the output, and one of them is visible where the other is declared.
+    Conflict(Name),
+}
+
+// Names in different namespaces never conflict.
+pub(crate) fn output_namespace(name: &Name) -> Option<&str> {
+    match &name.data {
+        InputName::Given(given) => given.namespace(),
+        InputName::Anonymous(_) => None,
+    }
+}
+
+// Namespace and output text.
+type OutputKey = (Option<String>, String);
+
+// The outputs of the current scope and its ancestors, counted since siblings may share.
+// Each output is counted under its key for every comparison.
+#[derive(Default)]
+struct TakenOutputs {
+    counts: HashMap<(OutputComparison, OutputKey), usize>,
+}
+
+impl TakenOutputs {
+    fn contains(&self, key: &OutputKey, comparison: OutputComparison) -> bool {
+        self.counts
+            .contains_key(&(comparison, compared(key, comparison)))
+    }
+
+    fn insert(&mut self, key: &OutputKey) {
+        for comparison in OutputComparison::all() {
+            *self
+                .counts
+                .entry((comparison, compared(key, comparison)))
+                .or_default() += 1;
+        }
+    }
+
+    fn remove(&mut self, key: &OutputKey) {
+        for comparison in OutputComparison::all() {
+            let counted = (comparison, compared(key, comparison));
+            if let Some(count) = self.counts.get_mut(&counted) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(&counted);
+                }
+            }
+        }
+    }
+}
+
+fn compared(key: &OutputKey, comparison: OutputComparison) -> OutputKey {
+    (key.0.clone(), comparison.key(&key.1))
+}
+
+// Outputs that names must avoid wherever the names that have them are visible, even if
+// they are reached first: those of unified names, and of names that are visible outside
+// their own subtree through imports or mixins.
+#[derive(Default)]
+struct PinnedOutputs {
+    // By namespace and output compared as loosely as possible, the exact outputs and a scope
+    // where each is visible.
+    pins: HashMap<OutputKey, Vec<(String, Scope)>>,
+}
+
+impl PinnedOutputs {
+    fn insert(&mut self, key: &OutputKey, scopes: &[Scope]) {
+        let pins = self.pins.entry(loosest(key)).or_default();
+        pins.extend(scopes.iter().map(|scope| (key.1.clone(), scope.clone())));
+    }
+
+    // Whether the output is pinned in a scope that contains or is inside one of the scopes,
+    // using the comparison, but ignoring case if either of those scopes does.
+    fn blocks(&self, key: &OutputKey, scopes: &[Scope], comparison: OutputComparison) -> bool {
+        self.pins.get(&loosest(key)).is_some_and(|pins| {
+            pins.iter().any(|(output, pinned)| {
+                scopes.iter().any(|scope| {
+                    let comparison = comparison
+                        .in_scope(scope.is_case_insensitive() || pinned.is_case_insensitive());
+                    overlaps(scope, pinned) && comparison.key(output) == comparison.key(&key.1)
+                })
+            })
+        })
+    }
+}
+
+fn loosest(key: &OutputKey) -> OutputKey {
+    compared(key, *OutputComparison::all().last().unwrap())
+}
+
+enum Step {
+    Enter(Scope),
+    // Release the outputs of a scope once its subtree is done.
+    Leave(Vec<OutputKey>),
+}
+
+// The state of one `generate_names` run.
+struct Generator<'a> {
+    policy: &'a OutputPolicy,
+    class_of: HashMap<usize, usize>,
+    classes: Vec<Vec<Name>>,
+    // The outputs of unified names, once the first of them is reached.
+    class_outputs: HashMap<usize, String>,
+    // Per name that is visible outside its own subtree, the other scopes where it is.
+    shared: HashMap<usize, Vec<Scope>>,
+    pinned: PinnedOutputs,
+    // Outputs to prefer, by arena index of the name, see `RootScope::generate_seeded`.
+    seeds: HashMap<usize, String>,
+    // If set, only names in this subtree get outputs, see `NameMap::generate_subtree`.
+    subtree: Option<Scope>,
+    // If set, only names active in this view get outputs, see `ConfigView::generate_names`.
+    view: Option<ConfigView>,
+    // The outputs by how often they were assigned, most first, for `Strategy::Compress`.
+    popular: BTreeSet<(Reverse<usize>, usize, String)>,
+    use_counts: HashMap<String, usize>,
+    // How often each name is referenced, to give the most used ones the shortest outputs.
+    heat: HeatMap,
+    outputs: BTreeMap<usize, OutputName>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(root: &RootScope, policy: &'a OutputPolicy) -> Result<Self, NotUnifiable> {
+        let mut class_of = HashMap::new();
+        let mut classes = vec![];
+        for members in root.unified_classes() {
+            let members: Vec<Name> = members
+                .into_iter()
+                .map(|index| root.name_at(index))
+                .collect();
+            check_unifiable(&members)?;
+            for member in &members {
+                class_of.insert(member.index, classes.len());
+            }
+            classes.push(members);
+        }
+        let mut shared: HashMap<usize, Vec<Scope>> = HashMap::new();
+        let root_scope = root.root_scope();
+        for scope in once(root_scope.clone()).chain(root_scope.descendants()) {
+            for name in scope.visible_names() {
+                if name.scope() != scope {
+                    shared.entry(name.index).or_default().push(scope.clone());
+                }
+            }
+        }
+        Ok(Generator {
+            policy,
+            class_of,
+            classes,
+            class_outputs: HashMap::new(),
+            shared,
+            pinned: PinnedOutputs::default(),
+            seeds: HashMap::new(),
+            subtree: None,
+            view: None,
+            popular: BTreeSet::new(),
+            use_counts: HashMap::new(),
+            heat: root.heat_map(),
+            outputs: BTreeMap::new(),
+        })
+    }
+
+    // The scopes where the name, or a name unified with it, is declared or otherwise visible;
+    // names visible in their descendants are visible there too.
+    fn visible_in(&self, name: &Name) -> Vec<Scope> {
+        let members = match self.class_of.get(&name.index) {
+            Some(&class) => self.classes[class].clone(),
+            None => vec![name.clone()],
+        };
+        let mut scopes = vec![];
+        for member in members {
+            scopes.push(member.scope());
+            scopes.extend(
+                self.shared
+                    .get(&member.index)
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            );
+        }
+        scopes
+    }
+
+    // Give names their seeded outputs before all others, in preorder, if those are still
+    // free, so that other names cannot take them. The others get fresh outputs later.
+    fn assign_seeded(&mut self, root: &RootScope) -> Result<(), GenerateError> {
+        let root_scope = root.root_scope();
+        for scope in once(root_scope.clone()).chain(root_scope.descendants()) {
+            for name in self.names_for_generation(&scope) {
+                let seed = match self.seeds.get(&name.index) {
+                    Some(seed) if !self.outputs.contains_key(&name.index) => seed,
+                    _ => continue,
+                };
+                let taken = TakenOutputs::default();
+                let visible_in = self.visible_in(&name);
+                let is_case_insensitive = scope.is_case_insensitive();
+                let is_unified = self.class_of.contains_key(&name.index);
+                let is_free = has_allowed_chars(self.policy.for_name(&name), seed)
+                    && self.is_free(&name, seed, &taken, is_case_insensitive, &visible_in);
+                if !is_unified && is_free {
+                    self.assign(&name, &taken, is_case_insensitive)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Give names bound to external symbols their fixed outputs, before any other name, and
+    // keep other names from them where they are visible.
+    fn assign_external(&mut self, root: &RootScope) {
+        let root_scope = root.root_scope();
+        for scope in once(root_scope.clone()).chain(root_scope.descendants()) {
+            for name in self.names_for_generation(&scope) {
+                let external = match name.external() {
+                    Some(external) if self.is_pending(&name) => external,
+                    _ => continue,
+                };
+                let policy = self.policy.for_name(&name);
+                let output = match &policy.external_thunk {
+                    Some(prefix) => format!("{}{}", prefix, external.symbol),
+                    None => external.symbol.to_string(),
+                };
+                let visible_in = self.visible_in(&name);
+                self.pinned.insert(&self.key(&name, &output), &visible_in);
+                if let Some(&class) = self.class_of.get(&name.index) {
+                    self.class_outputs.insert(class, output.clone());
+                }
+                self.outputs.insert(name.index, OutputName(output));
+            }
+        }
+    }
+
+    // Assign outputs to the names that are visible outside their own subtree first, since
+    // they may be visible in scopes that are reached before their own.
+    fn assign_shared(&mut self, root: &RootScope) -> Result<(), GenerateError> {
+        let root_scope = root.root_scope();
+        for scope in once(root_scope.clone()).chain(root_scope.descendants()) {
+            for name in self.names_for_generation(&scope) {
+                let is_shared = self.visible_in(&name).len() > 1
+                    && self.class_of.get(&name.index).is_none_or(|&class| {
+                        self.classes[class]
+                            .iter()
+                            .any(|member| self.shared.contains_key(&member.index))
+                    });
+                if is_shared && self.is_pending(&name) {
+                    self.assign(&name, &TakenOutputs::default(), scope.is_case_insensitive())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Assign outputs to the other names, one pass at a time, and pin the outputs of each pass
+    // wherever the names are visible, so that later passes cannot take them from inner scopes.
+    fn assign_passes(&mut self, root: &RootScope) -> Result<(), GenerateError> {
+        let root_scope = root.root_scope();
+        let names: Vec<Name> = once(root_scope.clone())
+            .chain(root_scope.descendants())
+            .flat_map(|scope| scope.names())
+            .collect();
+        let mut passes: Vec<(i32, bool)> = names.iter().map(|name| self.pass_of(name)).collect();
+        passes.sort_unstable_by(|first, second| second.cmp(first));
+        passes.dedup();
+        for (nr, &pass) in passes.iter().enumerate() {
+            self.assign_tree(root, pass)?;
+            if nr + 1 == passes.len() {
+                break;
+            }
+            for name in &names {
+                if self.pass_of(name) != pass || !self.is_in_subtree(name) {
+                    continue;
+                }
+                let key = self.key(name, &self.outputs[&name.index].0);
+                let visible_in = self.visible_in(name);
+                self.pinned.insert(&key, &visible_in);
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the name still needs an output in this run.
+    fn is_pending(&self, name: &Name) -> bool {
+        !self.outputs.contains_key(&name.index) && self.is_in_subtree(name)
+    }
+
+    // Whether the name is in the part of the tree that outputs are generated for.
+    fn is_in_subtree(&self, name: &Name) -> bool {
+        self.subtree
+            .as_ref()
+            .is_none_or(|subtree| name.scope().is_in_subtree_of(subtree))
+    }
+
+    // The pass in which the name gets its output; higher passes go first. By priority, then
+    // given names that keep their text before others, see `Strategy::Preserve`.
+    fn pass_of(&self, name: &Name) -> (i32, bool) {
+        let policy = self.policy.for_name(name);
+        let is_preserved = matches!(name.data, InputName::Given(_))
+            && matches!(policy.strategy.for_name(name).0, Strategy::Preserve);
+        ((policy.priority)(name), is_preserved)
+    }
+
+    // Assign outputs to the names of one pass, visiting scopes in preorder.
+    fn assign_tree(&mut self, root: &RootScope, pass: (i32, bool)) -> Result<(), GenerateError> {
+        let mut taken = TakenOutputs::default();
+        let mut pending = vec![Step::Enter(root.root_scope())];
+        while let Some(step) = pending.pop() {
+            let scope = match step {
+                Step::Enter(scope) => scope,
+                Step::Leave(keys) => {
+                    keys.iter().for_each(|key| taken.remove(key));
+                    continue;
+                }
+            };
+            let is_case_insensitive = scope.is_case_insensitive();
+            let mut keys = vec![];
+            for name in self.names_for_generation(&scope) {
+                let key = match self.outputs.get(&name.index) {
+                    Some(output) => self.key(&name, &output.0),
+                    None if self.pass_of(&name) != pass || !self.is_in_subtree(&name) => continue,
+                    None => self.assign(&name, &taken, is_case_insensitive)?,
+                };
+                taken.insert(&key);
+                keys.push(key);
+            }
+            pending.push(Step::Leave(keys));
+            let mut children: Vec<Scope> = scope.children().collect();
+            children.reverse();
+            pending.extend(children.into_iter().map(Step::Enter));
+        }
+        Ok(())
+    }
+
+    // The names of the scope in the order they get outputs, without inactive ones; for
+    // minified ones the most used first, so they get the shortest outputs.
+    fn names_for_generation(&self, scope: &Scope) -> Vec<Name> {
+        let scope_policy = self.policy.for_scope(scope);
+        let mut names = names_for_generation(scope, scope_policy.order);
+        if let Some(view) = &self.view {
+            names.retain(|name| view.is_active(name));
+        }
+        if matches!(scope_policy.strategy, Strategy::Minify | Strategy::Compress) {
+            names.sort_by_key(|name| Reverse(self.heat.name_uses(name)));
+        }
+        names
+    }
+
+    // Pick the output of a name, given the outputs taken on the way to its scope.
+    fn assign(
+        &mut self,
+        name: &Name,
+        taken: &TakenOutputs,
+        is_case_insensitive: bool,
+    ) -> Result<OutputKey, GenerateError> {
+        let name_policy = self.policy.for_name(name);
+        let base = self
+            .policy
+            .base_output(name, |origin| self.outputs.get(&origin.index).cloned());
+        let class = self.class_of.get(&name.index).cloned();
+        let visible_in = self.visible_in(name);
+        let is_free = |candidate: &str| {
+            self.is_free(name, candidate, taken, is_case_insensitive, &visible_in)
+        };
+        let is_compressed = matches!(name_policy.strategy.for_name(name).0, Strategy::Compress);
+        let popular = self
+            .popular
+            .iter()
+            .take_while(|_| is_compressed)
+            .map(|(_, _, output)| output.clone())
+            .filter(|output| has_allowed_chars(name_policy, output));
+        let mut forbidden_run = 0;
+        let output = match class.and_then(|class| self.class_outputs.get(&class)) {
+            Some(output) => output.clone(),
+            None => self
+                .seeds
+                .get(&name.index)
+                .cloned()
+                .filter(|seed| has_allowed_chars(name_policy, seed))
+                .into_iter()
+                .chain(popular)
+                .chain(self.policy.candidates_for(name, &base))
+                .take_while(|candidate| {
+                    forbidden_run = match name_policy.is_forbidden(candidate) {
+                        true => forbidden_run + 1,
+                        false => 0,
+                    };
+                    forbidden_run <= FORBIDDEN_LIMIT
+                })
+                .find(|candidate| is_free(candidate))
+                .ok_or_else(|| GenerateError::Exhausted(name.clone()))?,
+        };
+        let key = self.key(name, &output);
+        let is_first = match class {
+            Some(class) => match self.class_outputs.entry(class) {
+                Entry::Vacant(entry) => {
+                    entry.insert(output.clone());
+                    true
+                }
+                Entry::Occupied(_) => false,
+            },
+            None => visible_in.len() > 1 || self.seeds.contains_key(&name.index),
+        };
+        if is_first {
+            self.pinned.insert(&key, &visible_in);
+        }
+        let count = self.use_counts.entry(output.clone()).or_default();
+        self.popular
+            .remove(&(Reverse(*count), output.len(), output.clone()));
+        *count += 1;
+        self.popular
+            .insert((Reverse(*count), output.len(), output.clone()));
+        self.outputs.insert(name.index, OutputName(output));
+        Ok(key)
+    }
+
+    // Whether the candidate is not a keyword, forbidden or reserved, and not taken where the
+    // name is visible.
+    fn is_free(
+        &self,
+        name: &Name,
+        candidate: &str,
+        taken: &TakenOutputs,
+        is_case_insensitive: bool,
+        visible_in: &[Scope],
+    ) -> bool {
+        let name_policy = self.policy.for_name(name);
+        let comparison = name_policy.comparison;
+        let key = self.key(name, candidate);
+        !taken.contains(&key, comparison.in_scope(is_case_insensitive))
+            && !self.pinned.blocks(&key, visible_in, comparison)
+            && !name_policy.is_forbidden(candidate)
+            && visible_in.iter().all(|scope| {
+                !name_policy.is_keyword(candidate, scope.is_case_insensitive())
+                    && !scope.is_reserved(candidate)
+            })
+    }
+
+    // The namespace and significant part of the output, by which outputs are compared.
+    fn key(&self, name: &Name, output: &str) -> OutputKey {
+        (
+            output_namespace(name).map(str::to_owned),
+            self.policy.significant(output).to_owned(),
+        )
+    }
+}
+
+impl RootScope {
+    /// Assign an output to every name in the tree; names in detached scopes are skipped.
+    ///
+    /// Unified names (see `RootScope::unify`) get the same output. Fails if some of them
+    /// can no longer share one, because names were moved after they were unified, or if a
+    /// name runs out of outputs, e.g. within the significant length.
+    pub fn generate_names(&self, policy: &OutputPolicy) -> Result<NameMap, GenerateError> {
+        let map = self.generate_uncached(policy)?;
+        self.cache_outputs(&map);
+        Ok(map)
+    }
+
+    /// Like `generate_names`, but names keep their output from an earlier run where it is
+    /// still valid, so that published outputs stay stable across releases. The table maps
+    /// paths of names (see `Name`'s `Display`) to outputs, like `NameMap::path_table`.
+    ///
+    /// Seeded names are handled first, in preorder, so a seeded name only loses its output
+    /// if it became invalid, e.g. a keyword, or is taken by another seeded name visible in
+    /// the same scope; then it gets a fresh output like new names. Unified names are not
+    /// seeded.
+    pub fn generate_seeded(
+        &self,
+        policy: &OutputPolicy,
+        previous: &HashMap<String, String>,
+    ) -> Result<NameMap, GenerateError> {
+        let seeds = previous
+            .iter()
+            .filter_map(|(path, output)| Some((self.resolve_str(path)?.index, output.clone())))
+            .collect();
+        let map = self.generate_with_seeds(policy, seeds, None)?;
+        self.cache_outputs(&map);
+        Ok(map)
+    }
+
+    // Like `generate_names`, but without keeping the outputs for `Name::output`.
+    pub(crate) fn generate_uncached(
+        &self,
+        policy: &OutputPolicy,
+    ) -> Result<NameMap, GenerateError> {
+        self.generate_with_seeds(policy, HashMap::new(), None)
+    }
+
+    pub(crate) fn generate_with_seeds(
+        &self,
+        policy: &OutputPolicy,
+        seeds: HashMap<usize, String>,
+        view: Option<ConfigView>,
+    ) -> Result<NameMap, GenerateError> {
+        let mut generator = Generator::new(self, policy)?;
+        generator.seeds = seeds;
+        generator.view = view.clone();
+        generator.assign_external(self);
+        generator.assign_seeded(self)?;
+        generator.assign_shared(self)?;
+        generator.assign_passes(self)?;
+        Ok(NameMap {
+            root: self.clone(),
+            policy: policy.clone(),
+            view,
+            outputs: generator.outputs,
+        })
+    }
+
+    // Keep the outputs of the map in the tree, for `Name::output`.
+    pub(crate) fn cache_outputs(&self, map: &NameMap) {
+        let mut names = self.root_data.names.borrow_mut();
+        for index in 0..names.len() {
+            names[index].output = map.outputs.get(&index).cloned();
+        }
+    }
+
+    /// The regions in which `generate_names` keeps outputs unique: for each scope without
+    /// children, the path of scopes from the root down to it. Names that share a region get
+    /// different outputs (within a namespace), while names that share none, like those of
+    /// sibling scopes, may get the same output. Regions are ordered by their last scope in
+    /// preorder; detached scopes are left out.
+    pub fn visibility_regions(&self) -> Vec<Vec<Scope>> {
+        let root = self.root_scope();
+        once(root.clone())
+            .chain(root.descendants())
+            .filter(|scope| scope.children().next().is_none())
+            .map(|leaf| {
+                let mut path: Vec<Scope> = successors(Some(leaf), Scope::parent).collect();
+                path.reverse();
+                path
+            })
+            .collect()
+    }
+}
+
+// The text of a derived name, with the text of its origin replaced by the origin's output if
+// the origin already has one, so that e.g. `get_foo` becomes `get_foo2` if `foo` became `foo2`.
+pub(crate) fn related_text(
+    name: &Name,
+    text: &str,
+    origin_output: impl Fn(&Name) -> Option<OutputName>,
+) -> String {
+    let origin = match name.origin() {
+        Some((origin, _)) => origin,
+        None => return text.to_owned(),
+    };
+    let (origin_text, origin_output) = match (&origin.data, origin_output(&origin)) {
+        (InputName::Given(given), Some(output)) => (given.as_str(), output.0),
+        _ => return text.to_owned(),
+    };
+    if origin_text.is_empty() {
+        return text.to_owned();
+    }
+    text.replacen(origin_text, &origin_output, 1)
+}
+
+// Whether the output only has characters that the policy allows, e.g. for outputs that were
+// not built from the name's own text.
+fn has_allowed_chars(policy: &OutputPolicy, output: &str) -> bool {
+    let mut chars = output.chars();
+    chars.next().is_some_and(policy.allowed_start) && chars.all(policy.allowed)
+}
+
+// Whether one scope is the other or contains it, so names of both are visible in one.
+fn overlaps(first: &Scope, second: &Scope) -> bool {
+    first.is_in_subtree_of(second) || second.is_in_subtree_of(first)
+}
+
+// The names of the scope, given names first, each group in the requested order.
+fn names_for_generation(scope: &Scope, order: GenerationOrder) -> Vec<Name> {
+    let (mut given, mut anonymous): (Vec<Name>, Vec<Name>) = scope
+        .names()
+        .into_iter()
+        .partition(|name| matches!(name.data, InputName::Given(_)));
+    if order == GenerationOrder::ByText {
+        given.sort_by_key(|name| name.local_text().into_owned());
+        anonymous.sort_by_key(|name| name.local_text().into_owned());
+    }
+    given.extend(anonymous);
+    given
+}
+
+// The base itself, then with increasing numbers appended.
+pub(crate) fn candidates(base: &str) -> impl Iterator<Item = String> + '_ {
+    let separator = if base.ends_with(|c: char| c.is_ascii_digit()) {
+        "_"
+    } else {
+        ""
+    };
+    once(base.to_owned()).chain((2..).map(move |number| format!("{}{}{}", base, separator, number)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{
+        GenerateError, InvalidOverride, OutputComparison, OutputPattern, OutputPolicy, SuffixScheme,
+    };
+    use crate::import::ImportConflict;
+    use crate::name::InputName;
+    use crate::scope::RootScope;
+    use crate::strategy::Strategy;
+
+    #[test]
+    fn generate_without_shadowing() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let function = root.add_child();
+        let inner_x = function.add_named("x").unwrap();
+        let tmp = function.add_prefixed("tmp");
+        let other_tmp = function.add_prefixed("tmp");
+        let anon = function.add_anonymous();
+        let sibling = root.add_child();
+        let sibling_x = sibling.add_named("x").unwrap();
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.get(&x).unwrap().as_str(), "x");
+        assert_eq!(map.get(&inner_x).unwrap().as_str(), "x2");
+        assert_eq!(map.get(&tmp).unwrap().as_str(), "tmp");
+        assert_eq!(map.get(&other_tmp).unwrap().as_str(), "tmp2");
+        assert_eq!(map.get(&anon).unwrap().as_str(), "v");
+        // Siblings do not see each other, so they may share outputs.
+        assert_eq!(map.get(&sibling_x).unwrap().as_str(), "x2");
+        let conflicts = root
+            .root()
+            .check_injective(|name| map.get(name).map(|output| output.to_string()));
+        assert!(conflicts.is_empty());
+        assert_eq!(inner_x.output(), map.get(&inner_x).cloned());
+        let late = root.add_named("late").unwrap();
+        assert_eq!(late.output(), None);
+    }
+
+    #[test]
+    fn regions_are_root_to_leaf_paths() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        let first = function.add_child();
+        let second = function.add_child();
+        let other = root.add_child();
+        assert_eq!(
+            root.root().visibility_regions(),
+            vec![
+                vec![root.clone(), function.clone(), first],
+                vec![root.clone(), function, second],
+                vec![root.clone(), other],
+            ]
+        );
+        let leaf = RootScope::new_root();
+        assert_eq!(leaf.root().visibility_regions(), vec![vec![leaf]]);
+    }
+
+    #[test]
+    fn generate_valid_identifiers() {
+        let root = RootScope::new_root();
+        root.reserve("print");
+        let keyword = root.add_named("fn").unwrap();
+        let dashed = root.add_named("a-b").unwrap();
+        let digit = root.add_named("1st").unwrap();
+        let print = root.add_named("print").unwrap();
+        let numbered = root.add_named("v1").unwrap();
+        let child = root.add_child();
+        let inner = child.add_named("v1").unwrap();
+        let policy = OutputPolicy::default().with_keywords(&["fn"]);
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&keyword).unwrap().as_str(), "fn2");
+        assert_eq!(map.get(&dashed).unwrap().as_str(), "a_b");
+        assert_eq!(map.get(&digit).unwrap().as_str(), "_1st");
+        assert_eq!(map.get(&print).unwrap().as_str(), "print2");
+        assert_eq!(map.get(&numbered).unwrap().as_str(), "v1");
+        assert_eq!(map.get(&inner).unwrap().as_str(), "v1_2");
+        let other = RootScope::new_root().add_named("fn").unwrap();
+        assert_eq!(map.get(&other), None);
+    }
+
+    #[test]
+    fn keywords_and_reserved_are_avoided() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("tmp").unwrap();
+        let child = root.add_child();
+        child.reserve("tmp2");
+        child.set_case_insensitive(true);
+        let inner = child.add_named("tmp").unwrap();
+        let shouting = child.add_named("LOOP").unwrap();
+        let sibling = root.add_child();
+        let other = sibling.add_named("tmp2").unwrap();
+        let policy = OutputPolicy::default().with_keywords(&["loop"]);
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&outer).unwrap().as_str(), "tmp");
+        // Reserved in this scope, and a keyword when ignoring case.
+        assert_eq!(map.get(&inner).unwrap().as_str(), "tmp3");
+        assert_eq!(map.get(&shouting).unwrap().as_str(), "LOOP2");
+        // Reservations do not apply to other subtrees.
+        assert_eq!(map.get(&other).unwrap().as_str(), "tmp2");
+    }
+
+    #[test]
+    fn imported_names_are_visible() {
+        let root = RootScope::new_root();
+        let user = root.add_child();
+        let local = user.add_prefixed("f");
+        let module = root.add_child();
+        let f = module.add_named("f").unwrap();
+        user.import_all(&module, ImportConflict::Error).unwrap();
+        let other_user = root.add_child();
+        let other_local = other_user.add_prefixed("f");
+        other_user
+            .import_all(&module, ImportConflict::Error)
+            .unwrap();
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&f).unwrap().as_str(), "f");
+        assert_eq!(map.get(&local).unwrap().as_str(), "f2");
+        assert_eq!(map.get(&other_local).unwrap().as_str(), "f2");
+    }
+
+    #[test]
+    fn mixin_names_are_visible() {
+        let root = RootScope::new_root();
+        let class = root.add_child();
+        let method = class.add_child();
+        let local = method.add_prefixed("size");
+        let base = root.add_child();
+        let mixin = root.add_child();
+        let size = mixin.add_named("size").unwrap();
+        base.add_mixin(&mixin).unwrap();
+        class.add_mixin(&base).unwrap();
+        let unrelated = root.add_child().add_prefixed("size");
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&size).unwrap().as_str(), "size");
+        assert_eq!(map.get(&local).unwrap().as_str(), "size2");
+        assert_eq!(map.get(&unrelated).unwrap().as_str(), "size");
+    }
+
+    #[test]
+    fn policy_per_namespace() {
+        let root = RootScope::new_root();
+        let variable = root.add_named("main-view").unwrap();
+        let class = root.namespace("css").add_named("main-view").unwrap();
+        let keyword = root.namespace("css").add_named("inherit").unwrap();
+        let css = OutputPolicy {
+            allowed: |c| c.is_ascii_alphanumeric() || c == '-' || c == '_',
+            ..OutputPolicy::default()
+        }
+        .with_keywords(&["inherit"]);
+        let policy = OutputPolicy::default().with_namespace("css", css);
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&variable).unwrap().as_str(), "main_view");
+        assert_eq!(map.get(&class).unwrap().as_str(), "main-view");
+        assert_eq!(map.get(&keyword).unwrap().as_str(), "inherit2");
+    }
+
+    #[test]
+    fn policy_per_subtree() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let exported = root.add_child();
+        let inner = exported.add_named("x").unwrap();
+        let dashed = exported.add_child().add_named("a-b").unwrap();
+        let other = root.add_named("a-b").unwrap();
+        let readable = OutputPolicy {
+            allowed: |c| c.is_ascii_alphanumeric() || c == '_' || c == '-',
+            ..OutputPolicy::default()
+        };
+        let policy = OutputPolicy::default().with_subtree(&exported, readable);
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&dashed).unwrap().as_str(), "a-b");
+        assert_eq!(map.get(&other).unwrap().as_str(), "a_b");
+        // Names still avoid outputs across the boundary.
+        assert_eq!(map.get(&outer).unwrap().as_str(), "x");
+        assert_eq!(map.get(&inner).unwrap().as_str(), "x2");
+    }
+
+    #[test]
+    fn long_outputs_are_shortened() {
+        let root = RootScope::new_root();
+        let short = root.add_named("short_name").unwrap();
+        let long = root.add_named("a_very_long_name").unwrap();
+        let child = root.add_child();
+        let same = child.add_named("a_very_long_name").unwrap();
+        let policy = OutputPolicy {
+            max_length: Some(12),
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&short).unwrap().as_str(), "short_name");
+        let long_output = map.get(&long).unwrap().as_str();
+        assert_eq!(long_output.len(), 12);
+        assert!(long_output.starts_with("a_ver_"));
+        let same_output = map.get(&same).unwrap().as_str();
+        assert_eq!(same_output.len(), 12);
+        assert_ne!(same_output, long_output);
+        let again = root.root().generate_names(&policy).unwrap();
+        assert_eq!(again.get(&long).unwrap().as_str(), long_output);
+    }
+
+    #[test]
+    fn suffix_schemes() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let child = root.add_child();
+        let inner = child.add_named("x").unwrap();
+        let innermost = child.add_child().add_named("x").unwrap();
+        let policy = OutputPolicy {
+            suffix: SuffixScheme::Alphabetic,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&outer).unwrap().as_str(), "x");
+        assert_eq!(map.get(&inner).unwrap().as_str(), "x_b");
+        assert_eq!(map.get(&innermost).unwrap().as_str(), "x_c");
+        let alphabetic: Vec<String> = SuffixScheme::Alphabetic
+            .candidates("x")
+            .skip(25)
+            .take(3)
+            .collect();
+        assert_eq!(alphabetic, vec!["x_z", "x_ba", "x_bb"]);
+        let policy = OutputPolicy {
+            suffix: SuffixScheme::Hash,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        let hashed = map.get(&inner).unwrap().as_str();
+        assert!(hashed.starts_with("x_") && hashed.len() == 5);
+        assert_ne!(map.get(&innermost).unwrap().as_str(), hashed);
+    }
+
+    #[test]
+    fn compare_outputs_loosely() {
+        let root = RootScope::new_root();
+        let upper = root.add_named("Item").unwrap();
+        let child = root.add_child();
+        let lower = child.add_named("item").unwrap();
+        let keyword = child.add_named("SELECT").unwrap();
+        let policy = OutputPolicy {
+            comparison: OutputComparison::IgnoreCase,
+            ..OutputPolicy::default()
+        }
+        .with_keywords(&["select"]);
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&upper).unwrap().as_str(), "Item");
+        assert_eq!(map.get(&lower).unwrap().as_str(), "item2");
+        assert_eq!(map.get(&keyword).unwrap().as_str(), "SELECT2");
+        let exact = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(exact.get(&lower).unwrap().as_str(), "item");
+    }
+
+    #[cfg(feature = "normalization")]
+    #[test]
+    fn compare_normalized_outputs() {
+        let root = RootScope::new_root();
+        let ligature = root.add_named("\u{fb01}le").unwrap();
+        let child = root.add_child();
+        let plain = child.add_named("FILE").unwrap();
+        let policy = OutputPolicy {
+            allowed: char::is_alphanumeric,
+            allowed_start: char::is_alphabetic,
+            comparison: OutputComparison::Normalized,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&ligature).unwrap().as_str(), "\u{fb01}le");
+        assert_eq!(map.get(&plain).unwrap().as_str(), "FILE2");
+    }
+
+    #[test]
+    fn unique_within_significant_length() {
+        let root = RootScope::new_root();
+        let first = root.add_named("counter_first").unwrap();
+        let second = root.add_named("counter_second").unwrap();
+        let short = root.add_named("count").unwrap();
+        let policy = OutputPolicy {
+            significant_length: Some(7),
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&first).unwrap().as_str(), "counter_first");
+        assert_eq!(map.get(&second).unwrap().as_str(), "counte2");
+        assert_eq!(map.get(&short).unwrap().as_str(), "count");
+        let tiny = OutputPolicy {
+            significant_length: Some(1),
+            ..OutputPolicy::default()
+        };
+        assert_eq!(
+            root.root().generate_names(&tiny).unwrap_err(),
+            GenerateError::Exhausted(second)
+        );
+    }
+
+    #[test]
+    fn forbidden_patterns_are_avoided() {
+        let root = RootScope::new_root();
+        let tmp = root.add_prefixed("tmp");
+        let typed = root.add_named("point_t").unwrap();
+        let policy = OutputPolicy::default()
+            .with_pool("tmp", &["__a", "b"])
+            .with_forbidden(OutputPattern::Prefix("__".to_owned()))
+            .with_forbidden(OutputPattern::Suffix("_t".to_owned()));
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&tmp).unwrap().as_str(), "b");
+        assert_eq!(map.get(&typed).unwrap().as_str(), "point_t2");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn forbidden_regex() {
+        let root = RootScope::new_root();
+        let lower = root.add_named("_lower").unwrap();
+        let upper = root.add_named("Upper").unwrap();
+        let policy = OutputPolicy::default().with_forbidden(OutputPattern::Regex(
+            ::regex::Regex::new("^_?_[A-Z]").unwrap(),
+        ));
+        let mut map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&lower).unwrap().as_str(), "_lower");
+        assert_eq!(
+            map.override_output(&upper, "_Upper"),
+            Err(InvalidOverride::Reserved)
+        );
+        // Every candidate starts like the text, so none is allowed.
+        let init = root.add_named("__Init").unwrap();
+        assert_eq!(
+            root.root().generate_names(&policy).unwrap_err(),
+            GenerateError::Exhausted(init)
+        );
+    }
+
+    #[test]
+    fn format_outputs() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let prefixed = root.add_named("app_x").unwrap();
+        let anon = root.add_anonymous();
+        let policy = OutputPolicy {
+            formatter: Some(|name, output| match name.data {
+                InputName::Given(_) => format!("app_{}", output),
+                InputName::Anonymous(_) => output,
+            }),
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&x).unwrap().as_str(), "app_x");
+        // Formatted outputs are checked like any other.
+        assert_eq!(map.get(&prefixed).unwrap().as_str(), "app_app_x");
+        assert_eq!(map.get(&anon).unwrap().as_str(), "v");
+    }
+
+    #[test]
+    fn decorate_anonymous_names() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        function.set_label("fnFoo");
+        let first = function.add_prefixed("tmp");
+        let second = function.add_prefixed("tmp");
+        let plain = root.add_anonymous();
+        let debug = OutputPolicy {
+            allowed: |c| c.is_ascii_alphanumeric() || c == '_' || c == '$',
+            decorate_anonymous: Some('$'),
+            ..OutputPolicy::default()
+        }
+        .with_pool("tmp", &["t"]);
+        let map = root.root().generate_names(&debug).unwrap();
+        assert_eq!(map.get(&first).unwrap().as_str(), "tmp$fnFoo$0");
+        assert_eq!(map.get(&second).unwrap().as_str(), "tmp$fnFoo$1");
+        assert_eq!(map.get(&plain).unwrap().as_str(), "v$$0");
+        // The same tree with another policy.
+        let release = OutputPolicy {
+            strategy: Strategy::Minify,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&release).unwrap();
+        assert_eq!(map.get(&second).unwrap().as_str(), "c");
+    }
+
+    #[test]
+    fn seed_from_previous_run() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        function.set_label("main");
+        let inner = function.add_named("x").unwrap();
+        let previous = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap()
+            .path_table();
+        assert_eq!(previous["main::x"], "x");
+        // A new outer name would normally take the output of the inner one.
+        let outer = root.add_named("x").unwrap();
+        let keyword = function.add_named("y").unwrap();
+        let mut previous = previous;
+        previous.insert("main::y".to_owned(), "fn".to_owned());
+        let policy = OutputPolicy::default().with_keywords(&["fn"]);
+        let map = root.root().generate_seeded(&policy, &previous).unwrap();
+        assert_eq!(map.get(&inner).unwrap().as_str(), "x");
+        assert_eq!(map.get(&outer).unwrap().as_str(), "x2");
+        assert_eq!(map.get(&keyword).unwrap().as_str(), "y");
+        assert_eq!(map.verify(), vec![]);
+    }
+
+    #[test]
+    fn priorities_get_first_pick() {
+        let root = RootScope::new_root();
+        let tmp = root.add_prefixed("x");
+        let local = root.add_named("local").unwrap();
+        let module = root.add_child();
+        let exported = module.add_named("x").unwrap();
+        let policy = OutputPolicy {
+            priority: |name| match (&name.data, name.scope().parent()) {
+                (InputName::Anonymous(_), _) => -1,
+                (InputName::Given(_), None) => 0,
+                (InputName::Given(_), Some(_)) => 1,
+            },
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&exported).unwrap().as_str(), "x");
+        assert_eq!(map.get(&tmp).unwrap().as_str(), "x2");
+        let minified = OutputPolicy {
+            strategy: Strategy::Minify,
+            ..policy
+        };
+        let map = root.root().generate_names(&minified).unwrap();
+        assert_eq!(map.get(&exported).unwrap().as_str(), "a");
+        assert_eq!(map.get(&local).unwrap().as_str(), "b");
+        assert_eq!(map.get(&tmp).unwrap().as_str(), "c");
+    }
+
+    #[test]
+    fn generate_only_a_subtree() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let function = root.add_child();
+        let inner = function.add_named("y").unwrap();
+        let other = root.add_child().add_named("z").unwrap();
+        let mut map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        // The function was recompiled, and the rest of the tree changed too.
+        function.remove_name(&inner);
+        let recompiled = function.add_named("x").unwrap();
+        let late = root.add_named("late").unwrap();
+        map.generate_subtree(&function).unwrap();
+        assert_eq!(map.get(&recompiled).unwrap().as_str(), "x2");
+        assert_eq!(map.get(&outer).unwrap().as_str(), "x");
+        assert_eq!(map.get(&other).unwrap().as_str(), "z");
+        assert_eq!(map.get(&late), None);
+        assert_eq!(recompiled.output().unwrap().as_str(), "x2");
+    }
+
+    #[test]
+    fn pools_with_fallback() {
+        let root = RootScope::new_root();
+        let outer = root.add_prefixed("loop");
+        let body = root.add_child();
+        body.add_named("j").unwrap();
+        let inner = body.add_prefixed("loop");
+        let innermost = body.add_prefixed("loop");
+        let last = body.add_prefixed("loop");
+        let policy = OutputPolicy::default().with_pool("loop", &["i", "j", "k"]);
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&outer).unwrap().as_str(), "i");
+        assert_eq!(map.get(&inner).unwrap().as_str(), "k");
+        assert_eq!(map.get(&innermost).unwrap().as_str(), "loop");
+        assert_eq!(map.get(&last).unwrap().as_str(), "loop2");
+    }
+
+    #[test]
+    fn override_generated_output() {
+        let root = RootScope::new_root();
+        root.reserve("print");
+        let x = root.add_named("x").unwrap();
+        let child = root.add_child();
+        let inner = child.add_named("y").unwrap();
+        let sibling = root.add_child();
+        let other = sibling.add_named("z").unwrap();
+        let mut map = root
+            .root()
+            .generate_names(&OutputPolicy::default().with_keywords(&["fn"]))
+            .unwrap();
+        assert_eq!(
+            map.override_output(&x, "y"),
+            Err(InvalidOverride::Conflict(inner.clone()))
+        );
+        assert_eq!(
+            map.override_output(&inner, "x"),
+            Err(InvalidOverride::Conflict(x.clone()))
+        );
+        assert_eq!(
+            map.override_output(&x, "fn"),
+            Err(InvalidOverride::Reserved)
+        );
+        assert_eq!(
+            map.override_output(&inner, "print"),
+            Err(InvalidOverride::Reserved)
+        );
+        assert_eq!(
+            map.override_output(&x, "a b"),
+            Err(InvalidOverride::InvalidCharacters)
+        );
+        // Sibling scopes do not see each other.
+        assert_eq!(map.override_output(&inner, "counter"), Ok(()));
+        assert_eq!(map.override_output(&other, "counter"), Ok(()));
+        assert_eq!(map.get(&other).unwrap().as_str(), "counter");
+        assert_eq!(other.output().unwrap().as_str(), "counter");
+        assert_eq!(map.get(&x).unwrap().as_str(), "x");
+    }
+}
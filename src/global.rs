@@ -0,0 +1,62 @@
+/// Program-wide fresh names, for things that are emitted once per program, like helper
+/// functions and labels of static data, which must not collide with anything in any scope.
+use ::std::collections::HashSet;
+
+use crate::generate::candidates;
+use crate::name::{InputName, Name};
+use crate::scope::RootScope;
+
+impl RootScope {
+    /// Register a name in the root scope whose text is the prefix, or the prefix with the
+    /// lowest number appended, such that it differs from every given name in the tree (in
+    /// any scope or namespace), every reserved output, and every external symbol.
+    ///
+    /// The text is only unique at the time of the call; names registered later are not
+    /// prevented from using it.
+    pub fn global_fresh(&self, prefix: &str) -> Name {
+        let mut used: HashSet<String> = HashSet::new();
+        for data in self.root_data.names.borrow().iter() {
+            if data.removed {
+                continue;
+            }
+            if let InputName::Given(given) = &data.data {
+                used.insert(given.as_str().to_owned());
+            }
+            if let Some(external) = &data.external {
+                used.insert(external.symbol.as_str().to_owned());
+            }
+        }
+        let scope_count = self.root_data.scopes.borrow().len();
+        for index in 0..scope_count {
+            self.read_scope_names_at(index, |names| {
+                used.extend(names.reserved.iter().map(|text| text.as_str().to_owned()))
+            });
+        }
+        let text = candidates(prefix)
+            .find(|candidate| !used.contains(candidate))
+            .unwrap();
+        self.root_scope()
+            .add_named(&text)
+            .expect("fresh global name already exists")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn fresh_across_tree() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        function.add_named("helper").unwrap();
+        function.reserve("helper2");
+        let alloc = function.add_named("alloc").unwrap();
+        alloc.bind_external("libc", "helper3");
+        let helper = root.root().global_fresh("helper");
+        assert_eq!(helper.local_text(), "helper4");
+        assert_eq!(helper.scope(), root);
+        assert_eq!(root.root().global_fresh("init").local_text(), "init");
+        assert_eq!(root.root().global_fresh("init").local_text(), "init2");
+    }
+}
@@ -0,0 +1,144 @@
+/// A canonical text form of a tree, for golden or snapshot tests of compilers that use this
+/// crate. Unlike `Debug`, the format is meant to stay the same between versions.
+///
+/// Each scope is a line with its kind and settings, followed by its names and then its
+/// children, indented by two spaces per level. Names are sorted by namespace and text, so
+/// registration order does not matter, while children keep their order, which does.
+/// Ids, spans and provenance are left out, since they tend to change with unrelated edits.
+/// Detached scopes and removed names are not included.
+use ::std::fmt::Write;
+
+use crate::kind::ScopeKind;
+use crate::name::{InputName, Name};
+use crate::scope::{RootScope, Scope};
+use crate::visibility::Visibility;
+
+impl RootScope {
+    /// The tree in canonical text form, see the module documentation for the format.
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        write_scope(&self.root_scope(), 0, &mut out);
+        out
+    }
+}
+
+fn write_scope(scope: &Scope, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    let kind = match scope.kind() {
+        ScopeKind::Module => "module",
+        ScopeKind::Type => "type",
+        ScopeKind::Function => "function",
+        ScopeKind::Block => "block",
+    };
+    write!(out, "{}scope {}", indent, kind).unwrap();
+    if let Some(label) = scope.label() {
+        write!(out, " label={:?}", label.as_str()).unwrap();
+    }
+    if let Some(condition) = scope.condition() {
+        write!(out, " cfg={:?}", condition.as_str()).unwrap();
+    }
+    if scope.is_case_insensitive() {
+        out.push_str(" case-insensitive");
+    }
+    if let Some(target) = scope.target() {
+        write!(out, " target={:?}", target.as_str()).unwrap();
+    }
+    let mixins: Vec<String> = scope.mixins().iter().map(Scope::path_text).collect();
+    if !mixins.is_empty() {
+        write!(out, " mixins=[{}]", mixins.join(", ")).unwrap();
+    }
+    let mut reserved = scope.reserved();
+    if !reserved.is_empty() {
+        reserved.sort();
+        write!(out, " reserved={:?}", reserved).unwrap();
+    }
+    out.push('\n');
+    let mut names: Vec<(String, Name)> = scope
+        .names()
+        .into_iter()
+        .map(|name| (name_line(&name), name))
+        .collect();
+    names.sort_by(|(first, _), (second, _)| first.cmp(second));
+    for (line, _) in names {
+        writeln!(out, "{}  name {}", indent, line).unwrap();
+    }
+    for child in scope.children() {
+        write_scope(&child, depth + 1, out);
+    }
+}
+
+fn name_line(name: &Name) -> String {
+    let mut line = String::new();
+    if let InputName::Given(given) = &name.data {
+        if let Some(namespace) = given.namespace() {
+            write!(line, "{}:", namespace).unwrap();
+        }
+    }
+    line.push_str(&name.local_text());
+    if let InputName::Given(given) = &name.data {
+        if let Some(key) = given.overload_key() {
+            write!(line, " overload={}", key.0).unwrap();
+        }
+        if let Some(arity) = given.arity() {
+            write!(line, " arity={}", arity).unwrap();
+        }
+        if let Some(version) = given.version() {
+            write!(line, " version={}", version).unwrap();
+        }
+    }
+    match name.visibility() {
+        Visibility::Public => line.push_str(" public"),
+        Visibility::Crate => line.push_str(" crate"),
+        Visibility::Private => {}
+    }
+    if let Some(message) = name.deprecation() {
+        write!(line, " deprecated={:?}", message.as_str()).unwrap();
+    }
+    if let Some(condition) = name.condition() {
+        write!(line, " cfg={:?}", condition.as_str()).unwrap();
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kind::ScopeKind;
+    use crate::scope::RootScope;
+    use crate::visibility::Visibility;
+
+    #[test]
+    fn canonical_text() {
+        let build = |reversed: bool| {
+            let root = RootScope::new_root();
+            root.reserve("fn");
+            let module = root.add_child_with(ScopeKind::Module, "util");
+            let mut texts = vec!["b", "a"];
+            if reversed {
+                texts.reverse();
+            }
+            for text in texts {
+                module.add_named(text).unwrap();
+            }
+            module
+                .resolve("a")
+                .unwrap()
+                .set_visibility(Visibility::Public);
+            module.namespace("type").add_named("a").unwrap();
+            module.add_child().add_prefixed("tmp");
+            root.root().to_canonical_string()
+        };
+        assert_eq!(build(false), build(true));
+        assert_eq!(
+            build(false),
+            concat!(
+                "scope block reserved=[\"fn\"]\n",
+                "  scope module label=\"util\"\n",
+                "    name a public\n",
+                "    name b\n",
+                "    name type:a\n",
+                "    scope block\n",
+                "      name tmp#0\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,181 @@
+/// Use counts of names and scopes, based on the references recorded with `Scope::reference`,
+/// to see which regions of the tree drive identifier pressure before choosing strategies.
+///
+/// The counts can be rendered as a Graphviz graph, where busier scopes are drawn in a
+/// stronger color, or as JSON for other tools. Only bound references count towards names,
+/// so `RootScope::finalize` should be called first; all references count towards the scope
+/// they were recorded in. Detached scopes are not included.
+use ::std::collections::HashMap;
+use ::std::fmt::Write;
+
+use crate::name::Name;
+use crate::scope::{RootScope, Scope};
+
+/// Use counts per name and per scope, see `RootScope::heat_map`.
+#[derive(Debug, Clone)]
+pub struct HeatMap {
+    root: RootScope,
+    // Keyed by arena index; names and scopes without uses are left out.
+    name_uses: HashMap<usize, usize>,
+    scope_uses: HashMap<usize, usize>,
+}
+
+impl RootScope {
+    /// Count the recorded references per declaration and per scope they were made from.
+    pub fn heat_map(&self) -> HeatMap {
+        let mut name_uses = HashMap::new();
+        let mut scope_uses = HashMap::new();
+        for (scope, binding) in self.reference_sites() {
+            *scope_uses.entry(scope).or_default() += 1;
+            if let Some(name) = binding {
+                *name_uses.entry(name).or_default() += 1;
+            }
+        }
+        HeatMap {
+            root: self.clone(),
+            name_uses,
+            scope_uses,
+        }
+    }
+}
+
+impl HeatMap {
+    /// How often references were bound to the name.
+    pub fn name_uses(&self, name: &Name) -> usize {
+        self.name_uses.get(&name.index).cloned().unwrap_or(0)
+    }
+
+    /// How many references were made from the scope itself, not counting its descendants.
+    pub fn scope_uses(&self, scope: &Scope) -> usize {
+        self.scope_uses.get(&scope.index).cloned().unwrap_or(0)
+    }
+
+    /// A Graphviz digraph of the tree, with the names of each scope and their use counts.
+    pub fn to_dot(&self) -> String {
+        let max_uses = self.scope_uses.values().cloned().max().unwrap_or(0).max(1);
+        let mut out = String::from("digraph scopes {\n    node [shape=box, style=filled];\n");
+        for scope in self.scopes() {
+            let mut label = escape(&format!(
+                "{} ({})",
+                scope_title(&scope),
+                self.scope_uses(&scope)
+            ));
+            for name in scope.names() {
+                let line = format!("{}: {}", name.local_text(), self.name_uses(&name));
+                write!(label, "\\n{}", escape(&line)).unwrap();
+            }
+            // From white for no uses to full red for the busiest scope.
+            let saturation = self.scope_uses(&scope) as f64 / max_uses as f64;
+            writeln!(
+                out,
+                "    s{} [label=\"{}\", fillcolor=\"0.000 {:.3} 1.000\"];",
+                scope.index, label, saturation
+            )
+            .unwrap();
+            if let Some(parent) = scope.parent() {
+                writeln!(out, "    s{} -> s{};", parent.index, scope.index).unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The tree as nested JSON objects, with `id`, `label`, `uses`, `names` (each with
+    /// `name` and `uses`) and `children`.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&self.root.root_scope(), &mut out);
+        out
+    }
+
+    fn write_json(&self, scope: &Scope, out: &mut String) {
+        let label = match scope.label() {
+            Some(label) => format!("\"{}\"", escape(label.as_str())),
+            None => "null".to_owned(),
+        };
+        write!(
+            out,
+            "{{\"id\":{},\"label\":{},\"uses\":{},\"names\":[",
+            scope.index,
+            label,
+            self.scope_uses(scope)
+        )
+        .unwrap();
+        for (position, name) in scope.names().iter().enumerate() {
+            if position > 0 {
+                out.push(',');
+            }
+            write!(
+                out,
+                "{{\"name\":\"{}\",\"uses\":{}}}",
+                escape(&name.local_text()),
+                self.name_uses(name)
+            )
+            .unwrap();
+        }
+        out.push_str("],\"children\":[");
+        for (position, child) in scope.children().enumerate() {
+            if position > 0 {
+                out.push(',');
+            }
+            self.write_json(&child, out);
+        }
+        out.push_str("]}");
+    }
+
+    // Attached scopes in preorder.
+    fn scopes(&self) -> Vec<Scope> {
+        let root = self.root.root_scope();
+        let mut scopes = vec![root.clone()];
+        scopes.extend(root.descendants());
+        scopes
+    }
+}
+
+fn scope_title(scope: &Scope) -> String {
+    match (scope.label(), scope.debug_label()) {
+        (Some(label), _) => label.as_str().to_owned(),
+        (None, Some(debug_label)) => debug_label.as_str().to_owned(),
+        (None, None) => format!("scope {}", scope.index),
+    }
+}
+
+// Escape quotes and backslashes, which is enough for both formats since names and labels
+// do not normally contain control characters.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn count_uses() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let function = root.add_child();
+        function.set_label("f");
+        let y = function.add_named("y").unwrap();
+        function.reference("x");
+        function.reference("x");
+        function.reference("y");
+        root.reference("x");
+        root.root().finalize().unwrap();
+        let heat = root.root().heat_map();
+        assert_eq!(heat.name_uses(&x), 3);
+        assert_eq!(heat.name_uses(&y), 1);
+        assert_eq!(heat.scope_uses(&root), 1);
+        assert_eq!(heat.scope_uses(&function), 3);
+        assert_eq!(
+            heat.to_json(),
+            concat!(
+                r#"{"id":0,"label":null,"uses":1,"names":[{"name":"x","uses":3}],"children":["#,
+                r#"{"id":1,"label":"f","uses":3,"names":[{"name":"y","uses":1}],"children":[]}]}"#
+            )
+        );
+        let dot = heat.to_dot();
+        assert!(dot.contains(r#"s1 [label="f (3)\ny: 1", fillcolor="0.000 1.000 1.000"];"#));
+        assert!(dot.contains("s0 -> s1;"));
+    }
+}
@@ -0,0 +1,142 @@
+/// Glob imports, which make all given names of another scope visible in a scope, like
+/// `use module::*` in Rust or `from module import *` in Python.
+///
+/// Imports copy the names that exist at the time of the import; names added to the other
+/// scope later are not imported. Names declared in the importing scope itself always take
+/// precedence over imported ones.
+use crate::name::{GivenName, Name};
+use crate::scope::{AlreadyExists, Scope};
+
+/// What to do when an imported name has the same text as a name already visible through an
+/// earlier import, or declared in the importing scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportConflict {
+    /// Fail without importing anything.
+    Error,
+    /// The new import replaces the earlier import of the same text.
+    Shadow,
+    /// Keep the earlier import, and do not import this name.
+    Skip,
+}
+
+impl Scope {
+    /// Import all given names registered directly in the other scope into this one.
+    pub fn import_all(
+        &self,
+        from: &Scope,
+        on_conflict: ImportConflict,
+    ) -> Result<(), AlreadyExists> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        assert!(
+            self.root == from.root,
+            "cannot import from a scope of another tree"
+        );
+        self.root.assert_unsealed(self.index);
+        let imported: Vec<(GivenName, usize)> = from.root.scope_names_at(from.index, |data| {
+            data.given_names
+                .iter()
+                .map(|(given, &index)| (given.clone(), index))
+                .collect()
+        });
+        self.root.scope_names_at(self.index, |data| {
+            if on_conflict == ImportConflict::Error {
+                let has_conflict = imported.iter().any(|(given, _)| {
+                    data.given_names.contains_key(given) || data.imports.contains_key(given)
+                });
+                if has_conflict {
+                    return Err(AlreadyExists());
+                }
+            }
+            for (given, index) in imported {
+                if on_conflict == ImportConflict::Skip && data.imports.contains_key(&given) {
+                    continue;
+                }
+                data.imports.insert(given, index);
+            }
+            Ok(())
+        })?;
+        self.root.mark_dirty(self.index);
+        Ok(())
+    }
+
+    /// Look up a given name imported into this scope, ignoring case if the scope does.
+    pub(crate) fn find_imported(&self, given_name: &GivenName) -> Option<Name> {
+        let is_case_insensitive = self.is_case_insensitive();
+        let folded = given_name.as_str().to_lowercase();
+        self.root
+            .read_scope_names_at(self.index, |data| {
+                data.imports.get(given_name).cloned().or_else(|| {
+                    if !is_case_insensitive {
+                        return None;
+                    }
+                    data.imports
+                        .iter()
+                        .find(|(other, _)| {
+                            other.as_str().to_lowercase() == folded
+                                && GivenName {
+                                    name: given_name.name.clone(),
+                                    ..(*other).clone()
+                                } == *given_name
+                        })
+                        .map(|(_, index)| *index)
+                })
+            })
+            .map(|index| self.root.name_at(index))
+            .filter(|name| !name.is_removed())
+    }
+
+    /// The names imported into this scope, in registration order.
+    pub(crate) fn imported_names(&self) -> Vec<Name> {
+        let mut indices: Vec<usize> = self
+            .root
+            .read_scope_names_at(self.index, |data| data.imports.values().cloned().collect());
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|index| self.root.name_at(index))
+            .filter(|name| !name.is_removed())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn import_names() {
+        let root = RootScope::new_root();
+        let module = root.add_child();
+        let f = module.add_named("f").unwrap();
+        let g = module.add_named("g").unwrap();
+        let user = root.add_child();
+        let own_g = user.add_named("g").unwrap();
+        let inner = user.add_child();
+        assert!(user.import_all(&module, ImportConflict::Error).is_err());
+        assert!(inner.resolve("f").is_none());
+        user.import_all(&module, ImportConflict::Skip).unwrap();
+        assert_eq!(inner.resolve("f"), Some(f.clone()));
+        assert_eq!(inner.resolve("g"), Some(own_g));
+        // Names added after the import are not imported.
+        module.add_named("h").unwrap();
+        assert!(inner.resolve("h").is_none());
+        assert_eq!(module.resolve("g"), Some(g));
+    }
+
+    #[test]
+    fn import_conflicts() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let first_f = first.add_named("f").unwrap();
+        let second = root.add_child();
+        let second_f = second.add_named("f").unwrap();
+        let user = root.add_child();
+        user.import_all(&first, ImportConflict::Error).unwrap();
+        user.import_all(&second, ImportConflict::Skip).unwrap();
+        assert_eq!(user.resolve("f"), Some(first_f));
+        user.import_all(&second, ImportConflict::Shadow).unwrap();
+        assert_eq!(user.resolve("f"), Some(second_f));
+    }
+}
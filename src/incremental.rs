@@ -0,0 +1,101 @@
+/// Incremental updates after a source edit, so that e.g. a language server only rebuilds
+/// the scopes that the edit touched, instead of the whole tree on every keystroke.
+///
+/// The scope to rebuild is the innermost one whose range contains the whole edit. Its names
+/// and children are removed, so the caller can declare them again from the new source. All
+/// other scopes and names are kept, including their ids, with ranges moved to match the edit.
+use ::std::ops::Range;
+
+use crate::scope::{RootScope, Scope};
+
+impl RootScope {
+    /// Invalidate the scope that contains the edited range, which is replaced by text of
+    /// length `new_len`, and update the ranges of the other scopes. Returns the emptied scope,
+    /// which the caller should fill again; if no scope contains the edit, that is the root.
+    pub fn apply_edit(&self, edit: Range<usize>, new_len: usize) -> Scope {
+        assert!(edit.start <= edit.end, "edit range is reversed");
+        let index = self.innermost_containing(0, &edit).unwrap_or(0);
+        let scope = self.scope_at_index(index);
+        scope.clear_names();
+        scope.clear_children();
+
+        // Ranges after the edit move, ranges around it grow or shrink.
+        let shift = |offset: usize| offset - edit.len() + new_len;
+        let root_scope = self.root_scope();
+        for other in Some(root_scope.clone())
+            .into_iter()
+            .chain(root_scope.descendants())
+        {
+            if let Some(span) = other.span() {
+                if span.start >= edit.end {
+                    other.set_span(shift(span.start)..shift(span.end));
+                } else if span.end >= edit.end {
+                    other.set_span(span.start..shift(span.end));
+                }
+            }
+        }
+        scope
+    }
+
+    fn innermost_containing(&self, index: usize, edit: &Range<usize>) -> Option<usize> {
+        let span = self.scope_data_at(index, |data| data.span.clone());
+        let is_covered = match span {
+            Some(span) if span.start > edit.start || span.end < edit.end => return None,
+            Some(_) => true,
+            None => false,
+        };
+        self.child_indices(index)
+            .into_iter()
+            .find_map(|child| self.innermost_containing(child, edit))
+            .or(if is_covered { Some(index) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn rebuild_edited_scope() {
+        let root = RootScope::new_root();
+        root.set_span(0..100);
+        let f = root.add_named("f").unwrap();
+        let first = root.add_child();
+        first.set_span(10..40);
+        let x = first.add_named("x").unwrap();
+        let block = first.add_child();
+        block.set_span(20..30);
+        let second = root.add_child();
+        second.set_span(50..90);
+        let y = second.add_named("y").unwrap();
+        root.root().mark_clean();
+
+        // Replace 5 characters inside the first function by 15.
+        let rebuilt = root.root().apply_edit(12..17, 15);
+        assert_eq!(rebuilt, first);
+        assert!(x.is_removed());
+        assert_eq!(first.children().count(), 0);
+        assert_eq!(block.parent(), None);
+        assert!(!f.is_removed());
+        assert!(!y.is_removed());
+        assert_eq!(root.span(), Some(0..110));
+        assert_eq!(first.span(), Some(10..50));
+        assert_eq!(second.span(), Some(60..100));
+        assert_eq!(root.root().dirty_scopes(), vec![first.clone()]);
+        let new_x = first.add_named("x").unwrap();
+        assert_eq!(first.root().scope_at(15), Some(first.clone()));
+        assert_eq!(first.resolve("x"), Some(new_x));
+        assert_eq!(second.resolve("y"), Some(y));
+    }
+
+    #[test]
+    fn edit_outside_scopes() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        child.set_span(0..10);
+        let x = root.add_named("x").unwrap();
+        assert_eq!(root.root().apply_edit(20..20, 5), root);
+        assert!(x.is_removed());
+        assert_eq!(child.parent(), None);
+    }
+}
@@ -0,0 +1,81 @@
+/// Reading all properties of a scope at once, e.g. for debug views or serialization,
+/// without a call (and a borrow of the tree) per property.
+use ::std::ops::Range;
+
+use ::ustr::Ustr;
+
+use crate::kind::ScopeKind;
+use crate::name::Name;
+use crate::scope::Scope;
+
+/// The state of a scope at the time of `Scope::snapshot_data`. Later changes to the tree
+/// are not reflected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeSnapshot {
+    pub parent: Option<Scope>,
+    pub children: Vec<Scope>,
+    pub kind: ScopeKind,
+    pub label: Option<Ustr>,
+    pub debug_label: Option<Ustr>,
+    pub span: Option<Range<usize>>,
+    pub condition: Option<Ustr>,
+    pub mixins: Vec<Scope>,
+    /// Names registered directly in the scope, in registration order.
+    pub names: Vec<Name>,
+}
+
+impl Scope {
+    pub fn snapshot_data(&self) -> ScopeSnapshot {
+        let (parent, kind, label, debug_label, span, condition, mixins) =
+            self.root.read_scope_data_at(self.index, |data| {
+                (
+                    data.parent,
+                    data.kind,
+                    data.label,
+                    data.debug_label,
+                    data.span.clone(),
+                    data.condition,
+                    data.mixins.clone(),
+                )
+            });
+        ScopeSnapshot {
+            parent: parent.map(|index| self.root.scope_at_index(index)),
+            children: self.children().collect(),
+            kind,
+            label,
+            debug_label,
+            span,
+            condition,
+            mixins: mixins
+                .into_iter()
+                .map(|index| self.root.scope_at_index(index))
+                .collect(),
+            names: self.names(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kind::ScopeKind;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn snapshot_of_scope() {
+        let root = RootScope::new_root();
+        let function = root.add_child_with(ScopeKind::Function, "f");
+        function.set_span(3..8);
+        let x = function.add_named("x").unwrap();
+        let tmp = function.add_prefixed("tmp");
+        let block = function.add_child();
+        let data = function.snapshot_data();
+        assert_eq!(data.parent, Some(root));
+        assert_eq!(data.children, vec![block]);
+        assert_eq!(data.kind, ScopeKind::Function);
+        assert_eq!(data.label.unwrap().as_str(), "f");
+        assert_eq!(data.span, Some(3..8));
+        assert_eq!(data.names, vec![x, tmp]);
+        function.add_named("y").unwrap();
+        assert_eq!(data.names.len(), 2);
+    }
+}
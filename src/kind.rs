@@ -0,0 +1,113 @@
+/// What construct a scope belongs to, and iterator adapters to filter scopes and names
+/// without matching on internal details.
+#[cfg(feature = "serde")]
+use ::serde::{Deserialize, Serialize};
+
+use crate::name::{InputName, Name};
+use crate::scope::Scope;
+
+/// The language construct that introduces a scope. Front-ends that do not need this can
+/// leave every scope as a `Block`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScopeKind {
+    Module,
+    Type,
+    Function,
+    #[default]
+    Block,
+}
+
+impl Scope {
+    pub fn set_kind(&self, kind: ScopeKind) {
+        self.root.scope_data_at(self.index, |data| data.kind = kind);
+    }
+
+    pub fn kind(&self) -> ScopeKind {
+        self.root.read_scope_data_at(self.index, |data| data.kind)
+    }
+}
+
+/// Filters for iterators over scopes, like `Scope::children` and `Scope::descendants`.
+pub trait ScopeFilters: Iterator<Item = Scope> + Sized {
+    /// Only the scopes of the given kind.
+    fn of_kind(self, kind: ScopeKind) -> OfKind<Self> {
+        OfKind { inner: self, kind }
+    }
+}
+
+/// Iterator adapter returned by `ScopeFilters::of_kind`.
+#[derive(Debug)]
+pub struct OfKind<I> {
+    inner: I,
+    kind: ScopeKind,
+}
+
+impl<I: Iterator<Item = Scope>> Iterator for OfKind<I> {
+    type Item = Scope;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let kind = self.kind;
+        self.inner.find(|scope| scope.kind() == kind)
+    }
+}
+
+impl<I: Iterator<Item = Scope>> ScopeFilters for I {}
+
+/// Filters for iterators over names, like `Scope::names`.
+pub trait NameFilters: Iterator<Item = Name> + Sized {
+    /// Only the given names, skipping anonymous ones.
+    fn given_only(self) -> ::std::iter::Filter<Self, fn(&Name) -> bool> {
+        self.filter(|name| matches!(name.data, InputName::Given(_)))
+    }
+
+    /// Only the anonymous names, skipping given ones.
+    fn anonymous_only(self) -> ::std::iter::Filter<Self, fn(&Name) -> bool> {
+        self.filter(|name| matches!(name.data, InputName::Anonymous(_)))
+    }
+}
+
+impl<I: Iterator<Item = Name>> NameFilters for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn filter_by_kind() {
+        let root = RootScope::new_root();
+        root.set_kind(ScopeKind::Module);
+        let function = root.add_child();
+        function.set_kind(ScopeKind::Function);
+        let block = function.add_child();
+        let nested = block.add_child();
+        nested.set_kind(ScopeKind::Function);
+        assert_eq!(block.kind(), ScopeKind::Block);
+        assert_eq!(
+            root.descendants().collect::<Vec<_>>(),
+            vec![function.clone(), block.clone(), nested.clone()]
+        );
+        let functions: Vec<_> = root.descendants().of_kind(ScopeKind::Function).collect();
+        assert_eq!(functions, vec![function, nested]);
+        assert_eq!(root.children().of_kind(ScopeKind::Module).count(), 0);
+    }
+
+    #[test]
+    fn filter_names() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let tmp = root.add_prefixed("tmp");
+        assert_eq!(
+            root.names().into_iter().given_only().collect::<Vec<_>>(),
+            vec![x]
+        );
+        assert_eq!(
+            root.names()
+                .into_iter()
+                .anonymous_only()
+                .collect::<Vec<_>>(),
+            vec![tmp]
+        );
+    }
+}
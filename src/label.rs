@@ -0,0 +1,105 @@
+/// Loop and block labels, which live in their own namespace and only resolve within the
+/// enclosing function, since control flow cannot jump out of it.
+///
+/// Generated labels are short, like `L0`, and unique within the whole function, so that they
+/// also work for targets where labels are not scoped, like assembly.
+use ::std::iter::once;
+
+use crate::kind::ScopeKind;
+use crate::limit::RegisterError;
+use crate::name::{InputName, Name};
+use crate::scope::Scope;
+
+/// The namespace that labels are registered in.
+pub(crate) const LABEL_NAMESPACE: &str = "label";
+
+impl Scope {
+    /// Register a label in this scope, failing if it is already registered here. Labels do
+    /// not conflict with names in other namespaces.
+    pub fn add_label(&self, label: &str) -> Result<Name, RegisterError> {
+        self.namespace(LABEL_NAMESPACE).add_named(label)
+    }
+
+    /// Find the nearest label with this text, moving outwards but not beyond the enclosing
+    /// function (or the root, outside functions).
+    pub fn resolve_label(&self, label: &str) -> Option<Name> {
+        let boundary = self.enclosing_function();
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            if let Some(name) = scope.namespace(LABEL_NAMESPACE).get(label) {
+                return Some(name);
+            }
+            if scope == boundary {
+                break;
+            }
+            current = scope.parent();
+        }
+        None
+    }
+
+    /// Register a new label `L<n>` in this scope, with the lowest number that is not used by
+    /// any label in the enclosing function.
+    pub fn add_fresh_label(&self) -> Name {
+        let function = self.enclosing_function();
+        let used: Vec<String> = once(function.clone())
+            .chain(function.descendants())
+            .flat_map(|scope| scope.namespace(LABEL_NAMESPACE).names())
+            .filter_map(|name| match name.data {
+                InputName::Given(given) => Some(given.as_str().to_owned()),
+                InputName::Anonymous(_) => None,
+            })
+            .collect();
+        let label = (0..)
+            .map(|number| format!("L{}", number))
+            .find(|label| !used.contains(label))
+            .expect("ran out of labels");
+        self.add_label(&label).expect("fresh label already exists")
+    }
+
+    /// The nearest scope of kind `Function` from this one outwards, or the root.
+    fn enclosing_function(&self) -> Scope {
+        let mut current = self.clone();
+        while current.kind() != ScopeKind::Function {
+            match current.parent() {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kind::ScopeKind;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn labels_stop_at_function() {
+        let root = RootScope::new_root();
+        root.add_label("outer").unwrap();
+        let function = root.add_child();
+        function.set_kind(ScopeKind::Function);
+        let body = function.add_label("body").unwrap();
+        let inner = function.add_child();
+        inner.add_named("body").unwrap();
+        assert_eq!(inner.resolve_label("body"), Some(body));
+        assert!(inner.resolve_label("outer").is_none());
+        assert!(root.resolve_label("outer").is_some());
+        assert_eq!(inner.resolve("body").unwrap().scope(), inner);
+    }
+
+    #[test]
+    fn fresh_labels() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        function.set_kind(ScopeKind::Function);
+        let first_loop = function.add_child();
+        let second_loop = function.add_child();
+        first_loop.add_label("L1").unwrap();
+        let first = first_loop.add_fresh_label();
+        let second = second_loop.add_fresh_label();
+        assert_eq!(first.unwrap_given().as_str(), "L0");
+        assert_eq!(second.unwrap_given().as_str(), "L2");
+    }
+}
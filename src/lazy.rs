@@ -0,0 +1,81 @@
+/// Lazy generation, where names get their output the first time `Name::output` is called,
+/// for streaming code generators that emit code while later parts of the tree are still
+/// being built.
+///
+/// A lazily assigned output avoids the outputs of names that are visible where the name is
+/// (in its own scope, its ancestors and its descendants), the keywords of the policy and
+/// reserved texts, the same checks as `NameMap::override_output`. Since names only avoid the
+/// outputs assigned before them, the outputs depend on the order of the calls, and may have
+/// more suffixes than those of `RootScope::generate_names`, which sees the whole tree.
+use crate::backend::Shared;
+use crate::generate::{check_output, OutputName, OutputPolicy};
+use crate::name::Name;
+use crate::scope::RootScope;
+
+impl RootScope {
+    /// Assign outputs on demand from now on, see the module documentation. Names keep the
+    /// outputs they already have, e.g. from an earlier `generate_names`.
+    pub fn generate_lazily(&self, policy: OutputPolicy) {
+        *self.root_data.lazy_outputs.borrow_mut() = Some(Shared::new(policy));
+    }
+
+    /// Assign an output to the name and the names unified with it, if outputs are generated
+    /// lazily and the name is part of the tree.
+    pub(crate) fn assign_lazily(&self, name: &Name) -> Option<OutputName> {
+        // Another thread could assign a candidate between the check and the write.
+        let _exclusive = self.root_data.exclusive.lock();
+        let policy = self.root_data.lazy_outputs.borrow().clone()?;
+        if name.is_removed() || name.scope().preorder_index().is_none() {
+            return None;
+        }
+        let unified = name.unified_names();
+        let output = match unified.iter().find_map(Name::cached_output) {
+            Some(output) => output,
+            None => {
+                let base = policy.base_output(name, Name::output);
+                let output = policy.candidates_for(name, &base).find(|candidate| {
+                    unified.iter().all(|member| {
+                        check_output(&policy, member, candidate, &unified, Name::cached_output)
+                            .is_ok()
+                    })
+                })?;
+                OutputName(output)
+            }
+        };
+        let mut names = self.root_data.names.borrow_mut();
+        for member in &unified {
+            names[member.index].output = Some(output.clone());
+        }
+        Some(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn assign_on_first_use() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        assert_eq!(x.output(), None);
+        root.root()
+            .generate_lazily(OutputPolicy::default().with_keywords(&["fn"]));
+        assert_eq!(x.output().unwrap().as_str(), "x");
+        // Names added later avoid what was assigned before them.
+        let function = root.add_child();
+        let inner = function.add_named("x").unwrap();
+        let keyword = function.add_named("fn").unwrap();
+        assert_eq!(inner.output().unwrap().as_str(), "x2");
+        assert_eq!(keyword.output().unwrap().as_str(), "fn2");
+        let sibling = root.add_child().add_named("x").unwrap();
+        assert_eq!(sibling.output().unwrap().as_str(), "x2");
+        // The order of the calls matters: the outer name was not assigned yet.
+        let y = function.add_named("y").unwrap();
+        let outer_y = root.add_named("y").unwrap();
+        assert_eq!(y.output().unwrap().as_str(), "y");
+        assert_eq!(outer_y.output().unwrap().as_str(), "y2");
+        assert_eq!(inner.output().unwrap().as_str(), "x2");
+    }
+}
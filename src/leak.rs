@@ -0,0 +1,120 @@
+/// Finding names that were registered but never used, and scopes that were created but
+/// never populated, which point at front-end bugs that silently bloat the tree.
+///
+/// A name counts as used if a reference recorded with `Scope::reference` is bound to it,
+/// so `RootScope::finalize` should be called first. With `RootScope::set_report_unused`,
+/// both lists are printed to stderr when the last handle to the tree is dropped, in debug
+/// builds only.
+use ::std::collections::HashSet;
+
+use crate::name::{InputName, Name};
+use crate::scope::{RootScope, RootScopeData, Scope};
+
+impl RootScope {
+    /// Print unused names and empty scopes when the tree is dropped, in debug builds.
+    /// Off by default.
+    pub fn set_report_unused(&self, report: bool) {
+        self.root_data.report_unused.set(report);
+    }
+
+    /// Names that are not removed, and that no recorded reference is bound to, in
+    /// registration order.
+    pub fn unused_names(&self) -> Vec<Name> {
+        self.root_data
+            .unused_names()
+            .into_iter()
+            .map(|index| self.name_at(index))
+            .collect()
+    }
+
+    /// Scopes other than the root scope without names or children, including detached
+    /// ones but not collected ones.
+    pub fn empty_scopes(&self) -> Vec<Scope> {
+        self.root_data
+            .empty_scopes()
+            .into_iter()
+            .map(|index| self.scope_at_index(index))
+            .collect()
+    }
+}
+
+impl RootScopeData {
+    fn unused_names(&self) -> Vec<usize> {
+        let used: HashSet<usize> = self
+            .references
+            .borrow()
+            .iter()
+            .filter_map(|reference| reference.binding())
+            .collect();
+        let names = self.names.borrow();
+        (0..names.len())
+            .filter(|&index| !names[index].removed && !used.contains(&index))
+            .collect()
+    }
+
+    fn empty_scopes(&self) -> Vec<usize> {
+        let scopes = self.scopes.borrow();
+        let scope_names = self.scope_names.borrow();
+        (1..scopes.len())
+            .filter(|&index| {
+                !scopes[index].collected
+                    && scopes[index].first_child.is_none()
+                    && scope_names[index].given_names.is_empty()
+                    && scope_names[index].anon_names.is_empty()
+            })
+            .collect()
+    }
+}
+
+impl Drop for RootScopeData {
+    fn drop(&mut self) {
+        if !cfg!(debug_assertions) || !self.report_unused.get() {
+            return;
+        }
+        let unused_names = self.unused_names();
+        let empty_scopes = self.empty_scopes();
+        if unused_names.is_empty() && empty_scopes.is_empty() {
+            return;
+        }
+        // The handles are gone, so names are shown by their own text rather than their path.
+        let names = self.names.borrow();
+        let texts: Vec<String> = unused_names
+            .iter()
+            .map(|&index| match &names[index].data {
+                InputName::Given(given) => given.as_str().to_owned(),
+                InputName::Anonymous(anon) => format!("{}#", anon.name),
+            })
+            .collect();
+        eprintln!(
+            "scoped_name: tree dropped with {} unused names [{}] and {} empty scopes {:?}",
+            texts.len(),
+            texts.join(", "),
+            empty_scopes.len(),
+            empty_scopes
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn find_unused() {
+        let root = RootScope::new_root();
+        root.root().set_report_unused(true);
+        let used = root.add_named("used").unwrap();
+        let unused = root.add_named("unused").unwrap();
+        let function = root.add_child();
+        let empty = function.add_child();
+        let removed = function.add_prefixed("tmp");
+        function.remove_name(&removed);
+        function.reference("used");
+        root.root().finalize().unwrap();
+        assert_eq!(root.root().unused_names(), vec![unused]);
+        assert_eq!(root.root().empty_scopes(), vec![empty]);
+        assert!(!root.root().unused_names().contains(&used));
+        // Dropping prints the report, and must not disturb anything else.
+        drop(root);
+    }
+}
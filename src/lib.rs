@@ -1,7 +1,159 @@
+//! # Threads
+//!
+//! By default, trees use `Rc` and `RefCell`, so they stay on the thread that built them.
+//! With the `sync` feature, the same `RootScope` type uses `Arc` and locks instead, so trees
+//! are `Send` and `Sync`. There is no separate thread-safe root type: a parallel front-end
+//! can hand its finished tree to a single-threaded backend as it is, without freezing or
+//! thawing it, since both use the one `RootScope` of the build.
+//!
+//! Without `sync`, `RootScope::freeze` copies a tree into a `FrozenScopes`, which is `Send`
+//! and `Sync` in every build, so read-only passes like resolution can still run on other
+//! threads. A frozen tree cannot be thawed back into a `RootScope`.
+
 //TODO @mark: disable unused stuff later, but currently too much in-progress
 #![allow(unused_variables, dead_code, unused_imports)]
 
+mod backend;
+mod build;
+mod builder;
+mod canonical;
+mod case;
+mod cfg;
+mod check;
+mod collapse;
+mod copy;
+mod debug_label;
+mod declare;
+mod deprecation;
+mod derive;
+mod diagnostics;
+mod diff;
+mod dirty;
+mod dynamic;
+mod emit;
+mod explain;
+mod external;
+mod finalized;
+mod fingerprint;
+mod frozen;
+mod gc;
+mod generate;
+mod global;
+mod golden;
+mod heat;
+mod import;
+mod incremental;
+mod inspect;
+mod kind;
+mod label;
+mod lazy;
+mod leak;
+mod lifetime;
+mod limit;
+mod macros;
+mod merge;
+mod mixin;
+mod multi;
 mod name;
+mod namespace;
+mod order;
+mod ordinal;
+mod overload;
+mod path;
+mod pool;
+mod prefix;
+mod probe;
+mod profile;
+mod provenance;
+mod query;
+mod reference;
+mod relocate;
+mod remove;
+mod rename;
+mod report;
+mod reserve;
 mod scope;
+mod seal;
+mod shadow;
+mod similar;
+mod size;
+mod span;
+mod spec;
+mod stats;
+mod strategy;
+mod table;
+mod target;
+mod text;
+mod trace;
+mod unify;
+mod validate;
+mod verify;
+mod version;
+mod visibility;
+
+#[cfg(feature = "proptest")]
+mod arbitrary;
+mod arity;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "pyo3")]
+mod python;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(test)]
+mod test_trees;
+
+#[cfg(feature = "proptest")]
+pub use crate::arbitrary::{scope_tree, TreeBounds};
+pub use crate::build::BuildScopes;
+pub use crate::builder::RootScopeBuilder;
+pub use crate::canonical::Canonicalization;
+pub use crate::cfg::ConfigView;
+pub use crate::check::OutputConflict;
+pub use crate::copy::CopyError;
+pub use crate::declare::{AlreadyDefined, PendingName};
+pub use crate::diagnostics::Diagnostic;
+pub use crate::diff::TreeDiff;
+pub use crate::emit::EmitCycle;
+pub use crate::explain::{RenameReason, RenameStats, Renamed};
+pub use crate::external::ExternalSymbol;
+pub use crate::finalized::{FinalizeError, FinalizedScope, FinalizedScopes};
+pub use crate::fingerprint::StableHash;
+pub use crate::frozen::{CacheStats, FrozenScopes};
+pub use crate::generate::{
+    GenerateError, InvalidOverride, NameFormatter, NameMap, OutputComparison, OutputName,
+    OutputPattern, OutputPolicy, SuffixScheme,
+};
+pub use crate::heat::HeatMap;
+pub use crate::import::ImportConflict;
+pub use crate::inspect::ScopeSnapshot;
+pub use crate::kind::{NameFilters, OfKind, ScopeFilters, ScopeKind};
+pub use crate::limit::{LimitExceeded, Limits, RegisterError};
+pub use crate::multi::ProfileMaps;
+pub use crate::name::{AnonName, GivenName, Name, NameId, NameRef};
+pub use crate::namespace::Namespace;
+pub use crate::order::GenerationOrder;
+pub use crate::overload::OverloadKey;
+pub use crate::pool::RootPool;
+pub use crate::probe::AddOutcome;
+pub use crate::profile::SqlDialect;
+pub use crate::reference::Unresolved;
+pub use crate::relocate::{CreatesCycle, MergeError};
+pub use crate::report::TreeReport;
+pub use crate::reserve::ReservedOutput;
+pub use crate::scope::{
+    AlreadyExists, InvalidOrder, RootId, RootScope, Scope, ScopeChildrenIterator,
+    ScopeDescendantsIterator, ScopeId,
+};
+pub use crate::shadow::{NotShadowing, ShadowedName};
+pub use crate::spec::{DuplicateName, ScopeSpec, SpecError};
+pub use crate::stats::SubtreeStats;
+pub use crate::strategy::Strategy;
+pub use crate::trace::{ResolutionTrace, StepOutcome, TraceStep};
+pub use crate::unify::NotUnifiable;
+pub use crate::validate::{InvalidIdentifier, InvalidName};
+pub use crate::verify::Violation;
+pub use crate::visibility::{AccessError, Visibility};
 
 //TODO @mark: convert to https://github.com/anderslanglands/ustr
@@ -0,0 +1,97 @@
+use crate::limit::RegisterError;
+/// Rust lifetime names, like `'a`, in their own namespace, so that macro and code generation
+/// crates can produce fresh lifetimes without clashing with the ones in scope.
+///
+/// The leading tick is not part of the stored text; it is stripped on input and added back
+/// by `Name::lifetime`. The lifetime `'static` always exists, so it cannot be declared.
+use crate::name::{InputName, Name};
+use crate::scope::Scope;
+
+/// The namespace that lifetimes are registered in.
+pub(crate) const LIFETIME_NAMESPACE: &str = "lifetime";
+
+/// Lifetimes that are built into the language and can never be declared.
+const RESERVED_LIFETIMES: [&str; 2] = ["static", "_"];
+
+impl Scope {
+    /// Declare a lifetime in this scope, with or without the leading tick. Fails if it is
+    /// already declared here, or if it is reserved, like `'static`.
+    pub fn add_lifetime(&self, lifetime: &str) -> Result<Name, RegisterError> {
+        let text = lifetime.strip_prefix('\'').unwrap_or(lifetime);
+        if RESERVED_LIFETIMES.contains(&text) {
+            return Err(RegisterError::AlreadyExists);
+        }
+        self.namespace(LIFETIME_NAMESPACE).add_named(text)
+    }
+
+    /// Find the nearest declaration of the lifetime, with or without the leading tick.
+    pub fn resolve_lifetime(&self, lifetime: &str) -> Option<Name> {
+        let text = lifetime.strip_prefix('\'').unwrap_or(lifetime);
+        self.namespace(LIFETIME_NAMESPACE).resolve(text)
+    }
+
+    /// Declare the first lifetime in the sequence `'a` to `'z`, `'aa`, `'ab`, ... that does
+    /// not shadow a lifetime visible from this scope.
+    pub fn add_fresh_lifetime(&self) -> Name {
+        let text = (0..)
+            .map(lifetime_text)
+            .find(|text| self.resolve_lifetime(text).is_none())
+            .expect("ran out of lifetimes");
+        self.add_lifetime(&text)
+            .expect("fresh lifetime already exists")
+    }
+}
+
+impl Name {
+    /// The lifetime including its leading tick, or None if this is not a lifetime.
+    pub fn lifetime(&self) -> Option<String> {
+        match &self.data {
+            InputName::Given(given) if given.namespace() == Some(LIFETIME_NAMESPACE) => {
+                Some(format!("'{}", given.as_str()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The lifetime text for a number, counting `a` to `z` and then `aa`, `ab`, ...
+fn lifetime_text(number: usize) -> String {
+    let mut text = vec![];
+    let mut remaining = number + 1;
+    while remaining > 0 {
+        remaining -= 1;
+        text.push(b'a' + (remaining % 26) as u8);
+        remaining /= 26;
+    }
+    text.reverse();
+    String::from_utf8(text).expect("lifetime text is ascii")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    use super::lifetime_text;
+
+    #[test]
+    fn declare_lifetimes() {
+        let root = RootScope::new_root();
+        let a = root.add_lifetime("'a").unwrap();
+        root.add_lifetime("a").unwrap_err();
+        root.add_lifetime("'static").unwrap_err();
+        root.add_named("a").unwrap();
+        let child = root.add_child();
+        assert_eq!(child.resolve_lifetime("a"), Some(a.clone()));
+        assert_eq!(a.lifetime(), Some("'a".to_owned()));
+        assert_eq!(child.add_fresh_lifetime().lifetime(), Some("'b".to_owned()));
+        assert_eq!(root.add_fresh_lifetime().lifetime(), Some("'b".to_owned()));
+    }
+
+    #[test]
+    fn lifetime_sequence() {
+        assert_eq!(lifetime_text(0), "a");
+        assert_eq!(lifetime_text(25), "z");
+        assert_eq!(lifetime_text(26), "aa");
+        assert_eq!(lifetime_text(27), "ab");
+    }
+}
@@ -0,0 +1,231 @@
+/// Limits on the size of a tree, for services that build trees from untrusted programs,
+/// so that deeply nested or huge inputs fail cleanly instead of using unbounded memory.
+///
+/// Limits are off by default, and are checked when scopes and names are created or moved.
+/// The `try_` methods, like `Scope::try_add_named`, and the other fallible methods that add
+/// names or scopes, like `Scope::add_overload`, `Scope::declare`, `RootScope::from_spec`,
+/// `Scope::build_from`, `Scope::clone_subtree_with`, `Name::move_to` and
+/// `Scope::merge_sibling`, report a `LimitExceeded`. Only `add_named`, `add_prefixed`,
+/// `add_anonymous` and the `add_child` family panic instead, so callers that handle
+/// untrusted input should use their `try_` variants, or check first with
+/// `Scope::check_name_limits`. Moving scopes does not check the depth limit.
+use ::std::fmt;
+
+use crate::name::{GivenName, Name};
+use crate::scope::{AlreadyExists, RootScope, Scope};
+use crate::text::NameText;
+
+/// Bounds on the size of a tree; None means unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limits {
+    /// How many levels scopes can be nested; the root scope is at depth 0.
+    pub max_depth: Option<usize>,
+    /// How many scopes the tree can have, including the root and detached scopes.
+    pub max_scopes: Option<usize>,
+    /// How many given and anonymous names a single scope can have.
+    pub max_names_per_scope: Option<usize>,
+}
+
+/// Which limit an operation would exceed, with the value of that limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Depth(usize),
+    Scopes(usize),
+    NamesPerScope(usize),
+}
+
+/// Why a name could not be added to or moved into a scope, by the methods that check both
+/// for conflicts and for limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterError {
+    /// The scope already has a conflicting name.
+    AlreadyExists,
+    /// The scope cannot have more names, see `RootScope::set_limits`.
+    LimitExceeded(LimitExceeded),
+}
+
+impl From<AlreadyExists> for RegisterError {
+    fn from(_: AlreadyExists) -> Self {
+        RegisterError::AlreadyExists
+    }
+}
+
+impl From<LimitExceeded> for RegisterError {
+    fn from(exceeded: LimitExceeded) -> Self {
+        RegisterError::LimitExceeded(exceeded)
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitExceeded::Depth(max) => write!(f, "scopes cannot be nested deeper than {}", max),
+            LimitExceeded::Scopes(max) => write!(f, "tree cannot have more than {} scopes", max),
+            LimitExceeded::NamesPerScope(max) => {
+                write!(f, "scope cannot have more than {} names", max)
+            }
+        }
+    }
+}
+
+impl RootScope {
+    /// Bound the size of the tree from now on. Existing scopes and names are kept, even if
+    /// they exceed the limits.
+    pub fn set_limits(&self, limits: Limits) {
+        self.root_data.limits.set(limits);
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.root_data.limits.get()
+    }
+
+    /// Check that a scope can be created with the given parent.
+    pub(crate) fn check_scope_limits(&self, parent: Option<usize>) -> Result<(), LimitExceeded> {
+        let limits = self.limits();
+        if let Some(max_scopes) = limits.max_scopes {
+            if self.root_data.scopes.borrow().len() >= max_scopes {
+                return Err(LimitExceeded::Scopes(max_scopes));
+            }
+        }
+        if let (Some(max_depth), Some(parent)) = (limits.max_depth, parent) {
+            if self.scope_at_index(parent).depth() >= max_depth {
+                return Err(LimitExceeded::Depth(max_depth));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a name can be registered in the scope.
+    pub(crate) fn check_name_limits(&self, scope: usize) -> Result<(), LimitExceeded> {
+        if let Some(max_names) = self.limits().max_names_per_scope {
+            let count = self
+                .read_scope_names_at(scope, |data| data.given_names.len() + data.anon_names.len());
+            if count >= max_names {
+                return Err(LimitExceeded::NamesPerScope(max_names));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Scope {
+    /// How many ancestors this scope has, i.e. 0 for the root scope and detached scopes.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut current = self.parent();
+        while let Some(scope) = current {
+            depth += 1;
+            current = scope.parent();
+        }
+        depth
+    }
+
+    /// Like `add_child`, but fails instead of panicking if a limit would be exceeded.
+    pub fn try_add_child(&self) -> Result<Scope, LimitExceeded> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        self.root.check_scope_limits(Some(self.index))?;
+        Ok(self.add_child())
+    }
+
+    /// Like `add_named`, but fails instead of panicking if a limit would be exceeded. Merged
+    /// duplicates (see `RootScope::set_merge_duplicates`) do not count against the limit.
+    pub fn try_add_named(&self, name: &str) -> Result<Name, RegisterError> {
+        self.add_given(GivenName::new(NameText::from(name)))
+    }
+
+    /// Like `add_prefixed`, but fails instead of panicking if a limit would be exceeded.
+    pub fn try_add_prefixed(&self, prefix: &str) -> Result<Name, LimitExceeded> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        self.check_name_limits()?;
+        Ok(self.add_prefixed(prefix))
+    }
+
+    /// Whether another name can be registered in this scope without exceeding a limit,
+    /// e.g. before calling `add_named`.
+    pub fn check_name_limits(&self) -> Result<(), LimitExceeded> {
+        self.root.check_name_limits(self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use crate::generate::OutputPolicy;
+    use crate::limit::{LimitExceeded, Limits, RegisterError};
+    use crate::relocate::MergeError;
+    use crate::scope::RootScope;
+    use crate::validate::InvalidName;
+
+    #[test]
+    fn limits_fail_cleanly() {
+        let root = RootScope::builder()
+            .limits(Limits {
+                max_depth: Some(2),
+                max_scopes: Some(4),
+                max_names_per_scope: Some(2),
+            })
+            .build();
+        let child = root.try_add_child().unwrap();
+        let grandchild = child.try_add_child().unwrap();
+        assert_eq!(grandchild.depth(), 2);
+        assert_eq!(grandchild.try_add_child(), Err(LimitExceeded::Depth(2)));
+        root.try_add_child().unwrap();
+        assert_eq!(root.try_add_child(), Err(LimitExceeded::Scopes(4)));
+        assert!(catch_unwind(AssertUnwindSafe(|| root.add_child())).is_err());
+        root.try_add_named("x").unwrap();
+        root.try_add_prefixed("tmp").unwrap();
+        assert_eq!(
+            root.try_add_prefixed("tmp"),
+            Err(LimitExceeded::NamesPerScope(2))
+        );
+        assert_eq!(
+            root.check_name_limits(),
+            Err(LimitExceeded::NamesPerScope(2))
+        );
+        assert!(catch_unwind(AssertUnwindSafe(|| root.add_named("y"))).is_err());
+        assert_eq!(
+            root.try_add_named("y"),
+            Err(RegisterError::LimitExceeded(LimitExceeded::NamesPerScope(
+                2
+            )))
+        );
+        assert_eq!(root.try_add_named("x"), Err(RegisterError::AlreadyExists));
+        assert_eq!(root.names().len(), 2);
+    }
+
+    #[test]
+    fn limits_on_moves() {
+        let root = RootScope::builder()
+            .limits(Limits {
+                max_names_per_scope: Some(2),
+                ..Limits::default()
+            })
+            .build();
+        let first = root.add_child();
+        first.add_named("a").unwrap();
+        let b = first.add_named("b").unwrap();
+        let second = root.add_child();
+        let c = second.add_named("c").unwrap();
+        let policy = OutputPolicy::default();
+        assert_eq!(
+            first.add_validated("d", &policy),
+            Err(InvalidName::LimitExceeded(LimitExceeded::NamesPerScope(2)))
+        );
+        assert_eq!(
+            c.move_to(&first),
+            Err(RegisterError::LimitExceeded(LimitExceeded::NamesPerScope(
+                2
+            )))
+        );
+        assert_eq!(c.scope(), second);
+        assert_eq!(
+            first.merge_sibling(&second),
+            Err(MergeError::LimitExceeded(LimitExceeded::NamesPerScope(2)))
+        );
+        assert_eq!(second.parent(), Some(root.clone()));
+        first.remove_name(&b);
+        assert_eq!(first.merge_sibling(&second).unwrap(), vec![]);
+        assert_eq!(first.names().len(), 2);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::limit::RegisterError;
+/// Macro names, like `vec!`, in their own namespace, since a macro and a function with the
+/// same name can coexist in Rust.
+///
+/// The bang is not part of the stored text: it is stripped on input, and only added back
+/// for invocations by `Name::macro_call`, since definitions like `macro_rules! vec` and
+/// imports like `use crate::vec` do not include it.
+use crate::name::{InputName, Name};
+use crate::scope::Scope;
+
+/// The namespace that macros are registered in.
+pub(crate) const MACRO_NAMESPACE: &str = "macro";
+
+impl Scope {
+    /// Declare a macro in this scope, with or without the trailing bang. Fails if a macro
+    /// with that name is already declared here, but not if a value is.
+    pub fn add_macro(&self, name: &str) -> Result<Name, RegisterError> {
+        self.namespace(MACRO_NAMESPACE)
+            .add_named(name.strip_suffix('!').unwrap_or(name))
+    }
+
+    /// Find the nearest declaration of the macro, with or without the trailing bang.
+    pub fn resolve_macro(&self, name: &str) -> Option<Name> {
+        self.namespace(MACRO_NAMESPACE)
+            .resolve(name.strip_suffix('!').unwrap_or(name))
+    }
+}
+
+impl Name {
+    /// The text to invoke this macro, including the bang, or None if this is not a macro.
+    pub fn macro_call(&self) -> Option<String> {
+        match &self.data {
+            InputName::Given(given) if given.namespace() == Some(MACRO_NAMESPACE) => {
+                Some(format!("{}!", given.as_str()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn macros_beside_values() {
+        let root = RootScope::new_root();
+        let function = root.add_named("format").unwrap();
+        let makro = root.add_macro("format!").unwrap();
+        root.add_macro("format").unwrap_err();
+        let child = root.add_child();
+        assert_eq!(child.resolve("format"), Some(function.clone()));
+        assert_eq!(child.resolve_macro("format"), Some(makro.clone()));
+        assert_eq!(makro.macro_call(), Some("format!".to_owned()));
+        assert_eq!(makro.unwrap_given().as_str(), "format");
+        assert_eq!(function.macro_call(), None);
+    }
+}
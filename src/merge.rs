@@ -0,0 +1,67 @@
+/// Merging of repeated registrations, for front-ends that legitimately see the same
+/// declaration more than once, like repeated `extern` declarations in C.
+///
+/// When enabled on the root, `Scope::add_named` returns the existing name if the exact same
+/// given name is already registered in the scope, and counts the registration. It still
+/// fails if the text is taken in another way, like by overloads or a name that only differs
+/// in case.
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+impl RootScope {
+    /// Whether repeated registrations of a given name in a scope return the original name,
+    /// instead of failing. Off by default.
+    pub fn set_merge_duplicates(&self, merge: bool) {
+        self.root_data.merge_duplicates.set(merge);
+    }
+
+    pub fn merges_duplicates(&self) -> bool {
+        self.root_data.merge_duplicates.get()
+    }
+}
+
+impl Name {
+    /// How often this name was registered, which is more than one only if duplicates were
+    /// merged into it.
+    pub fn declaration_count(&self) -> usize {
+        self.root.root_data.names.borrow()[self.index].declarations
+    }
+}
+
+impl Scope {
+    /// Count another registration of an identical given name and return the existing name,
+    /// or None if the root does not merge duplicates or there is no identical name.
+    pub(crate) fn merge_duplicate(&self, given_name: &GivenName) -> Option<Name> {
+        if !self.root.merges_duplicates() {
+            return None;
+        }
+        let existing = self.find_given(given_name)?;
+        match &existing.data {
+            InputName::Given(existing_given) if existing_given == given_name => {}
+            _ => return None,
+        }
+        self.root.root_data.names.borrow_mut()[existing.index].declarations += 1;
+        Some(existing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::overload::OverloadKey;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn merge_repeated_declarations() {
+        let root = RootScope::new_root();
+        root.add_named("errno").unwrap();
+        root.add_named("errno").unwrap_err();
+        root.root().set_merge_duplicates(true);
+        let first = root.add_named("malloc").unwrap();
+        let second = root.add_named("malloc").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.declaration_count(), 2);
+        assert_eq!(root.names().len(), 2);
+        root.add_overload("f", OverloadKey(1)).unwrap();
+        root.add_named("f").unwrap_err();
+    }
+}
@@ -0,0 +1,157 @@
+/// Mixin scopes: secondary parents whose names are visible in a scope, like traits or
+/// mixins included into a class.
+///
+/// Resolution checks, in each scope on the way out, first the scope's own names and then
+/// those of its mixins, in the order they were added. A mixin's own mixins are included,
+/// but not its lexical parents. A scope's own names may override names of its mixins, but
+/// mixins may not collide with each other.
+use ::std::collections::HashSet;
+
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{AlreadyExists, Scope};
+
+impl Scope {
+    /// Make the names of another scope in the same tree visible in this one, with lower
+    /// precedence than this scope's own names and earlier mixins.
+    ///
+    /// Fails if the mixin has a given name that an earlier mixin also has, since it would
+    /// be ambiguous which one is meant.
+    pub fn add_mixin(&self, mixin: &Scope) -> Result<(), AlreadyExists> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        assert!(
+            self.root == mixin.root,
+            "cannot mix in a scope of another tree"
+        );
+        self.root.assert_unsealed(self.index);
+        let earlier = self.mixins();
+        for given_name in mixin.given_names() {
+            if earlier
+                .iter()
+                .any(|other| other.find_visible_given(&given_name).is_some())
+            {
+                return Err(AlreadyExists());
+            }
+        }
+        self.root
+            .scope_data_at(self.index, |data| data.mixins.push(mixin.index));
+        self.root.mark_dirty(self.index);
+        Ok(())
+    }
+
+    /// The mixins of this scope, in order of precedence.
+    pub fn mixins(&self) -> Vec<Scope> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.mixins.clone())
+            .into_iter()
+            .map(|index| self.root.scope_at_index(index))
+            .collect()
+    }
+
+    /// Look up a given name registered or imported in this scope or one of its mixins.
+    pub(crate) fn find_visible_given(&self, given_name: &GivenName) -> Option<Name> {
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.clone()];
+        while let Some(scope) = pending.pop() {
+            if !visited.insert(scope.index) {
+                continue;
+            }
+            if let Some(name) = scope
+                .find_given(given_name)
+                .or_else(|| scope.find_imported(given_name))
+            {
+                return Some(name);
+            }
+            let mut mixins = scope.mixins();
+            mixins.reverse();
+            pending.extend(mixins);
+        }
+        None
+    }
+
+    /// The names that resolution finds in this scope before moving on to the parent: its
+    /// own names, then the given names imported into it and those of its mixins, except
+    /// where an earlier one has the same text (in the same namespace) and hides them.
+    pub(crate) fn visible_names(&self) -> Vec<Name> {
+        let is_case_insensitive = self.is_case_insensitive();
+        let key = |name: &Name| match &name.data {
+            InputName::Given(given) if is_case_insensitive => Some((
+                given.namespace().map(str::to_owned),
+                given.as_str().to_lowercase(),
+            )),
+            InputName::Given(given) => Some((
+                given.namespace().map(str::to_owned),
+                given.as_str().to_owned(),
+            )),
+            InputName::Anonymous(_) => None,
+        };
+        let mut visible = self.names();
+        let mut hidden: HashSet<_> = visible.iter().filter_map(key).collect();
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.clone()];
+        while let Some(scope) = pending.pop() {
+            if !visited.insert(scope.index) {
+                continue;
+            }
+            let mut sources = vec![scope.imported_names()];
+            if scope != *self {
+                sources.insert(0, scope.names());
+            }
+            for source in sources {
+                let found: Vec<Name> = source
+                    .into_iter()
+                    .filter(|name| key(name).is_some_and(|key| !hidden.contains(&key)))
+                    .collect();
+                hidden.extend(found.iter().filter_map(key));
+                visible.extend(found);
+            }
+            let mut mixins = scope.mixins();
+            mixins.reverse();
+            pending.extend(mixins);
+        }
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn resolve_through_mixins() {
+        let root = RootScope::new_root();
+        let outer_x = root.add_named("x").unwrap();
+        let first = root.add_child();
+        let first_x = first.add_named("x").unwrap();
+        let second = root.add_child();
+        let y = second.add_named("y").unwrap();
+        let nested = root.add_child();
+        let z = nested.add_named("z").unwrap();
+        second.add_mixin(&nested).unwrap();
+        let class = root.add_child();
+        let method = class.add_child();
+        class.add_mixin(&first).unwrap();
+        class.add_mixin(&second).unwrap();
+        assert_eq!(method.resolve("x"), Some(first_x.clone()));
+        assert_eq!(method.resolve("y"), Some(y));
+        assert_eq!(method.resolve("z"), Some(z));
+        assert_eq!(method.shadow_chain("x"), vec![first_x, outer_x]);
+        assert_eq!(class.mixins(), vec![first.clone(), second.clone()]);
+        let overriding = root.add_child();
+        let own_y = overriding.add_named("y").unwrap();
+        overriding.add_mixin(&second).unwrap();
+        assert_eq!(overriding.resolve("y"), Some(own_y));
+        let conflicting = root.add_child();
+        conflicting.add_named("x").unwrap();
+        assert!(class.add_mixin(&conflicting).is_err());
+    }
+
+    #[test]
+    fn mixin_cycle() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let second = root.add_child();
+        first.add_mixin(&second).unwrap();
+        second.add_mixin(&first).unwrap();
+        assert!(first.resolve("missing").is_none());
+    }
+}
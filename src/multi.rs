@@ -0,0 +1,119 @@
+/// Generating outputs for several profiles of the same tree at once, e.g. a readable debug
+/// build next to a minified release build, with a table that maps between them.
+///
+/// Each profile is generated independently, so its outputs are the same as those of
+/// `RootScope::generate_names` with its policy. `Name::output` gives the outputs of the
+/// first profile.
+use crate::generate::{GenerateError, NameMap, OutputName, OutputPolicy};
+use crate::name::Name;
+use crate::scope::RootScope;
+
+/// The outputs of a tree for each profile, see `RootScope::generate_profiles`.
+#[derive(Debug, Clone)]
+pub struct ProfileMaps {
+    // In the order the profiles were given.
+    maps: Vec<(String, NameMap)>,
+}
+
+impl RootScope {
+    /// Generate outputs for each named profile, failing if any of them fails.
+    pub fn generate_profiles(
+        &self,
+        profiles: &[(&str, &OutputPolicy)],
+    ) -> Result<ProfileMaps, GenerateError> {
+        let maps = profiles
+            .iter()
+            .map(|(profile, policy)| Ok(((*profile).to_owned(), self.generate_uncached(policy)?)))
+            .collect::<Result<Vec<_>, GenerateError>>()?;
+        if let Some((_, first)) = maps.first() {
+            self.cache_outputs(first);
+        }
+        Ok(ProfileMaps { maps })
+    }
+}
+
+impl ProfileMaps {
+    /// The outputs of the profile, or None if there is no profile with this name.
+    pub fn get(&self, profile: &str) -> Option<&NameMap> {
+        self.maps
+            .iter()
+            .find(|(name, _)| name == profile)
+            .map(|(_, map)| map)
+    }
+
+    /// The names of the profiles, in the order they were given.
+    pub fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.maps.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// For every name that has an output, its output in each profile, in the order of
+    /// `profiles`. Names are in registration order; removed names are skipped.
+    pub fn table(&self) -> Vec<(Name, Vec<OutputName>)> {
+        let first = match self.maps.first() {
+            Some((_, first)) => first,
+            None => return vec![],
+        };
+        first
+            .iter()
+            .map(|(name, _)| {
+                let outputs = self
+                    .maps
+                    .iter()
+                    .filter_map(|(_, map)| map.get(&name).cloned())
+                    .collect();
+                (name, outputs)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+    use crate::strategy::Strategy;
+
+    #[test]
+    fn debug_and_release_profiles() {
+        let root = RootScope::new_root();
+        let counter = root.add_named("counter").unwrap();
+        let tmp = root.add_child().add_prefixed("tmp");
+        let debug = OutputPolicy::default();
+        let release = OutputPolicy {
+            strategy: Strategy::Minify,
+            ..OutputPolicy::default()
+        };
+        let maps = root
+            .root()
+            .generate_profiles(&[("debug", &debug), ("release", &release)])
+            .unwrap();
+        assert_eq!(
+            maps.profiles().collect::<Vec<_>>(),
+            vec!["debug", "release"]
+        );
+        assert_eq!(
+            maps.get("release").unwrap().get(&counter).unwrap().as_str(),
+            "a"
+        );
+        assert!(maps.get("test").is_none());
+        let table: Vec<(String, Vec<String>)> = maps
+            .table()
+            .into_iter()
+            .map(|(name, outputs)| {
+                let outputs = outputs.into_iter().map(String::from).collect();
+                (name.to_string(), outputs)
+            })
+            .collect();
+        assert_eq!(
+            table,
+            vec![
+                (
+                    "counter".to_owned(),
+                    vec!["counter".to_owned(), "a".to_owned()]
+                ),
+                (tmp.to_string(), vec!["tmp".to_owned(), "b".to_owned()]),
+            ]
+        );
+        assert_eq!(counter.output().unwrap().as_str(), "counter");
+    }
+}
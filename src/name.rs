@@ -1,16 +1,120 @@
-use crate::scope::Scope;
+use ::std::convert::TryFrom;
+use ::std::fmt;
+use ::std::hash;
+
+use crate::external::ExternalSymbol;
+use crate::generate::OutputName;
+use crate::overload::OverloadKey;
+use crate::scope::{RootId, RootScope, Scope};
+use crate::text::NameText;
+use crate::visibility::Visibility;
 use ustr::Ustr;
 
 /// An identifier, either anonymous or given.
 ///
-/// Instances should be created through `Scope`.
-#[derive(Debug, Clone, PartialEq)]
+/// Instances should be created through `Scope`. Names are equal if and only if they
+/// come from the same registration, so clones are equal to the original, but two
+/// registrations with the same text in different scopes or trees are not.
+///
+/// Equality and hashing only look at the root id and the index of the registration, so they
+/// take constant time, do not compare text, and do not borrow the tree. This makes names
+/// cheap keys for hash maps.
+#[derive(Clone)]
 pub struct Name {
-    pub(crate) scope: Scope,
+    pub(crate) root: RootScope,
     pub(crate) data: InputName,
+    // Index in the root's names arena, which also records the scope.
+    pub(crate) index: usize,
+}
+
+/// Lightweight handle to a name, e.g. for keying side tables by plain integers.
+///
+/// It is only meaningful together with the root it came from, see `RootScope::name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NameId(pub usize);
+
+/// Small copyable handle to a name, for hot data structures like instruction operands,
+/// where cloning a `Name` (which holds a reference-counted root) is too heavy.
+///
+/// Unlike `NameId`, it records which tree it belongs to, so `NameRef::get` fails instead
+/// of returning an unrelated name when given the wrong root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NameRef {
+    root: RootId,
+    index: u32,
+}
+
+impl NameRef {
+    pub fn id(&self) -> NameId {
+        NameId(self.index as usize)
+    }
+
+    /// The full name, or None if it belongs to another tree or was removed.
+    pub fn get(&self, root: &RootScope) -> Option<Name> {
+        if root.id() != self.root {
+            return None;
+        }
+        root.name(self.id())
+    }
+}
+
+/// The record of a name in the root's arena.
+#[derive(Debug, Clone)]
+pub(crate) struct NameData {
+    // Arena index of the scope the name is registered in.
+    pub(crate) scope: usize,
+    pub(crate) data: InputName,
+    // The pass that created this name, if known.
+    pub(crate) provenance: Option<Ustr>,
+    // Set when the name is unregistered from its scope.
+    pub(crate) removed: bool,
+    // Configuration flag that must be active for the name to exist, if any.
+    pub(crate) condition: Option<Ustr>,
+    // Whether the name is exported from its scope.
+    pub(crate) visibility: Visibility,
+    // Message to show when the name is used, if it is deprecated.
+    pub(crate) deprecated: Option<Ustr>,
+    // How often the name was registered, if duplicates are merged.
+    pub(crate) declarations: usize,
+    // The symbol in another library that this name refers to, if any.
+    pub(crate) external: Option<ExternalSymbol>,
+    // Arena index of the outer name this one intentionally shadows, if registered as such.
+    pub(crate) shadows: Option<usize>,
+    // Names that must be emitted after this one, as arena indices.
+    pub(crate) emit_before: Vec<usize>,
+    // Key that fixes the ordinal of an anonymous name, if it was registered with one.
+    pub(crate) ordinal_key: Option<u64>,
+    // Names that must get the same output as this one, as arena indices.
+    pub(crate) unified: Vec<usize>,
+    // Arena index of the name this one was generated from, and how, if recorded.
+    pub(crate) derived_from: Option<(usize, Ustr)>,
+    // The output from the latest generation, see `Name::output`.
+    pub(crate) output: Option<OutputName>,
 }
 
 impl Name {
+    pub fn id(&self) -> NameId {
+        NameId(self.index)
+    }
+
+    /// The scope the name is currently registered in.
+    pub fn scope(&self) -> Scope {
+        let scope_index = self.root.root_data.names.borrow()[self.index].scope;
+        self.root.scope_at_index(scope_index)
+    }
+
+    pub fn to_ref(&self) -> NameRef {
+        NameRef {
+            root: self.root.id(),
+            index: u32::try_from(self.index).expect("too many names for a NameRef"),
+        }
+    }
+
+    /// The tree that the name belongs to.
+    pub fn root(&self) -> &RootScope {
+        &self.root
+    }
+
     pub fn unwrap_given(self) -> GivenName {
         match self.data {
             InputName::Given(given) => given,
@@ -19,29 +123,146 @@ impl Name {
     }
 }
 
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.root == other.root
+    }
+}
+
+impl Eq for Name {}
+
+impl hash::Hash for Name {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.root.hash(state);
+        self.index.hash(state)
+    }
+}
+
+/// Shows the path (see `Display`) instead of the scope and root, so that the output does not
+/// contain the random root id, and is the same in every run that builds the same tree.
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Name");
+        debug.field("path", &self.to_string());
+        debug.field("data", &self.data);
+        if let Some(provenance) = self.provenance() {
+            debug.field("provenance", &provenance);
+        }
+        debug.finish()
+    }
+}
+
 /// A given identifier that should not collide within a scope.
+///
+/// This is the identity of the name within its scope, so it compares by text (and overload
+/// key, arity, version or namespace). It is a plain value that does not record where it was
+/// registered: scopes key their lookup tables on it, and `GivenName::new` builds one to look
+/// names up with. Use `Name`, which compares by registration, to distinguish registrations.
+///
+/// Comparing texts takes constant time: short texts are stored inline, and longer ones are
+/// interned and compared by pointer. Only with the `owned-names` feature are long texts
+/// compared by content.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct GivenName {
-    // Index in the scope's string 'arena'.
-    pub(crate) name: Ustr,
+    // The text, stored inline if it is short.
+    pub(crate) name: NameText,
+    // Distinguishes overloads of the same name; None for ordinary names.
+    pub(crate) overload: Option<OverloadKey>,
+    // Part of the identity for `name/arity` style names; None for ordinary names.
+    pub(crate) arity: Option<u32>,
+    // The version from which this declaration applies; None for unversioned names.
+    pub(crate) version: Option<u32>,
+    // The collision domain of this name; None for the ordinary value namespace.
+    pub(crate) namespace: Option<Ustr>,
+}
+
+impl GivenName {
+    /// An ordinary given name in the value namespace, without overload key, arity or version.
+    pub(crate) fn new(name: NameText) -> Self {
+        GivenName {
+            name,
+            overload: None,
+            arity: None,
+            version: None,
+            namespace: None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// The key that distinguishes this overload, or None for ordinary names.
+    pub fn overload_key(&self) -> Option<OverloadKey> {
+        self.overload
+    }
+
+    /// The arity that is part of this name's identity, if it was registered with one.
+    pub fn arity(&self) -> Option<u32> {
+        self.arity
+    }
+
+    /// The version from which this declaration applies, if it was registered with one.
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// The namespace of this name, or None for the ordinary value namespace.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_ref().map(|namespace| namespace.as_str())
+    }
 }
 
 /// An anonymous identifier, optionally with a prefix.
-#[derive(Debug, Clone)]
+///
+/// Anonymous names have no identifying text, so they are only equal to copies of the
+/// same registration, even if the prefixes match.
+#[derive(Clone)]
 pub struct AnonName {
-    // Index in the scope's string 'arena'.
-    // Empty string is used to mean 'no prefix'.
-    pub(crate) name: Ustr,
+    // The prefix; the empty string is used to mean 'no prefix'.
+    pub(crate) name: NameText,
+    // Id of the root, and index in its names arena, of the registration.
+    pub(crate) root: RootId,
+    pub(crate) index: usize,
+}
+
+impl AnonName {
+    /// The prefix, or None if the name was created without one.
+    pub fn prefix(&self) -> Option<&str> {
+        if self.name.as_str().is_empty() {
+            None
+        } else {
+            Some(self.name.as_str())
+        }
+    }
+}
+
+// Leaves out the root id, which differs between runs, and the arena index, which depends on
+// thread timing with the `sync` feature. The ordinal is part of the path of the `Name`.
+impl fmt::Debug for AnonName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnonName")
+            .field("prefix", &self.name)
+            .finish()
+    }
 }
 
-/// Only given identifiers can be equal; anonymous ones have no identifying information, so are assumed non-equal.
 impl PartialEq for AnonName {
-    fn eq(&self, _other: &Self) -> bool {
-        false
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.root == other.root
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Eq for AnonName {}
+
+impl hash::Hash for AnonName {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.root.hash(state);
+        self.index.hash(state)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum InputName {
     Given(GivenName),
     Anonymous(AnonName),
@@ -49,6 +270,8 @@ pub(crate) enum InputName {
 
 #[cfg(test)]
 mod mixed {
+    use ::std::collections::HashSet;
+
     use crate::scope::RootScope;
 
     #[test]
@@ -56,14 +279,85 @@ mod mixed {
         let scope = RootScope::new_root();
         let name1 = scope.add_prefixed("hello");
         let name2 = scope.add_named("hello").unwrap();
-        assert_ne!(name1, name1);
+        assert_eq!(name1, name1);
         assert_eq!(name2, name2.clone());
         assert_ne!(name1, name2);
     }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn compare_without_borrowing() {
+        let scope = RootScope::new_root();
+        let name = scope.add_named("hello").unwrap();
+        let copy = scope.root().name(name.id()).unwrap();
+        let other = scope.add_prefixed("hello");
+        let mut names = HashSet::new();
+        // Comparing and hashing must not touch the arena, even while it is being changed.
+        let _arena = scope.root().root_data.names.borrow_mut();
+        names.insert(name.clone());
+        assert!(names.contains(&copy));
+        assert!(!names.contains(&other));
+        assert_eq!(name, copy);
+    }
+
+    #[test]
+    fn debug_is_stable() {
+        let build = || {
+            let root = RootScope::new_root();
+            let function = root.add_child();
+            function.set_label("f");
+            function.add_prefixed("tmp");
+            function.add_prefixed("tmp")
+        };
+        let tmp = build();
+        assert_eq!(format!("{:?}", tmp), format!("{:?}", build()));
+        assert!(format!("{:?}", tmp).starts_with("Name { path: \"f::tmp#1\", "));
+    }
+}
+
+#[cfg(test)]
+mod navigation {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn scope_and_root() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let x = child.add_named("x").unwrap();
+        let y = child.add_named("y").unwrap();
+        assert_eq!(x.scope(), child);
+        assert_eq!(x.root(), root.root());
+        assert_eq!(x.scope().names(), vec![x.clone(), y]);
+        assert_eq!(x.scope().parent(), Some(root));
+    }
+}
+
+#[cfg(test)]
+mod handle {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn name_ref_round_trip() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let tmp = root.add_prefixed("tmp");
+        let x_ref = x.to_ref();
+        assert_eq!(x_ref, x.clone().to_ref());
+        assert_ne!(x_ref, tmp.to_ref());
+        assert_eq!(x_ref.id(), x.id());
+        assert_eq!(x_ref.get(root.root()), Some(x.clone()));
+        let other = RootScope::new_root();
+        other.add_named("x").unwrap();
+        assert_eq!(x_ref.get(other.root()), None);
+        root.remove_name(&x);
+        assert_eq!(x_ref.get(root.root()), None);
+    }
 }
 
 #[cfg(test)]
 mod anonymous {
+    use ::std::collections::HashSet;
+
     use crate::scope::RootScope;
 
     #[test]
@@ -71,8 +365,8 @@ mod anonymous {
         let scope = RootScope::new_root();
         let name1 = scope.add_prefixed("hello");
         let name2 = scope.add_prefixed("hello");
-        assert_ne!(name1, name1);
-        assert_ne!(name2, name2.clone());
+        assert_eq!(name1, name1);
+        assert_eq!(name2, name2.clone());
         assert_ne!(name1, name2);
     }
 
@@ -81,10 +375,32 @@ mod anonymous {
         let scope = RootScope::new_root();
         let name1 = scope.add_anonymous();
         let name2 = scope.add_anonymous();
-        assert_ne!(name1, name1);
-        assert_ne!(name2, name2.clone());
+        assert_eq!(name1, name1);
+        assert_eq!(name2, name2.clone());
         assert_ne!(name1, name2);
     }
+
+    #[test]
+    // The hash only uses the root number and arena index, which never change.
+    #[allow(clippy::mutable_key_type)]
+    fn anonymous_hash() {
+        let scope = RootScope::new_root();
+        let name1 = scope.add_prefixed("tmp");
+        let name2 = scope.add_prefixed("tmp");
+        let mut set = HashSet::new();
+        assert!(set.insert(name1.clone()));
+        assert!(set.insert(name2));
+        assert!(!set.insert(name1));
+        assert_eq!(scope.anonymous_names()[0], scope.anonymous_names()[0]);
+    }
+
+    #[test]
+    fn same_text_other_tree_ne() {
+        let first = RootScope::new_root().add_prefixed("tmp");
+        let second = RootScope::new_root().add_prefixed("tmp");
+        assert_ne!(first, second);
+        assert_ne!(first.data, second.data);
+    }
 }
 
 #[cfg(test)]
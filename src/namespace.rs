@@ -0,0 +1,116 @@
+/// Custom namespaces, so that one tree can manage several families of identifiers, e.g.
+/// variables and CSS classes, that share the scope structure but do not collide.
+///
+/// Names in a namespace are given names like any other, so they can be moved, removed or
+/// inspected as usual. They are only invisible to lookups in other namespaces, including
+/// `Scope::resolve`, which uses the ordinary value namespace.
+use ::ustr::Ustr;
+
+use crate::limit::RegisterError;
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+/// A scope seen through one namespace. Cheap to create, and holds no state of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Namespace {
+    scope: Scope,
+    namespace: Ustr,
+}
+
+impl Scope {
+    /// View this scope in the namespace with the given name. Namespaces exist implicitly, so
+    /// using the same name again gives the same namespace.
+    pub fn namespace(&self, namespace: &str) -> Namespace {
+        Namespace {
+            scope: self.clone(),
+            namespace: Ustr::from(namespace),
+        }
+    }
+}
+
+impl Namespace {
+    pub fn name(&self) -> &str {
+        self.namespace.as_str()
+    }
+
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    /// The same namespace in the parent scope, or None at the root.
+    pub fn parent(&self) -> Option<Namespace> {
+        self.scope.parent().map(|parent| Namespace {
+            scope: parent,
+            namespace: self.namespace,
+        })
+    }
+
+    /// Register a named identifier in this namespace, failing if it is already registered
+    /// here. Names in other namespaces of the scope do not conflict.
+    pub fn add_named(&self, name: &str) -> Result<Name, RegisterError> {
+        self.scope.add_given(self.given_name(name))
+    }
+
+    /// The declaration of the name directly in this scope in this namespace, if any.
+    pub fn get(&self, name: &str) -> Option<Name> {
+        self.scope.find_given(&self.given_name(name))
+    }
+
+    /// Find the nearest declaration of the name in this namespace, starting from this scope
+    /// and moving outwards.
+    pub fn resolve(&self, name: &str) -> Option<Name> {
+        let given_name = self.given_name(name);
+        let mut current = Some(self.scope.clone());
+        while let Some(scope) = current {
+            if let Some(name) = scope.find_given(&given_name) {
+                return Some(name);
+            }
+            current = scope.parent();
+        }
+        None
+    }
+
+    /// The names declared directly in this scope in this namespace, in registration order.
+    pub fn names(&self) -> Vec<Name> {
+        self.scope
+            .names()
+            .into_iter()
+            .filter(|name| match &name.data {
+                InputName::Given(given) => given.namespace == Some(self.namespace),
+                InputName::Anonymous(_) => false,
+            })
+            .collect()
+    }
+
+    fn given_name(&self, name: &str) -> GivenName {
+        GivenName {
+            namespace: Some(self.namespace),
+            ..self.scope.root.given_name(name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn independent_namespaces() {
+        let root = RootScope::new_root();
+        let value = root.add_named("header").unwrap();
+        let class = root.namespace("css-classes").add_named("header").unwrap();
+        let id = root.namespace("css-ids").add_named("header").unwrap();
+        root.namespace("css-classes")
+            .add_named("header")
+            .unwrap_err();
+        let child = root.add_child();
+        let classes = child.namespace("css-classes");
+        assert_eq!(child.resolve("header"), Some(value));
+        assert_eq!(classes.resolve("header"), Some(class.clone()));
+        assert_eq!(child.namespace("css-ids").resolve("header"), Some(id));
+        assert!(classes.resolve("footer").is_none());
+        assert_eq!(classes.parent().unwrap().names(), vec![class.clone()]);
+        assert_eq!(class.unwrap_given().namespace(), Some("css-classes"));
+    }
+}
@@ -0,0 +1,200 @@
+/// Preorder and postorder numbering of the scopes, so that a subtree is a contiguous range
+/// of preorder numbers, and checking whether a scope is in a subtree takes constant time.
+///
+/// The numbering is computed on first use after the shape of the tree changed, so it is
+/// cheap while building a tree, as long as it is not queried between every change.
+/// Detached scopes are not reachable from the root, so they have no number.
+///
+/// The same traversal gives the documented order of all names, which does not depend on
+/// hashing, so that e.g. rename tables are the same in every run and easy to diff.
+use ::std::iter::{once, successors};
+
+use crate::name::{InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+/// How names are ordered within each scope by `RootScope::names_in_order`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GenerationOrder {
+    /// In the order they were registered.
+    #[default]
+    Registration,
+    /// By text, or by prefix for anonymous names; equal texts in registration order.
+    ByText,
+}
+
+#[derive(Debug)]
+pub(crate) struct ScopeOrder {
+    // Indexed by arena index of the scope; None for detached scopes.
+    preorder: Vec<Option<usize>>,
+    postorder: Vec<Option<usize>>,
+    // The highest preorder number in the subtree of each scope.
+    subtree_end: Vec<usize>,
+}
+
+impl RootScope {
+    /// All names in the tree, with scopes in preorder (parents before children, children in
+    /// order), and the names within each scope in the given order. Names in detached scopes
+    /// are not included.
+    pub fn names_in_order(&self, order: GenerationOrder) -> Vec<Name> {
+        let root = self.root_scope();
+        once(root.clone())
+            .chain(root.descendants())
+            .flat_map(|scope| {
+                let mut names = scope.names();
+                if order == GenerationOrder::ByText {
+                    names.sort_by(|first, second| text(first).cmp(text(second)));
+                }
+                names
+            })
+            .collect()
+    }
+
+    /// Forget the numbering, because scopes were added, moved or removed.
+    pub(crate) fn shape_changed(&self) {
+        self.root_data.order.replace(None);
+    }
+
+    fn with_order<T>(&self, f: impl FnOnce(&ScopeOrder) -> T) -> T {
+        let mut order = self.root_data.order.borrow_mut();
+        let order = order.get_or_insert_with(|| self.compute_order());
+        f(order)
+    }
+
+    fn compute_order(&self) -> ScopeOrder {
+        let scopes = self.root_data.scopes.borrow();
+        let mut order = ScopeOrder {
+            preorder: vec![None; scopes.len()],
+            postorder: vec![None; scopes.len()],
+            subtree_end: vec![0; scopes.len()],
+        };
+        let mut next_pre = 0;
+        let mut next_post = 0;
+        // Each entry is a scope and whether its children have been visited.
+        let mut pending = vec![(0, false)];
+        while let Some((index, is_visited)) = pending.pop() {
+            if is_visited {
+                order.postorder[index] = Some(next_post);
+                order.subtree_end[index] = next_pre - 1;
+                next_post += 1;
+                continue;
+            }
+            order.preorder[index] = Some(next_pre);
+            next_pre += 1;
+            pending.push((index, true));
+            // Push the children last to first, so that the first one is visited first.
+            pending.extend(
+                successors(scopes[index].last_child, |&child| {
+                    scopes[child].prev_sibling
+                })
+                .map(|child| (child, false)),
+            );
+        }
+        order
+    }
+}
+
+impl Scope {
+    /// The position of this scope in a depth-first traversal that visits parents before
+    /// their children, or None for detached scopes.
+    pub fn preorder_index(&self) -> Option<usize> {
+        self.root.with_order(|order| order.preorder[self.index])
+    }
+
+    /// The position of this scope in a depth-first traversal that visits children before
+    /// their parents, or None for detached scopes.
+    pub fn postorder_index(&self) -> Option<usize> {
+        self.root.with_order(|order| order.postorder[self.index])
+    }
+
+    /// Whether this scope is the other scope or one of its descendants.
+    pub fn is_in_subtree_of(&self, other: &Scope) -> bool {
+        if self.root != other.root {
+            return false;
+        }
+        self.root.with_order(|order| {
+            match (order.preorder[self.index], order.preorder[other.index]) {
+                (Some(position), Some(start)) => {
+                    start <= position && position <= order.subtree_end[other.index]
+                }
+                _ => {
+                    // Detached subtrees are not numbered, so walk up instead.
+                    let mut current = Some(self.clone());
+                    while let Some(scope) = current {
+                        if scope == *other {
+                            return true;
+                        }
+                        current = scope.parent();
+                    }
+                    false
+                }
+            }
+        })
+    }
+}
+
+fn text(name: &Name) -> &str {
+    match &name.data {
+        InputName::Given(given) => given.as_str(),
+        InputName::Anonymous(anon) => anon.name.as_str(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    use super::GenerationOrder;
+
+    #[test]
+    fn numbering() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let nested = first.add_child();
+        let second = root.add_child();
+        assert_eq!(root.preorder_index(), Some(0));
+        assert_eq!(nested.preorder_index(), Some(2));
+        assert_eq!(second.preorder_index(), Some(3));
+        assert_eq!(nested.postorder_index(), Some(0));
+        assert_eq!(root.postorder_index(), Some(3));
+        assert!(nested.is_in_subtree_of(&first));
+        assert!(nested.is_in_subtree_of(&root));
+        assert!(first.is_in_subtree_of(&first));
+        assert!(!second.is_in_subtree_of(&first));
+        assert!(!root.is_in_subtree_of(&first));
+    }
+
+    #[test]
+    fn renumber_after_change() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let second = root.add_child();
+        assert_eq!(second.preorder_index(), Some(2));
+        root.reorder_children(&[1, 0]).unwrap();
+        assert_eq!(second.preorder_index(), Some(1));
+        second.reparent(&first).unwrap();
+        assert!(second.is_in_subtree_of(&first));
+        first.clear_children();
+        assert_eq!(second.preorder_index(), None);
+        assert!(!second.is_in_subtree_of(&root));
+        let nested = second.add_child();
+        assert!(nested.is_in_subtree_of(&second));
+    }
+
+    #[test]
+    fn names_in_documented_order() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let zeta = child.add_named("zeta").unwrap();
+        let alpha = child.add_named("alpha").unwrap();
+        let tmp = root.add_prefixed("tmp");
+        let main = root.add_named("main").unwrap();
+        assert_eq!(
+            root.root().names_in_order(GenerationOrder::Registration),
+            vec![tmp.clone(), main.clone(), zeta.clone(), alpha.clone()]
+        );
+        assert_eq!(
+            root.root().names_in_order(GenerationOrder::ByText),
+            vec![main, tmp, alpha, zeta]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+/// Ordinals of anonymous names, like the 1 in `tmp#1`, which are what distinguishes them in
+/// paths, diagnostics and generated output.
+///
+/// Ordinals count per scope and per prefix, so they do not depend on what happens in other
+/// scopes, even if other threads add to them at the same time with the `sync` feature. By
+/// default they follow registration order within the scope. If several threads add
+/// anonymous names to the same scope, that order depends on timing; registering with a key
+/// (e.g. a source offset or the number of a work item) makes the ordinals reproducible.
+use crate::name::{InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+impl Scope {
+    /// Like `add_prefixed`, but the ordinal of the name is decided by the key rather than by
+    /// when it was registered: keyed names are numbered after the unkeyed ones with the same
+    /// prefix, in order of their keys. Names with equal keys are numbered in registration
+    /// order, so keys should be unique for the ordinals to be reproducible.
+    pub fn add_prefixed_keyed(&self, prefix: &str, key: u64) -> Name {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        let name = self.add_prefixed(prefix);
+        self.root.root_data.names.borrow_mut()[name.index].ordinal_key = Some(key);
+        name
+    }
+}
+
+impl Name {
+    /// The position of this anonymous name among those with the same prefix in its scope,
+    /// or None for given names.
+    pub fn anonymous_ordinal(&self) -> Option<usize> {
+        let anon = match &self.data {
+            InputName::Anonymous(anon) => anon,
+            InputName::Given(_) => return None,
+        };
+        self.scope()
+            .anon_names_with_prefix(anon.name.as_str())
+            .iter()
+            .position(|other| other == self)
+    }
+}
+
+impl RootScope {
+    /// Stable sort of anonymous names by ordinal key, with unkeyed names first.
+    pub(crate) fn sort_by_ordinal_key(&self, names: &mut [Name]) {
+        let arena = self.root_data.names.borrow();
+        names.sort_by_key(|name| arena[name.index].ordinal_key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn keys_decide_ordinals() {
+        let root = RootScope::new_root();
+        let third = root.add_prefixed_keyed("tmp", 20);
+        let second = root.add_prefixed_keyed("tmp", 10);
+        let first = root.add_prefixed("tmp");
+        let given = root.add_named("tmp").unwrap();
+        assert_eq!(first.anonymous_ordinal(), Some(0));
+        assert_eq!(second.anonymous_ordinal(), Some(1));
+        assert_eq!(third.anonymous_ordinal(), Some(2));
+        assert_eq!(given.anonymous_ordinal(), None);
+        assert_eq!(third.to_string(), "tmp#2");
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn keyed_ordinals_from_threads() {
+        let root = RootScope::new_root();
+        let names = ::std::thread::scope(|threads| {
+            let handles: Vec<_> = (0..8)
+                .map(|key| {
+                    let root = root.clone();
+                    threads.spawn(move || root.add_prefixed_keyed("tmp", key))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        for (key, name) in names.iter().enumerate() {
+            assert_eq!(name.anonymous_ordinal(), Some(key));
+        }
+    }
+}
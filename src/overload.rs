@@ -0,0 +1,100 @@
+/// Overloaded names: the same given name registered several times in one scope, each
+/// with a distinguishing key, like function overloading in many languages.
+///
+/// A name is either overloaded or ordinary within a scope; registering the other kind
+/// with the same text fails.
+use ::ustr::Ustr;
+
+use crate::limit::RegisterError;
+use crate::name::{GivenName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+/// Distinguishes overloads of the same given name, e.g. a signature hash or the arity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OverloadKey(pub u64);
+
+impl Scope {
+    /// Register an overload of a given name in this scope. Fails if this key was already
+    /// registered for the name, or if the name was registered as an ordinary name.
+    pub fn add_overload(&self, name: &str, key: OverloadKey) -> Result<Name, RegisterError> {
+        self.add_given(GivenName {
+            overload: Some(key),
+            ..self.root.given_name(name)
+        })
+    }
+
+    /// The overloads of a name declared directly in this scope, in registration order.
+    /// Empty if the name is not overloaded here.
+    pub fn overloads(&self, name: &str) -> Vec<Name> {
+        let name = self.root.given_name(name).name;
+        let keys = self.root.scope_names_at(self.index, |data| {
+            data.overloads.get(&name).cloned().unwrap_or_default()
+        });
+        keys.into_iter()
+            .map(|key| {
+                self.find_given(&GivenName {
+                    overload: Some(key),
+                    ..GivenName::new(name.clone())
+                })
+                .expect("overload key without registered name")
+            })
+            .collect()
+    }
+
+    /// Find the nearest scope that declares the name, starting from this one and moving
+    /// outwards, and return the declarations there: either all overloads, or the single
+    /// ordinary name. Empty if no scope declares it.
+    pub fn resolve_overloads(&self, name: &str) -> Vec<Name> {
+        let ordinary_name = self.root.given_name(name);
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            let overloads = scope.overloads(name);
+            if !overloads.is_empty() {
+                return overloads;
+            }
+            if let Some(ordinary) = scope.find_given(&ordinary_name) {
+                return vec![ordinary];
+            }
+            current = scope.parent();
+        }
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    use super::*;
+
+    #[test]
+    fn add_overloads() {
+        let root = RootScope::new_root();
+        root.add_overload("f", OverloadKey(1)).unwrap();
+        root.add_overload("f", OverloadKey(2)).unwrap();
+        root.add_overload("f", OverloadKey(1)).unwrap_err();
+        root.add_named("f").unwrap_err();
+        root.add_named("g").unwrap();
+        root.add_overload("g", OverloadKey(1)).unwrap_err();
+        let keys: Vec<_> = root
+            .overloads("f")
+            .into_iter()
+            .map(|name| name.unwrap_given().overload_key())
+            .collect();
+        assert_eq!(keys, vec![Some(OverloadKey(1)), Some(OverloadKey(2))]);
+    }
+
+    #[test]
+    fn resolve_nearest() {
+        let root = RootScope::new_root();
+        root.add_overload("f", OverloadKey(1)).unwrap();
+        root.add_overload("f", OverloadKey(2)).unwrap();
+        let child = root.add_child();
+        let grandchild = child.add_child();
+        assert_eq!(grandchild.resolve_overloads("f").len(), 2);
+        let shadow = child.add_named("f").unwrap();
+        assert_eq!(grandchild.resolve_overloads("f"), vec![shadow]);
+        assert!(grandchild.resolve_overloads("g").is_empty());
+    }
+}
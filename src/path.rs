@@ -0,0 +1,213 @@
+/// Qualified paths of names, like `module::inner::x`, for diagnostics and debug output.
+///
+/// A path contains the labels of the scopes from the root down to the name's scope, except
+/// the root itself. Scopes without label are shown by their position among their siblings,
+/// like `{0}`. Anonymous names are shown as their prefix followed by their ordinal among
+/// anonymous names with the same prefix in that scope, like `tmp#1`.
+///
+/// Paths can be parsed again with `RootScope::resolve_str`.
+use ::std::borrow::Cow;
+use ::std::fmt;
+
+use ::ustr::Ustr;
+
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{RootScope, Scope};
+use crate::text::NameText;
+
+pub(crate) const SEPARATOR: &str = "::";
+
+impl Scope {
+    /// Set the name of this scope in paths, typically the module, type or function name.
+    pub fn set_label(&self, label: &str) {
+        self.root
+            .scope_data_at(self.index, |data| data.label = Some(Ustr::from(label)));
+    }
+
+    pub fn label(&self) -> Option<Ustr> {
+        self.root.read_scope_data_at(self.index, |data| data.label)
+    }
+
+    /// The path segments of this scope, starting below the root.
+    pub(crate) fn path_segments(&self) -> Vec<String> {
+        let mut segments = vec![];
+        let mut current = self.clone();
+        while let Some(parent) = current.parent() {
+            segments.push(match current.label() {
+                Some(label) => label.to_string(),
+                None => {
+                    let position = parent
+                        .children()
+                        .position(|child| child == current)
+                        .expect("scope is not a child of its parent");
+                    format!("{{{}}}", position)
+                }
+            });
+            current = parent;
+        }
+        segments.reverse();
+        segments
+    }
+
+    /// The path of this scope, or `<root>` for the root, for messages.
+    pub(crate) fn path_text(&self) -> String {
+        let segments = self.path_segments();
+        if segments.is_empty() {
+            "<root>".to_owned()
+        } else {
+            segments.join(SEPARATOR)
+        }
+    }
+}
+
+impl RootScope {
+    /// Find the name with the given path, in the format of `Display` for `Name`. The name
+    /// must be registered directly in the scope that the path leads to.
+    ///
+    /// Returns None if the path does not lead to a scope or name. If several siblings have
+    /// the same label, the first one is used. Given names that contain `#` or the separator
+    /// cannot be found this way.
+    pub fn resolve_str(&self, path: &str) -> Option<Name> {
+        let mut segments: Vec<&str> = path.split(SEPARATOR).collect();
+        let name = segments.pop()?;
+        let mut scope = self.root_scope();
+        for segment in segments {
+            scope = match segment
+                .strip_prefix('{')
+                .and_then(|rest| rest.strip_suffix('}'))
+            {
+                Some(position) => scope.children().nth(position.parse().ok()?)?,
+                None => scope
+                    .children()
+                    .find(|child| child.label().is_some_and(|label| label == segment))?,
+            };
+        }
+        match name.rfind('#') {
+            Some(hash) => {
+                let ordinal: usize = name[hash + 1..].parse().ok()?;
+                scope
+                    .anon_names_with_prefix(&name[..hash])
+                    .into_iter()
+                    .nth(ordinal)
+            }
+            None => scope.find_given(&scope.root.given_name(name)),
+        }
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.scope().path_segments() {
+            write!(f, "{}{}", segment, SEPARATOR)?;
+        }
+        f.write_str(&self.local_text())
+    }
+}
+
+impl Name {
+    /// The last segment of the path: the text of a given name, or the prefix and ordinal of
+    /// an anonymous one.
+    pub fn local_text(&self) -> Cow<'_, str> {
+        match &self.data {
+            InputName::Given(given) => Cow::Borrowed(given.as_str()),
+            InputName::Anonymous(anon) => {
+                let ordinal = self.anonymous_ordinal().unwrap_or(0);
+                Cow::Owned(format!("{}#{}", anon.name, ordinal))
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a Name> for Cow<'a, str> {
+    fn from(name: &'a Name) -> Self {
+        name.local_text()
+    }
+}
+
+impl From<&Name> for String {
+    fn from(name: &Name) -> Self {
+        name.local_text().into_owned()
+    }
+}
+
+impl From<Name> for String {
+    fn from(name: Name) -> Self {
+        String::from(&name)
+    }
+}
+
+impl<'a> From<&'a GivenName> for Cow<'a, str> {
+    fn from(name: &'a GivenName) -> Self {
+        Cow::Borrowed(name.as_str())
+    }
+}
+
+impl From<&GivenName> for String {
+    fn from(name: &GivenName) -> Self {
+        name.as_str().to_owned()
+    }
+}
+
+impl From<GivenName> for String {
+    fn from(name: GivenName) -> Self {
+        String::from(&name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn display_paths() {
+        let root = RootScope::new_root();
+        let module = root.add_child();
+        module.set_label("module");
+        let inner = module.add_child();
+        inner.set_label("inner");
+        assert_eq!(
+            inner.add_named("x").unwrap().to_string(),
+            "module::inner::x"
+        );
+        assert_eq!(root.add_named("top").unwrap().to_string(), "top");
+        let block = module.add_child();
+        block.add_prefixed("tmp");
+        assert_eq!(block.add_prefixed("tmp").to_string(), "module::{1}::tmp#1");
+        assert_eq!(block.add_anonymous().to_string(), "module::{1}::#0");
+        assert_eq!(module.label().unwrap().as_str(), "module");
+    }
+
+    #[test]
+    fn convert_to_strings() {
+        use ::std::borrow::Cow;
+
+        let root = RootScope::new_root();
+        let x = root.add_child().add_named("x").unwrap();
+        let tmp = root.add_prefixed("tmp");
+        assert!(matches!(Cow::from(&x), Cow::Borrowed("x")));
+        assert_eq!(String::from(&tmp), "tmp#0");
+        assert_eq!(String::from(x.clone().unwrap_given()), "x");
+        assert_eq!(String::from(x), "x");
+    }
+
+    #[test]
+    fn parse_paths() {
+        let root = RootScope::new_root();
+        let module = root.add_child();
+        module.set_label("module");
+        let x = module.add_child().add_named("x").unwrap();
+        let tmp = module.add_child().add_prefixed("tmp");
+        let top = root.add_named("top").unwrap();
+        for name in [&x, &tmp, &top] {
+            assert_eq!(
+                root.root().resolve_str(&name.to_string()).as_ref(),
+                Some(name)
+            );
+        }
+        assert_eq!(root.root().resolve_str("module::{0}::x"), Some(x));
+        assert_eq!(root.root().resolve_str("module::x"), None);
+        assert_eq!(root.root().resolve_str("other::{0}::x"), None);
+        assert_eq!(root.root().resolve_str("module::{1}::tmp#1"), None);
+        assert_eq!(root.root().resolve_str("module::{9}::x"), None);
+    }
+}
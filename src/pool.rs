@@ -0,0 +1,119 @@
+/// Reuse of root allocations between compilations on the same thread, for batch compilers
+/// that process many small units, to reduce allocator pressure.
+///
+/// A root can only be recycled once all its scopes and names have been dropped; its arenas
+/// are then cleared, keeping their capacity, and handed out again by the next `new_root`.
+use ::std::cell::RefCell;
+
+use crate::backend::Shared;
+use crate::canonical::Canonicalization;
+use crate::limit::Limits;
+use crate::scope::{RootId, RootScope, RootScopeData, Scope, ScopeData, ScopeNames};
+
+thread_local! {
+    static POOL: RefCell<Vec<RootScopeData>> = const { RefCell::new(vec![]) };
+}
+
+/// Handle to the pool of recycled roots of the current thread.
+#[derive(Debug, Clone, Copy)]
+pub struct RootPool {
+    // Not Send, since the pool belongs to the thread.
+    _thread: ::std::marker::PhantomData<*const ()>,
+}
+
+impl RootScope {
+    /// The pool of recycled roots for the current thread.
+    pub fn thread_local_pool() -> RootPool {
+        RootPool {
+            _thread: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl RootPool {
+    /// Like `RootScope::new_root`, but reusing a recycled root if there is one.
+    pub fn new_root(&self) -> Scope {
+        match POOL.with(|pool| pool.borrow_mut().pop()) {
+            Some(root_data) => RootScope {
+                root_data: Shared::new(root_data),
+            }
+            .root_scope(),
+            None => RootScope::new_root(),
+        }
+    }
+
+    /// Clear the root and keep its allocations for reuse. Returns false, and just drops
+    /// the handle, if there are other handles to the root (or any of its scopes or names).
+    pub fn recycle(&self, root: RootScope) -> bool {
+        let mut root_data = match Shared::try_unwrap(root.root_data) {
+            Ok(root_data) => root_data,
+            Err(_) => return false,
+        };
+        root_data.reset();
+        POOL.with(|pool| pool.borrow_mut().push(root_data));
+        true
+    }
+
+    /// The number of recycled roots waiting to be reused.
+    pub fn len(&self) -> usize {
+        POOL.with(|pool| pool.borrow().len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl RootScopeData {
+    /// Make this data equivalent to a newly created root, keeping allocated capacity.
+    fn reset(&mut self) {
+        self.id = RootId::random();
+        let scopes = self.scopes.get_mut();
+        scopes.clear();
+        crate::backend::push(scopes, ScopeData::new(None));
+        let scope_names = self.scope_names.get_mut();
+        scope_names.clear();
+        crate::backend::push(scope_names, ScopeNames::default());
+        self.names.get_mut().clear();
+        self.order.replace(None);
+        self.dirty.get_mut().clear();
+        self.references.get_mut().clear();
+        self.provenance.set(None);
+        self.diagnostics.get_mut().clear();
+        self.has_case_insensitive.set(false);
+        self.merge_duplicates.set(false);
+        self.canonicalization.set(Canonicalization::default());
+        self.limits.set(Limits::default());
+        self.report_unused.set(false);
+        *self.lazy_outputs.get_mut() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn reuse_root() {
+        let pool = RootScope::thread_local_pool();
+        let root = pool.new_root();
+        root.add_child().add_named("x").unwrap();
+        root.root().generate_lazily(OutputPolicy::default());
+        let old_id = root.root().root_data.id;
+        let kept = root.clone();
+        assert!(!pool.recycle(root.root().clone()));
+        drop(kept);
+        let handle = root.root().clone();
+        drop(root);
+        assert!(pool.recycle(handle));
+        assert_eq!(pool.len(), 1);
+        let reused = pool.new_root();
+        assert!(pool.is_empty());
+        assert_ne!(reused.root().root_data.id, old_id);
+        assert_eq!(reused.children().count(), 0);
+        assert!(reused.resolve("x").is_none());
+        let x = reused.add_named("x").unwrap();
+        assert_eq!(x.output(), None);
+    }
+}
@@ -0,0 +1,56 @@
+/// Default prefixes for anonymous names, so that e.g. all temporaries created while
+/// lowering a loop are called `loop_tmp`, without passing the prefix to every helper.
+///
+/// A default prefix applies to `Scope::add_anonymous` in the scope and its descendants,
+/// unless a descendant sets its own. `Scope::add_prefixed` always uses the given prefix.
+use ::ustr::Ustr;
+
+use crate::scope::Scope;
+
+impl Scope {
+    /// Use the prefix for anonymous names in this scope and its descendants that are
+    /// registered without one.
+    pub fn set_anonymous_prefix(&self, prefix: &str) {
+        self.root.scope_data_at(self.index, |data| {
+            data.anonymous_prefix = Some(Ustr::from(prefix))
+        });
+    }
+
+    /// The default prefix for anonymous names, set on this scope or inherited from the
+    /// nearest ancestor that has one, or None if neither has.
+    pub fn anonymous_prefix(&self) -> Option<Ustr> {
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            if let Some(prefix) = scope
+                .root
+                .read_scope_data_at(scope.index, |data| data.anonymous_prefix)
+            {
+                return Some(prefix);
+            }
+            current = scope.parent();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn inherit_anonymous_prefix() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        let plain = function.add_anonymous();
+        let lowered = function.add_child();
+        lowered.set_anonymous_prefix("loop_tmp");
+        let body = lowered.add_child();
+        let temporary = body.add_anonymous();
+        let explicit = body.add_prefixed("cond");
+        assert_eq!(plain.local_text(), "#0");
+        assert_eq!(temporary.local_text(), "loop_tmp#0");
+        assert_eq!(explicit.local_text(), "cond#0");
+        assert_eq!(body.anonymous_prefix().unwrap().as_str(), "loop_tmp");
+        assert_eq!(function.anonymous_prefix(), None);
+    }
+}
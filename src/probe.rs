@@ -0,0 +1,92 @@
+/// Checking what adding a name would do, without changing anything, e.g. so that an
+/// interactive tool can validate a name the user typed before committing to it, and
+/// suggest alternatives if it is taken.
+use ::std::iter::once;
+
+use crate::name::{GivenName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+/// What `Scope::add_named` would do with a name, as reported by `Scope::check_add`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddOutcome {
+    /// The name can be added, and does not hide any outer name.
+    Available,
+    /// The name can be added, but it would shadow this outer name.
+    Shadows(Name),
+    /// The name cannot be added, because of this name in the scope itself (for overloaded
+    /// names, the first overload). With merged duplicates, adding returns it instead.
+    Collides(Name),
+}
+
+impl Scope {
+    /// Check whether `add_named` would succeed for the name, and whether it would shadow a
+    /// name of an ancestor.
+    pub fn check_add(&self, name: &str) -> AddOutcome {
+        let given_name = self.root.given_name(name);
+        if let Some(existing) = self
+            .find_given(&given_name)
+            .or_else(|| self.overloads(name).into_iter().next())
+        {
+            return AddOutcome::Collides(existing);
+        }
+        match self
+            .parent()
+            .and_then(|parent| parent.resolve_silently(name))
+        {
+            Some(outer) => AddOutcome::Shadows(outer),
+            None => AddOutcome::Available,
+        }
+    }
+
+    /// Up to `count` variants of the name that could be added to this scope, for error
+    /// messages and interactive renaming. Variants `hello2` to `hello9` come first, then
+    /// `hello_new`, then higher numbers; variants that would shadow an outer name are skipped.
+    pub fn suggest_alternatives(&self, name: &str, count: usize) -> Vec<String> {
+        let numbered = |number: usize| format!("{}{}", name, number);
+        (2..10)
+            .map(numbered)
+            .chain(once(format!("{}_new", name)))
+            .chain((10..).map(numbered))
+            .filter(|candidate| self.check_add(candidate) == AddOutcome::Available)
+            .take(count)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::overload::OverloadKey;
+    use crate::scope::RootScope;
+
+    use super::AddOutcome;
+
+    #[test]
+    fn dry_run() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let f = root.add_overload("f", OverloadKey(1)).unwrap();
+        let child = root.add_child();
+        let local = child.add_named("y").unwrap();
+        assert_eq!(child.check_add("z"), AddOutcome::Available);
+        assert_eq!(child.check_add("x"), AddOutcome::Shadows(outer));
+        assert_eq!(child.check_add("y"), AddOutcome::Collides(local));
+        assert_eq!(root.check_add("f"), AddOutcome::Collides(f));
+        assert_eq!(child.names().len(), 1);
+    }
+
+    #[test]
+    fn suggest_free_names() {
+        let root = RootScope::new_root();
+        root.add_named("hello3").unwrap();
+        let child = root.add_child();
+        child.add_named("hello").unwrap();
+        child.add_named("hello2").unwrap();
+        assert_eq!(
+            child.suggest_alternatives("hello", 2),
+            vec!["hello4".to_owned(), "hello5".to_owned()]
+        );
+        assert_eq!(child.suggest_alternatives("hello", 9)[6], "hello_new");
+        assert!(child.suggest_alternatives("hello", 0).is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+/// Output policies for common targets, as starting points that can be adjusted further,
+/// e.g. with `OutputPolicy::with_keywords`.
+use crate::generate::{OutputComparison, OutputPolicy, LETTERS};
+
+/// The SQL dialects that `OutputPolicy::sql` knows the rules of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// The SQL standard, for databases that are not listed.
+    Standard,
+    PostgreSql,
+    MySql,
+    Sqlite,
+}
+
+impl SqlDialect {
+    /// The longest identifier, in characters, if there is a limit.
+    pub fn max_length(self) -> Option<usize> {
+        match self {
+            SqlDialect::Standard => Some(128),
+            SqlDialect::PostgreSql => Some(63),
+            SqlDialect::MySql => Some(64),
+            SqlDialect::Sqlite => None,
+        }
+    }
+
+    /// The characters that quoted identifiers are put between.
+    pub fn quotes(self) -> (char, char) {
+        match self {
+            SqlDialect::MySql => ('`', '`'),
+            _ => ('"', '"'),
+        }
+    }
+
+    /// The reserved words, in lowercase.
+    pub fn keywords(self) -> Vec<&'static str> {
+        let extra: &[&str] = match self {
+            SqlDialect::Standard => &[],
+            SqlDialect::PostgreSql => &["analyze", "limit", "offset", "returning", "verbose"],
+            SqlDialect::MySql => &["interval", "keys", "limit", "rlike", "regexp", "show"],
+            SqlDialect::Sqlite => &["autoincrement", "glob", "limit", "offset", "pragma"],
+        };
+        SQL_KEYWORDS.iter().chain(extra).cloned().collect()
+    }
+}
+
+// Reserved words that all dialects have in common.
+const SQL_KEYWORDS: &[&str] = &[
+    "all",
+    "alter",
+    "and",
+    "as",
+    "between",
+    "by",
+    "case",
+    "check",
+    "column",
+    "constraint",
+    "create",
+    "default",
+    "delete",
+    "distinct",
+    "drop",
+    "else",
+    "end",
+    "exists",
+    "foreign",
+    "from",
+    "group",
+    "having",
+    "in",
+    "index",
+    "inner",
+    "insert",
+    "into",
+    "is",
+    "join",
+    "key",
+    "left",
+    "like",
+    "not",
+    "null",
+    "on",
+    "or",
+    "order",
+    "outer",
+    "primary",
+    "references",
+    "right",
+    "select",
+    "set",
+    "table",
+    "then",
+    "to",
+    "union",
+    "unique",
+    "update",
+    "values",
+    "when",
+    "where",
+    "with",
+];
+
+// Device names that Windows does not allow as file names, with any extension.
+const WINDOWS_DEVICES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+// Reserved words of JavaScript, including those of strict mode.
+const JAVASCRIPT_KEYWORDS: &[&str] = &[
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+// Well-known globals of browsers and Node.js, which generated names should not shadow.
+const JAVASCRIPT_GLOBALS: &[&str] = &[
+    "Array",
+    "Boolean",
+    "Date",
+    "Error",
+    "Infinity",
+    "JSON",
+    "Map",
+    "Math",
+    "NaN",
+    "Number",
+    "Object",
+    "Promise",
+    "Proxy",
+    "Reflect",
+    "RegExp",
+    "Set",
+    "String",
+    "Symbol",
+    "WeakMap",
+    "WeakSet",
+    "arguments",
+    "clearInterval",
+    "clearTimeout",
+    "console",
+    "decodeURI",
+    "decodeURIComponent",
+    "document",
+    "encodeURI",
+    "encodeURIComponent",
+    "eval",
+    "exports",
+    "fetch",
+    "global",
+    "globalThis",
+    "history",
+    "isFinite",
+    "isNaN",
+    "localStorage",
+    "location",
+    "module",
+    "navigator",
+    "parseFloat",
+    "parseInt",
+    "process",
+    "require",
+    "self",
+    "setInterval",
+    "setTimeout",
+    "undefined",
+    "window",
+];
+
+impl OutputPolicy {
+    /// File and module names that are valid on Linux, macOS and Windows: ascii letters,
+    /// digits, underscores and dashes, not starting with a dash, avoiding device names like
+    /// `con` and `nul`, and unique when ignoring case, for file systems that do.
+    pub fn filesystem() -> Self {
+        OutputPolicy {
+            allowed: |c| c.is_ascii_alphanumeric() || c == '_' || c == '-',
+            allowed_start: |c| c.is_ascii_alphanumeric() || c == '_',
+            max_length: Some(255),
+            comparison: OutputComparison::IgnoreCase,
+            ..OutputPolicy::default()
+        }
+        .with_keywords(WINDOWS_DEVICES)
+    }
+
+    /// Unquoted SQL identifiers for the dialect: ascii letters, digits and underscores,
+    /// not starting with a digit, within the dialect's length limit, avoiding its reserved
+    /// words, and unique when ignoring case. To quote reserved words instead of avoiding
+    /// them, set `OutputPolicy::quote` to the dialect's `quotes`.
+    pub fn sql(dialect: SqlDialect) -> Self {
+        OutputPolicy {
+            max_length: dialect.max_length(),
+            comparison: OutputComparison::IgnoreCase,
+            ..OutputPolicy::default()
+        }
+        .with_keywords(&dialect.keywords())
+    }
+
+    /// JavaScript identifiers: ascii letters, digits, `_` and `$`, not starting with a digit,
+    /// avoiding reserved words and the well-known globals of browsers and Node.js, like
+    /// `window`, `document` and `require`, so that outputs never shadow them. Minified
+    /// outputs use `$` and `_` too.
+    pub fn javascript() -> Self {
+        OutputPolicy {
+            allowed: |c| c.is_ascii_alphanumeric() || c == '_' || c == '$',
+            allowed_start: |c| c.is_ascii_alphabetic() || c == '_' || c == '$',
+            alphabet: format!("{}0123456789_$", LETTERS),
+            start_alphabet: format!("{}_$", LETTERS),
+            ..OutputPolicy::default()
+        }
+        .with_keywords(JAVASCRIPT_KEYWORDS)
+        .with_keywords(JAVASCRIPT_GLOBALS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::profile::SqlDialect;
+    use crate::scope::RootScope;
+    use crate::strategy::Strategy;
+
+    #[test]
+    fn filesystem_names() {
+        let root = RootScope::new_root();
+        let device = root.add_named("CON").unwrap();
+        let path = root.add_named("a/b:c").unwrap();
+        let report = root.add_named("report").unwrap();
+        let other_report = root.add_named("Report").unwrap();
+        let dashed = root.add_named("-v").unwrap();
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::filesystem())
+            .unwrap();
+        assert_eq!(map.get(&device).unwrap().as_str(), "CON2");
+        assert_eq!(map.get(&path).unwrap().as_str(), "a_b_c");
+        assert_eq!(map.get(&report).unwrap().as_str(), "report");
+        assert_eq!(map.get(&other_report).unwrap().as_str(), "Report2");
+        assert_eq!(map.get(&dashed).unwrap().as_str(), "_-v");
+    }
+
+    #[test]
+    fn sql_identifiers() {
+        let root = RootScope::new_root();
+        let order = root.add_named("Order").unwrap();
+        let limit = root.add_named("limit").unwrap();
+        let long = root.add_named(&"x".repeat(70)).unwrap();
+        let postgres = OutputPolicy::sql(SqlDialect::PostgreSql);
+        let map = root.root().generate_names(&postgres).unwrap();
+        assert_eq!(map.get(&order).unwrap().as_str(), "Order2");
+        assert_eq!(map.get(&limit).unwrap().as_str(), "limit2");
+        assert_eq!(map.get(&long).unwrap().as_str().len(), 63);
+        let quoted = OutputPolicy {
+            quote: Some(SqlDialect::MySql.quotes()),
+            ..OutputPolicy::sql(SqlDialect::MySql)
+        };
+        let map = root.root().generate_names(&quoted).unwrap();
+        assert_eq!(map.get(&order).unwrap().as_str(), "`Order`");
+        assert_eq!(map.get(&limit).unwrap().as_str(), "`limit`");
+        let standard = root
+            .root()
+            .generate_names(&OutputPolicy::sql(SqlDialect::Standard))
+            .unwrap();
+        assert_eq!(standard.get(&limit).unwrap().as_str(), "limit");
+    }
+
+    #[test]
+    fn javascript_globals() {
+        let root = RootScope::new_root();
+        let window = root.add_named("window").unwrap();
+        let dollar = root.add_named("$el").unwrap();
+        let function = root.add_child();
+        let temporaries: Vec<_> = (0..200).map(|_| function.add_prefixed("t")).collect();
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::javascript())
+            .unwrap();
+        assert_eq!(map.get(&window).unwrap().as_str(), "window2");
+        assert_eq!(map.get(&dollar).unwrap().as_str(), "$el");
+        let policy = OutputPolicy {
+            strategy: Strategy::Minify,
+            ..OutputPolicy::javascript()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        for temporary in &temporaries {
+            let output = map.get(temporary).unwrap().as_str();
+            assert!(!["do", "if", "in", "NaN", "Map", "Set"].contains(&output));
+        }
+        assert_eq!(map.get(&temporaries[0]).unwrap().as_str(), "c");
+    }
+}
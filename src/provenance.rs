@@ -0,0 +1,64 @@
+/// Provenance labels, recording which compiler pass or macro created a name, so that
+/// unexpected names (e.g. temporaries) can be traced back to their origin.
+///
+/// The label is set on the root, and recorded on every name registered while it is set.
+use ::ustr::Ustr;
+
+use crate::name::Name;
+use crate::scope::RootScope;
+
+impl RootScope {
+    /// Set the label that is recorded on names registered from now on, or None to stop
+    /// recording. Typically set at the start of each pass.
+    pub fn set_provenance(&self, label: Option<&str>) {
+        self.root_data.provenance.set(label.map(Ustr::from));
+    }
+
+    /// The label currently recorded on new names, if any.
+    pub fn provenance(&self) -> Option<Ustr> {
+        self.root_data.provenance.get()
+    }
+}
+
+impl Name {
+    /// The label that was set when this name was registered, if any.
+    pub fn provenance(&self) -> Option<Ustr> {
+        self.root.root_data.names.borrow()[self.index].provenance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn record_provenance() {
+        let root = RootScope::new_root();
+        let plain = root.add_named("plain").unwrap();
+        root.root().set_provenance(Some("inliner"));
+        let given = root.add_named("inlined").unwrap();
+        let temporary = root.add_prefixed("tmp");
+        root.root().set_provenance(None);
+        let after = root.add_anonymous();
+        assert_eq!(plain.provenance(), None);
+        assert_eq!(given.provenance().unwrap().as_str(), "inliner");
+        assert_eq!(temporary.provenance().unwrap().as_str(), "inliner");
+        assert_eq!(after.provenance(), None);
+        assert_eq!(
+            root.resolve("inlined")
+                .unwrap()
+                .provenance()
+                .unwrap()
+                .as_str(),
+            "inliner"
+        );
+    }
+
+    #[test]
+    fn provenance_in_debug() {
+        let root = RootScope::new_root();
+        root.root().set_provenance(Some("desugar"));
+        let name = root.add_named("x").unwrap();
+        assert!(format!("{:?}", name).contains("desugar"));
+    }
+}
@@ -0,0 +1,156 @@
+/// Python bindings, enabled with the `pyo3` feature.
+///
+/// The classes wrap the Rust types directly. Scopes are not thread-safe, so the
+/// classes are marked unsendable, and Python will refuse to use them from another
+/// thread than the one that created them.
+///
+/// Outputs are generated with the default policy by `Scope.generate_names`, for the whole
+/// tree of the scope; it raises `GenerateError` if that fails.
+use ::pyo3::create_exception;
+use ::pyo3::exceptions::PyException;
+use ::pyo3::prelude::*;
+
+use crate::generate::{NameMap, OutputPolicy};
+use crate::name::{InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+create_exception!(scoped_name, AlreadyExists, PyException);
+create_exception!(scoped_name, GenerateError, PyException);
+
+/// Entry point for creating new scope trees.
+#[pyclass(name = "RootScope", unsendable)]
+pub struct PyRootScope {}
+
+#[pymethods]
+impl PyRootScope {
+    /// Return the scope of a newly created root.
+    #[staticmethod]
+    fn new_root() -> PyScope {
+        PyScope {
+            inner: RootScope::new_root(),
+        }
+    }
+}
+
+#[pyclass(name = "Scope", unsendable)]
+#[derive(Clone)]
+pub struct PyScope {
+    inner: Scope,
+}
+
+#[pymethods]
+impl PyScope {
+    fn children(&self) -> Vec<PyScope> {
+        self.inner
+            .children()
+            .map(|inner| PyScope { inner })
+            .collect()
+    }
+
+    fn add_child(&self) -> PyScope {
+        PyScope {
+            inner: self.inner.add_child(),
+        }
+    }
+
+    /// Raises `AlreadyExists` if the name is already registered in this scope.
+    fn add_named(&self, name: &str) -> PyResult<PyName> {
+        match self.inner.add_named(name) {
+            Ok(inner) => Ok(PyName { inner }),
+            Err(_) => Err(AlreadyExists::new_err(name.to_owned())),
+        }
+    }
+
+    fn add_prefixed(&self, prefix: &str) -> PyName {
+        PyName {
+            inner: self.inner.add_prefixed(prefix),
+        }
+    }
+
+    fn add_anonymous(&self) -> PyName {
+        PyName {
+            inner: self.inner.add_anonymous(),
+        }
+    }
+
+    /// The nearest declaration of the name, from this scope outwards, or None.
+    fn resolve(&self, name: &str) -> Option<PyName> {
+        self.inner.resolve(name).map(|inner| PyName { inner })
+    }
+
+    /// Raises `GenerateError` if no valid outputs could be generated.
+    fn generate_names(&self) -> PyResult<PyNameMap> {
+        match self.inner.root().generate_names(&OutputPolicy::default()) {
+            Ok(inner) => Ok(PyNameMap { inner }),
+            Err(error) => Err(GenerateError::new_err(format!("{:?}", error))),
+        }
+    }
+
+    fn __eq__(&self, other: &PyScope) -> bool {
+        self.inner == other.inner
+    }
+}
+
+#[pyclass(name = "Name", unsendable)]
+#[derive(Clone)]
+pub struct PyName {
+    inner: Name,
+}
+
+#[pymethods]
+impl PyName {
+    /// The given name, or the prefix for anonymous names.
+    #[getter]
+    fn text(&self) -> String {
+        match &self.inner.data {
+            InputName::Given(given) => given.name.as_str().to_owned(),
+            InputName::Anonymous(anon) => anon.name.as_str().to_owned(),
+        }
+    }
+
+    #[getter]
+    fn is_anonymous(&self) -> bool {
+        matches!(self.inner.data, InputName::Anonymous(_))
+    }
+
+    #[getter]
+    fn scope(&self) -> PyScope {
+        PyScope {
+            inner: self.inner.scope(),
+        }
+    }
+
+    fn __eq__(&self, other: &PyName) -> bool {
+        self.inner == other.inner
+    }
+}
+
+#[pyclass(name = "NameMap", unsendable)]
+pub struct PyNameMap {
+    inner: NameMap,
+}
+
+#[pymethods]
+impl PyNameMap {
+    /// The output of the name, or None if it has none in this map.
+    fn get(&self, name: &PyName) -> Option<String> {
+        self.inner
+            .get(&name.inner)
+            .map(|output| output.as_str().to_owned())
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+#[pymodule]
+fn scoped_name(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyRootScope>()?;
+    module.add_class::<PyScope>()?;
+    module.add_class::<PyName>()?;
+    module.add_class::<PyNameMap>()?;
+    module.add("AlreadyExists", module.py().get_type::<AlreadyExists>())?;
+    module.add("GenerateError", module.py().get_type::<GenerateError>())?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+/// Queries over the whole tree, for tooling that needs to find scopes or names matching some
+/// condition without writing its own traversal.
+///
+/// The `_pruned` variants take a callback that decides whether to enter a scope; if it returns
+/// false, that scope and its whole subtree are skipped.
+use crate::name::Name;
+use crate::scope::{RootScope, Scope};
+
+impl RootScope {
+    /// All scopes for which the predicate is true, parents before children.
+    pub fn find_scopes(&self, predicate: impl FnMut(&Scope) -> bool) -> Vec<Scope> {
+        self.find_scopes_pruned(|_| true, predicate)
+    }
+
+    /// All names for which the predicate is true, by scope (parents before children) and
+    /// then in registration order.
+    pub fn find_names(&self, predicate: impl FnMut(&Name) -> bool) -> Vec<Name> {
+        self.find_names_pruned(|_| true, predicate)
+    }
+
+    /// Like `find_scopes`, but only entering scopes for which `enter` is true.
+    pub fn find_scopes_pruned(
+        &self,
+        mut enter: impl FnMut(&Scope) -> bool,
+        mut predicate: impl FnMut(&Scope) -> bool,
+    ) -> Vec<Scope> {
+        let mut found = vec![];
+        self.visit(&mut enter, |scope| {
+            if predicate(scope) {
+                found.push(scope.clone());
+            }
+        });
+        found
+    }
+
+    /// Like `find_names`, but only entering scopes for which `enter` is true.
+    pub fn find_names_pruned(
+        &self,
+        mut enter: impl FnMut(&Scope) -> bool,
+        mut predicate: impl FnMut(&Name) -> bool,
+    ) -> Vec<Name> {
+        let mut found = vec![];
+        self.visit(&mut enter, |scope| {
+            found.extend(scope.names().into_iter().filter(|name| predicate(name)));
+        });
+        found
+    }
+
+    fn visit(&self, enter: &mut impl FnMut(&Scope) -> bool, mut action: impl FnMut(&Scope)) {
+        let mut pending = vec![self.root_scope()];
+        while let Some(scope) = pending.pop() {
+            if !enter(&scope) {
+                continue;
+            }
+            action(&scope);
+            let mut children: Vec<Scope> = scope.children().collect();
+            children.reverse();
+            pending.extend(children);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kind::ScopeKind;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn find_matching() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        function.set_kind(ScopeKind::Function);
+        let x = function.add_named("x").unwrap();
+        let block = function.add_child();
+        let y = block.add_named("y").unwrap();
+        root.add_prefixed("tmp");
+        let functions = root
+            .root()
+            .find_scopes(|scope| scope.kind() == ScopeKind::Function);
+        assert_eq!(functions, vec![function.clone()]);
+        let in_function = root
+            .root()
+            .find_names(|name| name.scope().is_in_subtree_of(&function));
+        assert_eq!(in_function, vec![x, y.clone()]);
+        let pruned = root
+            .root()
+            .find_names_pruned(|scope| scope.kind() != ScopeKind::Function, |_| true);
+        assert_eq!(pruned.len(), 1);
+        assert!(!pruned.contains(&y));
+    }
+}
@@ -0,0 +1,177 @@
+/// Uses of names that may not have been declared yet, for single-pass front-ends that
+/// encounter forward references.
+///
+/// References are recorded on the root, and bound by `RootScope::finalize`, at which
+/// point the tree is expected to contain all declarations.
+use ::std::collections::{HashMap, HashSet};
+
+use ::ustr::Ustr;
+
+use crate::backend;
+use crate::name::Name;
+use crate::scope::{RootScope, Scope};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Reference {
+    // Arena index of the scope in which the name was used.
+    scope: usize,
+    name: Ustr,
+    // Arena index of the declared name, once bound.
+    binding: Option<usize>,
+}
+
+/// A recorded use of a name, which can be inspected after `RootScope::finalize`.
+#[derive(Debug, Clone)]
+pub struct Unresolved {
+    root: RootScope,
+    // Index in the root's list of references.
+    nr: usize,
+}
+
+impl Reference {
+    /// Arena index of the declaration, once bound.
+    pub(crate) fn binding(&self) -> Option<usize> {
+        self.binding
+    }
+}
+
+impl Scope {
+    /// Record a use of a given name in this scope, to be bound when the tree is finalized.
+    pub fn reference(&self, name: &str) -> Unresolved {
+        let mut references = self.root.root_data.references.borrow_mut();
+        backend::push(
+            &mut references,
+            Reference {
+                scope: self.index,
+                name: Ustr::from(name),
+                binding: None,
+            },
+        );
+        Unresolved {
+            root: self.root.clone(),
+            nr: references.len() - 1,
+        }
+    }
+}
+
+impl RootScope {
+    /// Arena indices of the scopes where references were recorded.
+    pub(crate) fn referencing_scopes(&self) -> HashSet<usize> {
+        self.root_data
+            .references
+            .borrow()
+            .iter()
+            .map(|reference| reference.scope)
+            .collect()
+    }
+
+    /// The scope and, if bound, the declaration of every recorded reference, as arena indices.
+    pub(crate) fn reference_sites(&self) -> Vec<(usize, Option<usize>)> {
+        self.root_data
+            .references
+            .borrow()
+            .iter()
+            .map(|reference| (reference.scope, reference.binding))
+            .collect()
+    }
+
+    /// Point the bound references of replaced names at their replacements, by arena index,
+    /// and spell them with the text of the replacement.
+    pub(crate) fn rebind_references(&self, replacements: &HashMap<usize, (usize, Ustr)>) {
+        let count = self.root_data.references.borrow().len();
+        for nr in 0..count {
+            let binding = self.root_data.references.borrow()[nr].binding;
+            if let Some(&(replacement, text)) = binding.and_then(|index| replacements.get(&index)) {
+                let reference = &mut self.root_data.references.borrow_mut()[nr];
+                reference.binding = Some(replacement);
+                reference.name = text;
+            }
+        }
+    }
+
+    /// Bind all recorded references that are not bound yet to their declarations, as seen
+    /// from the scope where they were used. Returns the references for which no declaration
+    /// exists; those stay unbound, and can be bound by finalizing again later.
+    pub fn finalize(&self) -> Result<(), Vec<Unresolved>> {
+        let mut undefined = vec![];
+        let count = self.root_data.references.borrow().len();
+        for nr in 0..count {
+            let (scope_index, name, binding) = {
+                let reference = &self.root_data.references.borrow()[nr];
+                (reference.scope, reference.name, reference.binding)
+            };
+            if binding.is_some() {
+                continue;
+            }
+            match self.scope_at_index(scope_index).resolve(name.as_str()) {
+                Some(declaration) => {
+                    self.root_data.references.borrow_mut()[nr].binding = Some(declaration.index)
+                }
+                None => undefined.push(Unresolved {
+                    root: self.clone(),
+                    nr,
+                }),
+            }
+        }
+        if undefined.is_empty() {
+            Ok(())
+        } else {
+            Err(undefined)
+        }
+    }
+}
+
+impl Unresolved {
+    /// The text of the name that was used.
+    pub fn text(&self) -> Ustr {
+        self.root.root_data.references.borrow()[self.nr].name
+    }
+
+    /// The scope in which the name was used.
+    pub fn scope(&self) -> Scope {
+        let scope_index = self.root.root_data.references.borrow()[self.nr].scope;
+        self.root.scope_at_index(scope_index)
+    }
+
+    /// The declaration this use refers to, or None if it has not been bound (yet).
+    pub fn binding(&self) -> Option<Name> {
+        let binding = self.root.root_data.references.borrow()[self.nr].binding;
+        binding.map(|index| self.root.name_at(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn forward_reference() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let use_f = child.reference("f");
+        let use_x = child.reference("x");
+        assert_eq!(use_f.binding(), None);
+        let outer_x = root.add_named("x").unwrap();
+        // Declared later in the inner scope, which should win over the outer one.
+        let inner_x = child.add_named("x").unwrap();
+        let f = root.add_named("f").unwrap();
+        root.root().finalize().unwrap();
+        assert_eq!(use_f.binding(), Some(f));
+        assert_eq!(use_x.binding(), Some(inner_x));
+        assert_ne!(use_x.binding(), Some(outer_x));
+    }
+
+    #[test]
+    fn undefined_reference() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        child.reference("missing");
+        let undefined = root.root().finalize().unwrap_err();
+        assert_eq!(undefined.len(), 1);
+        assert_eq!(undefined[0].text().as_str(), "missing");
+        assert_eq!(undefined[0].scope(), child);
+        let late = root.add_named("missing").unwrap();
+        root.root().finalize().unwrap();
+        assert_eq!(undefined[0].binding(), Some(late));
+    }
+}
@@ -0,0 +1,395 @@
+/// Moving names and subtrees within a tree, e.g. for hoisting and sinking optimizations.
+///
+/// Moved names keep their identity, so existing handles, provenance and bound references
+/// refer to the name in its new scope.
+use crate::generate::candidates;
+use crate::limit::{LimitExceeded, RegisterError};
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{AlreadyExists, Scope, ScopeId};
+use crate::text::NameText;
+
+#[derive(Debug)]
+pub struct CreatesCycle();
+
+/// Why two sibling scopes could not be merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeError {
+    /// One of the scopes, or their parent, is sealed.
+    Sealed(ScopeId),
+    /// The merged scope would have too many names.
+    LimitExceeded(LimitExceeded),
+}
+
+impl From<LimitExceeded> for MergeError {
+    fn from(exceeded: LimitExceeded) -> Self {
+        MergeError::LimitExceeded(exceeded)
+    }
+}
+
+impl Name {
+    /// Move this name to another scope of the same tree. Fails, without changing anything,
+    /// if the target scope already has a conflicting name, or cannot have more names.
+    ///
+    /// Panics if the target is in another tree, if the name was removed, or if the source or
+    /// target scope is sealed.
+    pub fn move_to(&self, target: &Scope) -> Result<(), RegisterError> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        assert!(
+            self.root == target.root,
+            "cannot move a name to another tree"
+        );
+        assert!(!self.is_removed(), "cannot move a removed name");
+        let source = self.scope();
+        if source == *target {
+            return Ok(());
+        }
+        self.root.assert_unsealed(source.index);
+        self.root.assert_unsealed(target.index);
+        // Check that the target has no conflicting name.
+        if let InputName::Given(given) = &self.data {
+            if target.blocks_arrival(given) {
+                return Err(RegisterError::AlreadyExists);
+            }
+        }
+        target.check_name_limits()?;
+        // During this method, the state is not consistent.
+        // Step 1: remove the name from the source scope.
+        let was_undefined = source.unregister(self);
+        // Step 2: record the new scope in the arena.
+        self.root.root_data.names.borrow_mut()[self.index].scope = target.index;
+        // Step 3: register the name in the target scope.
+        target.root.mark_dirty(target.index);
+        let index = self.index;
+        target
+            .root
+            .scope_names_at(target.index, |data| match &self.data {
+                InputName::Given(given) => {
+                    data.given_names.insert(given.clone(), index);
+                    if let Some(key) = given.overload {
+                        data.overloads
+                            .entry(given.name.clone())
+                            .or_insert_with(Vec::new)
+                            .push(key);
+                    }
+                    if was_undefined {
+                        data.undefined.insert(given.clone());
+                    }
+                }
+                InputName::Anonymous(_) => {
+                    // Keep anonymous names in registration order.
+                    let position = data.anon_names.partition_point(|&other| other < index);
+                    data.anon_names.insert(position, index);
+                }
+            });
+        Ok(())
+    }
+}
+
+impl Scope {
+    /// Whether a given name cannot be moved here: it conflicts with a name declared here,
+    /// or it would hide a name imported here, which changes what existing uses refer to.
+    /// Both ignore case if this scope does.
+    pub(crate) fn blocks_arrival(&self, given: &GivenName) -> bool {
+        self.has_conflicting(given) || self.find_imported(given).is_some()
+    }
+
+    /// Register a given name of any kind, including overloads. The caller should have
+    /// checked that it does not conflict.
+    pub(crate) fn register_any(&self, given: GivenName) -> Name {
+        let key = given.overload;
+        let text = given.name.clone();
+        let name = self.register_given(given);
+        if let Some(key) = key {
+            self.root.scope_names_at(self.index, |data| {
+                data.overloads.entry(text).or_default().push(key)
+            });
+        }
+        name
+    }
+
+    /// Merge a sibling scope into this one, e.g. when merging blocks: its names and children
+    /// move here, and it is left empty and detached. Names keep their identity, except given
+    /// names that conflict with a name here. Those are replaced by a new name, spelled with
+    /// the lowest free number appended (like generated outputs), which takes over everything
+    /// about the original like in `RootScope::rename_all`, and the original is removed.
+    ///
+    /// Returns the replaced names, as pairs of the removed original and its replacement.
+    /// Fails, without changing anything, if either scope or their parent is sealed, or if
+    /// this scope cannot hold the names of both.
+    ///
+    /// Panics if the other scope is not a sibling of this one.
+    pub fn merge_sibling(&self, other: &Scope) -> Result<Vec<(Name, Name)>, MergeError> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        let parent = self.parent();
+        assert!(
+            other != self && parent.is_some() && other.parent() == parent,
+            "can only merge sibling scopes"
+        );
+        let parent = parent.unwrap();
+        for scope in &[self, other, &parent] {
+            if scope.is_sealed() {
+                return Err(MergeError::Sealed(scope.id()));
+            }
+        }
+        if let Some(max_names) = self.root.limits().max_names_per_scope {
+            if self.names().len() + other.names().len() > max_names {
+                return Err(LimitExceeded::NamesPerScope(max_names).into());
+            }
+        }
+        let mut renamed = vec![];
+        for name in other.names() {
+            if name.move_to(self).is_ok() {
+                continue;
+            }
+            let given = match &name.data {
+                InputName::Given(given) => given.clone(),
+                InputName::Anonymous(_) => unreachable!("anonymous names cannot conflict"),
+            };
+            let replacement_given = candidates(given.as_str())
+                .map(|text| GivenName {
+                    name: NameText::from(text.as_str()),
+                    ..given.clone()
+                })
+                .find(|candidate| !self.blocks_arrival(candidate))
+                .unwrap();
+            let replacement = self.register_any(replacement_given);
+            other.remove_name(&name);
+            renamed.push((name, replacement));
+        }
+        self.root.transfer_names(&renamed);
+        for child in other.children().collect::<Vec<_>>() {
+            child
+                .reparent(self)
+                .expect("a sibling cannot be a descendant");
+        }
+        self.root.unlink_child(parent.index, other.index);
+        self.root
+            .scope_data_at(other.index, |data| data.parent = None);
+        self.root.shape_changed();
+        self.root.mark_dirty(parent.index);
+        Ok(renamed)
+    }
+
+    /// Detach this scope from its parent, and attach it as the last child of another scope
+    /// in the same tree. Fails, without changing anything, if the new parent is this scope
+    /// or one of its descendants.
+    ///
+    /// Returns the given names in the moved subtree that shadow an outer name that they did
+    /// not shadow before the move, as pairs of the inner and the outer name.
+    ///
+    /// Panics if the new parent is in another tree.
+    pub fn reparent(&self, new_parent: &Scope) -> Result<Vec<(Name, Name)>, CreatesCycle> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        assert!(
+            self.root == new_parent.root,
+            "cannot move a scope to another tree"
+        );
+        let mut ancestor = Some(new_parent.clone());
+        while let Some(scope) = ancestor {
+            if scope == *self {
+                return Err(CreatesCycle());
+            }
+            ancestor = scope.parent();
+        }
+        let old_parent = self.parent();
+        if let Some(old_parent) = &old_parent {
+            self.root.assert_unsealed(old_parent.index);
+        }
+        self.root.assert_unsealed(new_parent.index);
+        // During this method, the state is not consistent.
+        // Step 1: detach from the old parent.
+        if let Some(old_parent) = &old_parent {
+            self.root.unlink_child(old_parent.index, self.index);
+        }
+        // Step 2: attach to the new parent.
+        self.root
+            .scope_data_at(self.index, |data| data.parent = Some(new_parent.index));
+        self.root.link_child(new_parent.index, self.index, None);
+        self.root.shape_changed();
+        if let Some(old_parent) = &old_parent {
+            self.root.mark_dirty(old_parent.index);
+        }
+        self.root.mark_dirty(new_parent.index);
+        self.root.mark_dirty(self.index);
+        // Step 3: find the newly shadowed names.
+        let mut conflicts = vec![];
+        let mut pending = vec![self.clone()];
+        while let Some(scope) = pending.pop() {
+            for inner in scope.names() {
+                if let InputName::Given(given) = &inner.data {
+                    let text = given.as_str();
+                    let outer = new_parent.resolve_silently(text);
+                    let old_outer = old_parent
+                        .as_ref()
+                        .and_then(|parent| parent.resolve_silently(text));
+                    if let Some(outer) = outer {
+                        if Some(&outer) != old_outer.as_ref() {
+                            conflicts.push((inner.clone(), outer));
+                        }
+                    }
+                }
+            }
+            let mut children: Vec<Scope> = scope.children().collect();
+            children.reverse();
+            pending.extend(children);
+        }
+        Ok(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::ImportConflict;
+    use crate::overload::OverloadKey;
+    use crate::relocate::MergeError;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn hoist_name() {
+        let root = RootScope::new_root();
+        root.root().set_provenance(Some("parser"));
+        let child = root.add_child();
+        let x = child.add_named("x").unwrap();
+        let use_x = child.add_child().reference("x");
+        root.root().finalize().unwrap();
+        x.move_to(&root).unwrap();
+        assert_eq!(x.scope(), root);
+        assert_eq!(root.resolve("x"), Some(x.clone()));
+        assert_eq!(use_x.binding(), Some(x.clone()));
+        assert_eq!(x.provenance().unwrap().as_str(), "parser");
+        assert!(child.names().is_empty());
+        child.add_named("x").unwrap();
+        assert!(x.move_to(&child).is_err());
+    }
+
+    #[test]
+    fn move_keeps_order_and_state() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let first = root.add_prefixed("a");
+        let moved = child.add_prefixed("b");
+        let last = root.add_prefixed("c");
+        moved.move_to(&root).unwrap();
+        assert_eq!(root.names(), vec![first, moved, last]);
+        let pending = child.declare("f").unwrap();
+        pending.name().move_to(&root).unwrap();
+        assert!(!pending.is_defined());
+        pending.define().unwrap();
+        root.add_overload("g", OverloadKey(1)).unwrap();
+        let g = child.add_named("g").unwrap();
+        assert!(g.move_to(&root).is_err());
+    }
+
+    #[test]
+    fn move_respects_case_and_imports() {
+        let root = RootScope::new_root();
+        let sql = root.add_child();
+        sql.set_case_insensitive(true);
+        sql.add_named("Total").unwrap();
+        let module = root.add_child();
+        module.add_named("helper").unwrap();
+        sql.import_all(&module, ImportConflict::Error).unwrap();
+        let other = root.add_child();
+        let total = other.add_named("TOTAL").unwrap();
+        let helper = other.add_named("Helper").unwrap();
+        assert!(total.move_to(&sql).is_err());
+        assert!(helper.move_to(&sql).is_err());
+        assert_eq!(total.scope(), other);
+        assert_eq!(sql.names().len(), 1);
+        let renamed = sql.merge_sibling(&other).unwrap();
+        let texts: Vec<_> = renamed
+            .iter()
+            .map(|(_, replacement)| replacement.local_text().into_owned())
+            .collect();
+        assert_eq!(texts, vec!["TOTAL2", "Helper2"]);
+    }
+
+    #[test]
+    fn reparent_subtree() {
+        let root = RootScope::new_root();
+        let left = root.add_child();
+        let right = root.add_child();
+        let outer_x = right.add_named("x").unwrap();
+        left.add_named("y").unwrap();
+        let moved = left.add_child();
+        let inner_x = moved.add_child().add_named("x").unwrap();
+        let conflicts = moved.reparent(&right).unwrap();
+        assert_eq!(conflicts, vec![(inner_x, outer_x)]);
+        assert_eq!(moved.parent(), Some(right.clone()));
+        assert_eq!(left.children().count(), 0);
+        assert_eq!(right.children().collect::<Vec<_>>(), vec![moved.clone()]);
+        assert!(moved.resolve("y").is_none());
+        assert!(right.reparent(&moved).is_err());
+        assert!(root.reparent(&left).is_err());
+        assert!(moved.reparent(&moved).is_err());
+    }
+
+    #[test]
+    fn merge_siblings() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        first.add_named("x").unwrap();
+        first.add_named("x2").unwrap();
+        let second = root.add_child();
+        let x = second.add_named("x").unwrap();
+        let z = second.add_named("z").unwrap();
+        let tmp = second.add_prefixed("tmp");
+        let nested = second.add_child();
+        let x_use = nested.reference("x");
+        root.root().finalize().unwrap();
+        let renamed = first.merge_sibling(&second).unwrap();
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(renamed[0].0, x);
+        assert!(x.is_removed());
+        assert_eq!(renamed[0].1.local_text(), "x3");
+        assert_eq!(renamed[0].1.scope(), first);
+        assert_eq!(x_use.binding(), Some(renamed[0].1.clone()));
+        assert_eq!(z.scope(), first);
+        assert_eq!(tmp.scope(), first);
+        assert_eq!(nested.parent(), Some(first.clone()));
+        assert_eq!(second.parent(), None);
+        assert!(second.names().is_empty());
+        assert_eq!(root.children().collect::<Vec<_>>(), vec![first]);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn move_from_threads() {
+        let root = RootScope::new_root();
+        let names: Vec<_> = (0..8)
+            .map(|_| root.add_child().add_named("x").unwrap())
+            .collect();
+        let moved: Vec<bool> = ::std::thread::scope(|threads| {
+            let handles: Vec<_> = names
+                .iter()
+                .map(|x| {
+                    let root = root.clone();
+                    threads.spawn(move || x.move_to(&root).is_ok())
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        assert_eq!(moved.iter().filter(|&&moved| moved).count(), 1);
+        assert_eq!(root.names().len(), 1);
+    }
+
+    #[test]
+    fn merge_into_sealed_parent() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let second = root.add_child();
+        let x = second.add_named("x").unwrap();
+        second.add_child();
+        root.seal();
+        assert_eq!(
+            first.merge_sibling(&second),
+            Err(MergeError::Sealed(root.id()))
+        );
+        assert_eq!(x.scope(), second);
+        assert_eq!(second.children().count(), 1);
+        assert_eq!(second.parent(), Some(root.clone()));
+    }
+}
@@ -0,0 +1,131 @@
+/// Removing names and child scopes, e.g. for dead-code elimination, or for passes that
+/// rebuild a scope from scratch.
+///
+/// Removed names free their slot, so the same text can be registered again. Existing
+/// handles stay valid objects, but report that they were removed. Removed child scopes
+/// are detached: their handles stay usable, but they no longer have a parent.
+use crate::name::{InputName, Name};
+use crate::scope::Scope;
+
+impl Scope {
+    /// Unregister a name that was registered directly in this scope. Returns false, without
+    /// changing anything, if it belongs to another scope or was already removed.
+    pub fn remove_name(&self, name: &Name) -> bool {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        if name.scope() != *self || name.is_removed() {
+            return false;
+        }
+        self.root.assert_unsealed(self.index);
+        self.root.root_data.names.borrow_mut()[name.index].removed = true;
+        self.unregister(name);
+        true
+    }
+
+    /// Remove a name from this scope's lookup tables, without marking the handle as removed.
+    /// Returns whether the name was declared but not defined yet.
+    pub(crate) fn unregister(&self, name: &Name) -> bool {
+        self.root.assert_unsealed(self.index);
+        self.root.mark_dirty(self.index);
+        self.root
+            .scope_names_at(self.index, |data| match &name.data {
+                InputName::Given(given) => {
+                    data.given_names.remove(given);
+                    if let Some(key) = given.overload {
+                        if let Some(keys) = data.overloads.get_mut(&given.name) {
+                            keys.retain(|&other| other != key);
+                            if keys.is_empty() {
+                                data.overloads.remove(&given.name);
+                            }
+                        }
+                    }
+                    data.undefined.remove(given)
+                }
+                InputName::Anonymous(_) => {
+                    data.anon_names.retain(|&index| index != name.index);
+                    false
+                }
+            })
+    }
+
+    /// Remove all names registered directly in this scope. Child scopes are unaffected.
+    pub fn clear_names(&self) {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        for name in self.names() {
+            self.remove_name(&name);
+        }
+    }
+
+    /// Detach all child scopes, together with their subtrees.
+    pub fn clear_children(&self) {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        self.root.assert_unsealed(self.index);
+        for child in self.root.child_indices(self.index) {
+            self.root.unlink_child(self.index, child);
+            self.root.scope_data_at(child, |data| data.parent = None);
+        }
+        self.root.shape_changed();
+        self.root.mark_dirty(self.index);
+    }
+}
+
+impl Name {
+    /// Whether this name was unregistered from its scope using `Scope::remove_name`.
+    pub fn is_removed(&self) -> bool {
+        self.root.root_data.names.borrow()[self.index].removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::overload::OverloadKey;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn remove_given() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let outer = root.add_named("x").unwrap();
+        let inner = child.add_named("x").unwrap();
+        assert!(!root.remove_name(&inner));
+        assert!(child.remove_name(&inner));
+        assert!(!child.remove_name(&inner));
+        assert!(inner.is_removed());
+        assert!(!outer.is_removed());
+        assert_eq!(child.resolve("x"), Some(outer));
+        assert_eq!(root.root().name(inner.id()), None);
+        let again = child.add_named("x").unwrap();
+        assert_ne!(again, inner);
+    }
+
+    #[test]
+    fn remove_anonymous_and_overload() {
+        let root = RootScope::new_root();
+        let tmp = root.add_prefixed("tmp");
+        root.add_anonymous();
+        let f1 = root.add_overload("f", OverloadKey(1)).unwrap();
+        assert!(root.remove_name(&tmp));
+        assert_eq!(root.anonymous_names().len(), 1);
+        assert!(root.remove_name(&f1));
+        assert!(root.overloads("f").is_empty());
+        root.add_named("f").unwrap();
+    }
+
+    #[test]
+    fn clear_scope() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        root.add_prefixed("tmp");
+        let child = root.add_child();
+        child.add_named("y").unwrap();
+        root.clear_names();
+        assert!(x.is_removed());
+        assert!(root.names().is_empty());
+        assert_eq!(root.children().count(), 1);
+        root.add_named("x").unwrap();
+        root.clear_children();
+        assert_eq!(root.children().count(), 0);
+        assert_eq!(child.parent(), None);
+        assert_eq!(child.resolve("y").unwrap().scope(), child);
+        assert_eq!(root.names().len(), 1);
+    }
+}
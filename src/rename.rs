@@ -0,0 +1,337 @@
+/// Renaming given names across the whole tree at once, e.g. for a refactoring tool that
+/// renames a symbol in a whole project.
+///
+/// Handles hold the text of their name, so a name cannot change its text in place. Instead,
+/// a rename registers a replacement in the same scope, with the same overload key, arity,
+/// version and namespace, which takes over everything else about the original: its metadata,
+/// and all links to it. Bound references (respelled with the new text), imports,
+/// unification, derivation, emission order and intentional shadowing refer to the
+/// replacement afterwards, and the original is removed.
+use ::std::collections::{HashMap, HashSet};
+use ::std::iter::once;
+
+use ::ustr::Ustr;
+
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{RootScope, Scope};
+use crate::spec::DuplicateName;
+use crate::text::NameText;
+
+impl RootScope {
+    /// Rename every given name in the attached scopes for which `matcher` returns true, to
+    /// the text returned by `renamer`. Either all renames happen, or, if a new text would
+    /// collide in some scope with a name declared or imported there, none do. Names can
+    /// swap texts. Case-insensitive scopes compare texts ignoring case.
+    ///
+    /// Returns the renamed names, as pairs of the removed original and its replacement, in
+    /// preorder of their scopes. Panics if a renamed name is in a sealed scope, or is
+    /// imported into one.
+    pub fn rename_all(
+        &self,
+        mut matcher: impl FnMut(&Name) -> bool,
+        mut renamer: impl FnMut(&Name) -> String,
+    ) -> Result<Vec<(Name, Name)>, DuplicateName> {
+        let _exclusive = self.root_data.exclusive.lock();
+        let root = self.root_scope();
+        let scopes: Vec<Scope> = once(root.clone()).chain(root.descendants()).collect();
+        // Rename and check everything first, so that nothing changes on failure.
+        let mut renames: Vec<(Name, GivenName)> = vec![];
+        // Per scope, the final declared names (compared as `folded` keys) and whether they
+        // were renamed.
+        let mut declared: Vec<HashMap<GivenName, bool>> = vec![];
+        for scope in &scopes {
+            let is_case_insensitive = scope.is_case_insensitive();
+            let mut seen: HashMap<GivenName, bool> = HashMap::new();
+            let mut overloaded: HashSet<NameText> = HashSet::new();
+            for name in scope.names() {
+                let given = match &name.data {
+                    InputName::Given(given) => given,
+                    InputName::Anonymous(_) => continue,
+                };
+                let new_given = if matcher(&name) {
+                    GivenName {
+                        name: self.given_name(&renamer(&name)).name,
+                        ..given.clone()
+                    }
+                } else {
+                    given.clone()
+                };
+                let key = folded(&new_given, is_case_insensitive);
+                let ordinary = GivenName::new(key.name.clone());
+                let is_duplicate = seen.contains_key(&key)
+                    || (key.overload.is_some() && seen.contains_key(&ordinary))
+                    || (key == ordinary && overloaded.contains(&key.name));
+                if is_duplicate {
+                    return Err(DuplicateName(new_given.as_str().to_owned()));
+                }
+                if key.overload.is_some() {
+                    overloaded.insert(key.name.clone());
+                }
+                let is_renamed = new_given != *given;
+                seen.insert(key, is_renamed);
+                if is_renamed {
+                    renames.push((name, new_given));
+                }
+            }
+            declared.push(seen);
+        }
+        // Imports keep the text of the name they import, so they must not collide either.
+        let renamed_to: HashMap<usize, &GivenName> = renames
+            .iter()
+            .map(|(name, new_given)| (name.index, new_given))
+            .collect();
+        let mut importing = vec![];
+        for (scope, declared) in scopes.iter().zip(&declared) {
+            let is_case_insensitive = scope.is_case_insensitive();
+            let imports: Vec<(GivenName, usize)> = self.read_scope_names_at(scope.index, |data| {
+                data.imports
+                    .iter()
+                    .map(|(given, &index)| (given.clone(), index))
+                    .collect()
+            });
+            let mut imported: HashMap<GivenName, bool> = HashMap::new();
+            for (given, index) in imports {
+                if self.name_at(index).is_removed() {
+                    continue;
+                }
+                let (final_given, is_renamed) = match renamed_to.get(&index) {
+                    Some(new_given) => ((*new_given).clone(), true),
+                    None => (given, false),
+                };
+                let key = folded(&final_given, is_case_insensitive);
+                // Names that already coexisted are fine; only renames can introduce clashes.
+                let clashes = |others: &HashMap<GivenName, bool>| {
+                    others
+                        .get(&key)
+                        .is_some_and(|&other_renamed| is_renamed || other_renamed)
+                };
+                if clashes(declared) || clashes(&imported) {
+                    return Err(DuplicateName(final_given.as_str().to_owned()));
+                }
+                if is_renamed {
+                    importing.push(scope.index);
+                }
+                imported.insert(key, is_renamed);
+            }
+        }
+        for (name, _) in &renames {
+            self.assert_unsealed(name.scope().index);
+        }
+        for index in importing {
+            self.assert_unsealed(index);
+        }
+        // During this method, the state is not consistent.
+        // Step 1: remove all originals, so that names can take each other's text.
+        let mut undefined = vec![];
+        for (name, _) in &renames {
+            self.root_data.names.borrow_mut()[name.index].removed = true;
+            undefined.push(name.scope().unregister(name));
+        }
+        // Step 2: register the replacements, declared but not defined where the original was.
+        let mut renamed = vec![];
+        for ((name, new_given), is_undefined) in renames.into_iter().zip(undefined) {
+            let scope = name.scope();
+            let replacement = scope.register_any(new_given.clone());
+            if is_undefined {
+                self.scope_names_at(scope.index, |data| data.undefined.insert(new_given));
+            }
+            renamed.push((name, replacement));
+        }
+        // Step 3: move metadata and links over to the replacements.
+        self.transfer_names(&renamed);
+        Ok(renamed)
+    }
+
+    /// Let each replacement take over the metadata of its original, and redirect all links
+    /// to the original (see the module documentation) to the replacement. The originals
+    /// should already be removed.
+    pub(crate) fn transfer_names(&self, pairs: &[(Name, Name)]) {
+        let replacement_of: HashMap<usize, usize> = pairs
+            .iter()
+            .map(|(original, replacement)| (original.index, replacement.index))
+            .collect();
+        let redirect = |index: usize| replacement_of.get(&index).cloned().unwrap_or(index);
+        {
+            let mut names = self.root_data.names.borrow_mut();
+            for (original, replacement) in pairs {
+                let data = names[original.index].clone();
+                let target = &mut names[replacement.index];
+                target.provenance = data.provenance;
+                target.condition = data.condition;
+                target.visibility = data.visibility;
+                target.deprecated = data.deprecated;
+                target.declarations = data.declarations;
+                target.external = data.external;
+                target.shadows = data.shadows;
+                target.emit_before = data.emit_before;
+                target.ordinal_key = data.ordinal_key;
+                target.unified = data.unified;
+                target.derived_from = data.derived_from;
+                // The original no longer takes part in anything.
+                let source = &mut names[original.index];
+                source.shadows = None;
+                source.emit_before.clear();
+                source.unified.clear();
+                source.derived_from = None;
+            }
+            for index in 0..names.len() {
+                let data = &mut names[index];
+                data.shadows = data.shadows.map(redirect);
+                data.emit_before
+                    .iter_mut()
+                    .for_each(|later| *later = redirect(*later));
+                data.unified
+                    .iter_mut()
+                    .for_each(|other| *other = redirect(*other));
+                if let Some((origin, relation)) = data.derived_from {
+                    data.derived_from = Some((redirect(origin), relation));
+                }
+            }
+        }
+        let given_of: HashMap<usize, (usize, GivenName)> = pairs
+            .iter()
+            .filter_map(|(original, replacement)| match &replacement.data {
+                InputName::Given(given) => {
+                    Some((original.index, (replacement.index, given.clone())))
+                }
+                InputName::Anonymous(_) => None,
+            })
+            .collect();
+        let count = self.root_data.scope_names.borrow().len();
+        for index in 0..count {
+            let is_changed = self.scope_names_at(index, |data| {
+                if !data
+                    .imports
+                    .values()
+                    .any(|target| given_of.contains_key(target))
+                {
+                    return false;
+                }
+                data.imports = data
+                    .imports
+                    .drain()
+                    .map(|(given, target)| match given_of.get(&target) {
+                        Some((replacement, new_given)) => (new_given.clone(), *replacement),
+                        None => (given, target),
+                    })
+                    .collect();
+                true
+            });
+            if is_changed {
+                self.mark_dirty(index);
+            }
+        }
+        let references: HashMap<usize, (usize, Ustr)> = given_of
+            .iter()
+            .map(|(&original, (replacement, given))| {
+                (original, (*replacement, Ustr::from(given.as_str())))
+            })
+            .collect();
+        self.rebind_references(&references);
+    }
+}
+
+// The name as compared in a scope: in lowercase if the scope ignores case.
+fn folded(given: &GivenName, is_case_insensitive: bool) -> GivenName {
+    if !is_case_insensitive {
+        return given.clone();
+    }
+    GivenName {
+        name: NameText::from(given.as_str().to_lowercase().as_str()),
+        ..given.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::ImportConflict;
+    use crate::scope::RootScope;
+    use crate::spec::DuplicateName;
+
+    #[test]
+    fn rename_across_tree() {
+        let root = RootScope::new_root();
+        let old = root.add_named("old_name").unwrap();
+        let other = root.add_named("other").unwrap();
+        let function = root.add_child();
+        let inner = function.add_named("old_name").unwrap();
+        let renamed = root
+            .root()
+            .rename_all(
+                |name| name.local_text() == "old_name",
+                |_| "new_name".to_owned(),
+            )
+            .unwrap();
+        assert_eq!(renamed.len(), 2);
+        assert_eq!(renamed[0].0, old);
+        assert_eq!(renamed[1].0, inner);
+        assert!(old.is_removed());
+        assert_eq!(root.resolve("new_name"), Some(renamed[0].1.clone()));
+        assert_eq!(function.resolve("new_name"), Some(renamed[1].1.clone()));
+        assert_eq!(root.resolve("old_name"), None);
+        assert!(!other.is_removed());
+    }
+
+    #[test]
+    fn rename_is_all_or_nothing() {
+        let root = RootScope::new_root();
+        let a = root.add_named("a").unwrap();
+        let b = root.add_named("b").unwrap();
+        let child = root.add_child();
+        child.add_named("a").unwrap();
+        child.add_named("c").unwrap();
+        // Swapping works, since the originals are removed first.
+        let swap =
+            |name: &crate::name::Name| if name.local_text() == "a" { "b" } else { "a" }.to_owned();
+        root.root()
+            .rename_all(|name| name.scope() == root, swap)
+            .unwrap();
+        assert!(a.is_removed() && b.is_removed());
+        assert_eq!(root.names().len(), 2);
+        // Renaming `a` to `c` in the child collides, so the root is not renamed either.
+        assert_eq!(
+            root.root()
+                .rename_all(|name| name.local_text() == "a", |_| "c".to_owned()),
+            Err(DuplicateName("c".to_owned()))
+        );
+        assert!(root.resolve("a").is_some());
+        assert_eq!(child.resolve("a").map(|name| name.scope()), Some(child));
+    }
+
+    #[test]
+    fn rename_keeps_links() {
+        let root = RootScope::new_root();
+        let module = root.add_child();
+        let helper = module.add_named("helper").unwrap();
+        helper.deprecate("use assist");
+        let user = root.add_child();
+        user.import_all(&module, ImportConflict::Error).unwrap();
+        let local = user.add_named("local").unwrap();
+        let first_use = user.reference("helper");
+        root.root().finalize().unwrap();
+        root.root().unify(&helper, &local).unwrap();
+        let renamed = root
+            .root()
+            .rename_all(|name| name == &helper, |_| "assist".to_owned())
+            .unwrap();
+        let assist = renamed[0].1.clone();
+        assert_eq!(first_use.binding(), Some(assist.clone()));
+        assert_eq!(first_use.text().as_str(), "assist");
+        assert_eq!(user.resolve("assist"), Some(assist.clone()));
+        assert!(user.resolve("helper").is_none());
+        assert_eq!(local.unified_names(), vec![local.clone(), assist.clone()]);
+        assert_eq!(assist.deprecation().unwrap().as_str(), "use assist");
+        // The new text would hide the import behind a local name, or collide ignoring case.
+        assert_eq!(
+            root.root()
+                .rename_all(|name| name == &local, |_| "assist".to_owned()),
+            Err(DuplicateName("assist".to_owned()))
+        );
+        user.set_case_insensitive(true);
+        assert_eq!(
+            root.root()
+                .rename_all(|name| name == &local, |_| "ASSIST".to_owned()),
+            Err(DuplicateName("assist".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,111 @@
+/// A summary of the shape of a tree, for tuning how a front-end constructs scopes, e.g. to
+/// spot deep nesting from desugaring or scopes with unusually many names.
+use ::std::collections::BTreeMap;
+use ::std::fmt;
+
+use crate::kind::ScopeKind;
+use crate::scope::RootScope;
+
+/// The result of `RootScope::report`. `Display` renders it for humans.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TreeReport {
+    pub scopes: usize,
+    /// The number of scopes at each depth, starting with the root at depth 0.
+    pub scopes_per_depth: Vec<usize>,
+    /// In declaration order of `ScopeKind`.
+    pub scopes_per_kind: BTreeMap<ScopeKind, usize>,
+    /// For each number of names, how many scopes have that many names.
+    pub names_per_scope: BTreeMap<usize, usize>,
+    pub given_names: usize,
+    pub anonymous_names: usize,
+}
+
+impl RootScope {
+    /// Summarize the scopes that are attached to the tree.
+    pub fn report(&self) -> TreeReport {
+        let mut report = TreeReport::default();
+        let mut pending = vec![(self.root_scope(), 0)];
+        while let Some((scope, depth)) = pending.pop() {
+            report.scopes += 1;
+            if report.scopes_per_depth.len() <= depth {
+                report.scopes_per_depth.push(0);
+            }
+            report.scopes_per_depth[depth] += 1;
+            *report.scopes_per_kind.entry(scope.kind()).or_default() += 1;
+            let (given, anonymous) = self.read_scope_names_at(scope.index, |data| {
+                (data.given_names.len(), data.anon_names.len())
+            });
+            *report.names_per_scope.entry(given + anonymous).or_default() += 1;
+            report.given_names += given;
+            report.anonymous_names += anonymous;
+            pending.extend(scope.children().map(|child| (child, depth + 1)));
+        }
+        report
+    }
+}
+
+impl fmt::Display for TreeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "scopes: {}", self.scopes)?;
+        writeln!(f, "scopes per depth:")?;
+        for (depth, count) in self.scopes_per_depth.iter().enumerate() {
+            writeln!(f, "  {:>4}: {}", depth, count)?;
+        }
+        writeln!(f, "scopes per kind:")?;
+        for (kind, count) in &self.scopes_per_kind {
+            writeln!(f, "  {:?}: {}", kind, count)?;
+        }
+        writeln!(f, "names per scope:")?;
+        for (names, count) in &self.names_per_scope {
+            writeln!(f, "  {:>4}: {}", names, count)?;
+        }
+        let total = self.given_names + self.anonymous_names;
+        let anonymous_percent = match total {
+            0 => 0.0,
+            _ => 100.0 * self.anonymous_names as f64 / total as f64,
+        };
+        writeln!(
+            f,
+            "names: {} given, {} anonymous ({:.1}% anonymous)",
+            self.given_names, self.anonymous_names, anonymous_percent
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kind::ScopeKind;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn report_shape() {
+        let root = RootScope::new_root();
+        root.set_kind(ScopeKind::Module);
+        root.add_named("main").unwrap();
+        let function = root.add_child_with(ScopeKind::Function, "main");
+        function.add_named("x").unwrap();
+        function.add_prefixed("tmp");
+        function.add_child().add_anonymous();
+        root.add_child();
+        let report = root.root().report();
+        assert_eq!(report.scopes, 4);
+        assert_eq!(report.scopes_per_depth, vec![1, 2, 1]);
+        let kinds: Vec<_> = report.scopes_per_kind.clone().into_iter().collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (ScopeKind::Module, 1),
+                (ScopeKind::Function, 1),
+                (ScopeKind::Block, 2)
+            ]
+        );
+        assert_eq!(report.names_per_scope.get(&0), Some(&1));
+        assert_eq!(report.names_per_scope.get(&1), Some(&2));
+        assert_eq!(report.names_per_scope.get(&2), Some(&1));
+        assert_eq!((report.given_names, report.anonymous_names), (2, 2));
+        let text = report.to_string();
+        assert!(text.starts_with("scopes: 4\n"));
+        assert!(text.contains("  Function: 1\n"));
+        assert!(text.ends_with("names: 2 given, 2 anonymous (50.0% anonymous)\n"));
+    }
+}
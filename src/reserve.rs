@@ -0,0 +1,116 @@
+/// Reserved outputs: strings that generated names must avoid in a scope and the scopes
+/// below it, e.g. because the emitted code there uses a fixed helper identifier.
+///
+/// Reserving does not create a name, so it does not affect resolution, and input names with
+/// the same text can still be registered. An output can also be claimed for later use, e.g.
+/// by a hand-written runtime snippet, in which case a token keeps track of it.
+use crate::scope::{AlreadyExists, Scope};
+use crate::text::NameText;
+
+/// An output claimed with `Scope::reserve_output`, which stays reserved until released.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReservedOutput {
+    scope: Scope,
+    text: NameText,
+}
+
+impl ReservedOutput {
+    /// The scope where the output was reserved.
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// Give up the claim, so that generated names may use the output again.
+    pub fn release(self) {
+        self.scope
+            .root
+            .scope_names_at(self.scope.index, |data| data.reserved.remove(&self.text));
+    }
+}
+
+impl Scope {
+    /// Forbid the text as output for names in this scope and its descendants.
+    pub fn reserve(&self, text: &str) {
+        self.root.scope_names_at(self.index, |data| {
+            data.reserved.insert(NameText::from(text))
+        });
+    }
+
+    /// Claim the output for later use in this scope and its descendants. Unlike `reserve`,
+    /// this fails if the output is already reserved here or in an ancestor, since two
+    /// claims on the same output would conflict.
+    pub fn reserve_output(&self, text: &str) -> Result<ReservedOutput, AlreadyExists> {
+        if self.is_reserved(text) {
+            return Err(AlreadyExists());
+        }
+        self.reserve(text);
+        Ok(ReservedOutput {
+            scope: self.clone(),
+            text: NameText::from(text),
+        })
+    }
+
+    /// Whether the text was reserved in this scope or one of its ancestors.
+    pub fn is_reserved(&self, text: &str) -> bool {
+        let text = NameText::from(text);
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            if scope
+                .root
+                .read_scope_names_at(scope.index, |data| data.reserved.contains(&text))
+            {
+                return true;
+            }
+            current = scope.parent();
+        }
+        false
+    }
+
+    /// The texts reserved directly in this scope, sorted.
+    pub fn reserved(&self) -> Vec<String> {
+        let mut reserved: Vec<String> = self.root.scope_names_at(self.index, |data| {
+            data.reserved
+                .iter()
+                .map(|text| text.as_str().to_owned())
+                .collect()
+        });
+        reserved.sort_unstable();
+        reserved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn reserve_in_subtree() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        let block = function.add_child();
+        function.reserve("__helper");
+        function.reserve("$tmp");
+        assert!(block.is_reserved("__helper"));
+        assert!(!root.is_reserved("__helper"));
+        assert_eq!(function.reserved(), vec!["$tmp", "__helper"]);
+        block.add_named("__helper").unwrap();
+        assert!(root.resolve("__helper").is_none());
+    }
+
+    #[test]
+    fn claim_and_release_output() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let claim = root.reserve_output("rt_alloc").unwrap();
+        assert_eq!(claim.as_str(), "rt_alloc");
+        assert_eq!(claim.scope(), &root);
+        child.reserve_output("rt_alloc").unwrap_err();
+        claim.release();
+        assert!(!child.is_reserved("rt_alloc"));
+        child.reserve_output("rt_alloc").unwrap();
+    }
+}
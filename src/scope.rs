@@ -6,47 +6,110 @@
 /// It is designed to avoid excessive allocations, by storing names and sub-scopes
 /// contiguously inside the root scope. This does mean that no memory will be
 /// reclaimed until the last scope is dropped (which drops the root along with data).
-use ::std::cell::RefCell;
-use ::std::collections::HashSet;
+use ::std::collections::hash_map::RandomState;
+use ::std::collections::{HashMap, HashSet};
 use ::std::fmt;
-use ::std::hash;
-use ::std::rc::Rc;
+use ::std::hash::{self, BuildHasher, Hasher};
+use ::std::iter::{once, successors};
+use ::std::ops::Range;
 use ::std::sync::atomic::AtomicUsize;
 use ::std::sync::atomic::Ordering::Relaxed;
+use ::std::vec::IntoIter;
 
 use ::lazy_static::lazy_static;
 
-use crate::name::{AnonName, GivenName, InputName, Name};
+use crate::backend::{self, Arena, Exclusive, Flag, Lock, Shared};
+use crate::canonical::Canonicalization;
+use crate::diagnostics::DiagnosticData;
+use crate::generate::OutputPolicy;
+use crate::kind::ScopeKind;
+use crate::limit::{Limits, RegisterError};
+use crate::name::{AnonName, GivenName, InputName, Name, NameData, NameId};
+use crate::order::ScopeOrder;
+use crate::overload::OverloadKey;
+use crate::reference::Reference;
+use crate::text::NameText;
+use crate::visibility::Visibility;
 use ustr::Ustr;
 
 lazy_static! {
     static ref COUNTER: AtomicUsize = AtomicUsize::new(0);
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Identity of a root, which is unique across processes with overwhelming probability, so
+/// that it can be stored with a serialized tree and still be meaningful after loading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RootId(pub u128);
+
+impl RootId {
+    /// A new random id.
+    pub fn random() -> Self {
+        // The std hasher keys are random per process (and differ per instance), and the
+        // counter makes sure ids differ within the process. The clock is not used, since
+        // reading it panics on `wasm32-unknown-unknown`.
+        let count = COUNTER.fetch_add(1, Relaxed);
+        let half = || {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_usize(count);
+            hasher.finish() as u128
+        };
+        RootId(half() << 64 | half())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct RootScope {
     // This prevents us from needing
-    root_data: Rc<RootScopeData>,
+    pub(crate) root_data: Shared<RootScopeData>,
 }
 
-struct RootScopeData {
-    // This number just exists for equality/hash, so that each RootScope is equal
+pub(crate) struct RootScopeData {
+    // This id just exists for equality/hash, so that each RootScope is equal
     // if it points to the same RootScopeData. Perhaps this could have been done
     // with pointers, but for now I'm not confident I understand the guarantees
     // around moving and pointers and optimizations well enough for that.
-    nr: usize,
-    //TODO @mark: it might actually be better to just make name cache global or thread-local-global, names probably re-appear in different program units (would delay memory cleanup though).
-    //names: RefCell<StringInterner<usize>>,  //TODO @mark: TEMPORARY! REMOVE THIS!
-    scopes: RefCell<Vec<ScopeData>>,
-    // I decided to not expose the Scope of the root for now. If it's desired after
-    // all, it can be obtained by relying on the convention that scopes[0] is the root.
+    pub(crate) id: RootId,
+    // There is no name cache per root: texts are interned with `ustr`, whose cache is global,
+    // so identical names in different program units already share one string.
+    pub(crate) scopes: Lock<Arena<ScopeData>>,
+    // The names of each scope, at the same index as its `ScopeData`.
+    pub(crate) scope_names: Lock<Arena<ScopeNames>>,
+    // All names of all scopes, in registration order.
+    pub(crate) names: Lock<Arena<NameData>>,
+    // Traversal numbering of the scopes, computed on demand and cleared when the shape changes.
+    pub(crate) order: Lock<Option<ScopeOrder>>,
+    // Arena indices of scopes changed since the last `RootScope::mark_clean`.
+    pub(crate) dirty: Lock<HashSet<usize>>,
+    // Uses of names recorded before they could be resolved.
+    pub(crate) references: Lock<Arena<Reference>>,
+    // Label recorded on every name registered while it is set.
+    pub(crate) provenance: Flag<Option<Ustr>>,
+    // Diagnostics recorded since the last `RootScope::take_diagnostics`.
+    pub(crate) diagnostics: Lock<Vec<DiagnosticData>>,
+    // Set once any scope is marked case-insensitive, so other trees skip the check.
+    pub(crate) has_case_insensitive: Flag<bool>,
+    // Whether registering an existing given name again returns the original.
+    pub(crate) merge_duplicates: Flag<bool>,
+    // How given names are normalized before they are registered or looked up.
+    pub(crate) canonicalization: Flag<Canonicalization>,
+    // Held during compound changes, like checking for and then registering a name.
+    pub(crate) exclusive: Exclusive,
+    // Bounds on the size of the tree, checked when scopes and names are created.
+    pub(crate) limits: Flag<Limits>,
+    // Whether to print unused names and empty scopes when the tree is dropped.
+    pub(crate) report_unused: Flag<bool>,
+    // The policy for outputs that are assigned on demand, see `RootScope::generate_lazily`.
+    pub(crate) lazy_outputs: Lock<Option<Shared<OutputPolicy>>>,
+    // By convention, scopes[0] is the scope of the root, see `RootScope::root_scope`.
 }
 
 impl fmt::Debug for RootScopeData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "RootScopeData {{ ")?;
-        write!(f, "nr: {}, ", self.nr)?;
+        write!(f, "id: {:032x}, ", self.id.0)?;
         write!(f, "scopes: {}, ", self.scopes.borrow().len())?;
+        write!(f, "names: {}, ", self.names.borrow().len())?;
+        write!(f, "references: {}, ", self.references.borrow().len())?;
         write!(f, " }}")
     }
 }
@@ -54,20 +117,42 @@ impl fmt::Debug for RootScopeData {
 impl RootScope {
     /// Return a new Scope, that holds a reference to a newly created RootScope.
     pub fn new_root() -> Scope {
+        RootScope::new_root_with_id(RootId::random())
+    }
+
+    /// Like `new_root`, but with a known id, e.g. one stored with a serialized tree.
+    /// Roots with the same id compare equal, so ids should not be reused in a process.
+    pub fn new_root_with_id(id: RootId) -> Scope {
         // Create the root element.
         let root = RootScope {
-            root_data: Rc::new(RootScopeData {
-                nr: COUNTER.fetch_add(1, Relaxed),
-                scopes: RefCell::new(vec![]),
+            root_data: Shared::new(RootScopeData {
+                id,
+                scopes: Lock::new(backend::new_arena()),
+                scope_names: Lock::new(backend::new_arena()),
+                names: Lock::new(backend::new_arena()),
+                order: Lock::new(None),
+                dirty: Lock::new(HashSet::new()),
+                references: Lock::new(backend::new_arena()),
+                provenance: Flag::new(None),
+                diagnostics: Lock::new(vec![]),
+                has_case_insensitive: Flag::new(false),
+                merge_duplicates: Flag::new(false),
+                canonicalization: Flag::new(Canonicalization::default()),
+                exclusive: backend::new_exclusive(),
+                limits: Flag::new(Limits::default()),
+                report_unused: Flag::new(false),
+                lazy_outputs: Lock::new(None),
             }),
         };
         // Create ScopeData for the root element.
-        root.root_data.scopes.borrow_mut().push(ScopeData {
-            parent: None,
-            children: vec![],
-            given_names: HashSet::new(),
-            anon_names: vec![],
-        });
+        backend::push(
+            &mut root.root_data.scopes.borrow_mut(),
+            ScopeData::new(None),
+        );
+        backend::push(
+            &mut root.root_data.scope_names.borrow_mut(),
+            ScopeNames::default(),
+        );
         // Return a Scope pointing to that element.
         Scope {
             root,
@@ -75,25 +160,317 @@ impl RootScope {
         }
     }
 
+    /// Copy the whole tree into a new, independent root, e.g. to keep the state of the
+    /// symbol table after each compilation phase. Scopes and names keep their ids.
+    ///
+    /// This copies all data, unless the `persistent` feature is enabled, in which case the
+    /// copy shares structure with the original, and only parts that change are copied.
+    pub fn snapshot(&self) -> RootScope {
+        RootScope {
+            root_data: Shared::new(RootScopeData {
+                id: RootId::random(),
+                scopes: self.root_data.scopes.clone(),
+                scope_names: self.root_data.scope_names.clone(),
+                names: self.root_data.names.clone(),
+                order: Lock::new(None),
+                dirty: self.root_data.dirty.clone(),
+                references: self.root_data.references.clone(),
+                provenance: self.root_data.provenance.clone(),
+                diagnostics: self.root_data.diagnostics.clone(),
+                has_case_insensitive: self.root_data.has_case_insensitive.clone(),
+                merge_duplicates: self.root_data.merge_duplicates.clone(),
+                canonicalization: self.root_data.canonicalization.clone(),
+                exclusive: backend::new_exclusive(),
+                limits: self.root_data.limits.clone(),
+                report_unused: self.root_data.report_unused.clone(),
+                lazy_outputs: self.root_data.lazy_outputs.clone(),
+            }),
+        }
+    }
+
+    pub fn id(&self) -> RootId {
+        self.root_data.id
+    }
+
+    /// The top-level scope of this tree, i.e. the one returned by `new_root`.
+    pub fn root_scope(&self) -> Scope {
+        self.scope_at_index(0)
+    }
+
     /// Add new scope data, returning a new scope that refers to it.
     fn add_scope(&self, scope_data: ScopeData) -> Scope {
+        // Check before allocating, so that a failure leaves no orphan scope behind.
+        if let Some(parent) = scope_data.parent {
+            self.assert_unsealed(parent);
+        }
+        if let Err(exceeded) = self.check_scope_limits(scope_data.parent) {
+            panic!("{}", exceeded);
+        }
         let mut scopes = self.root_data.scopes.borrow_mut();
-        scopes.push(scope_data);
+        backend::push(&mut scopes, scope_data);
+        backend::push(
+            &mut self.root_data.scope_names.borrow_mut(),
+            ScopeNames::default(),
+        );
         Scope {
             root: self.clone(),
             index: scopes.len() - 1,
         }
     }
 
+    /// Look up a scope by its handle, or None if it is not part of this tree.
+    pub fn scope(&self, id: ScopeId) -> Option<Scope> {
+        if id.0 < self.root_data.scopes.borrow().len() {
+            Some(self.scope_at_index(id.0))
+        } else {
+            None
+        }
+    }
+
+    /// Look up a name by its handle, or None if it is not part of this tree or was removed.
+    pub fn name(&self, id: NameId) -> Option<Name> {
+        let is_registered = self
+            .root_data
+            .names
+            .borrow()
+            .get(id.0)
+            .is_some_and(|name_data| !name_data.removed);
+        if is_registered {
+            Some(self.name_at(id.0))
+        } else {
+            None
+        }
+    }
+
+    /// Compare the shape and names of two trees, ignoring the identity of the roots.
+    ///
+    /// Trees are structurally equal if all their scopes have the same kind and label, the same
+    /// given names, the same anonymous prefixes in the same order, and structurally equal
+    /// children in the same order.
+    pub fn structurally_equal(&self, other: &RootScope) -> bool {
+        if self == other {
+            return true;
+        }
+        let scopes = self.root_data.scopes.borrow();
+        let scope_names = self.root_data.scope_names.borrow();
+        let names = self.root_data.names.borrow();
+        let other_scopes = other.root_data.scopes.borrow();
+        let other_scope_names = other.root_data.scope_names.borrow();
+        let other_names = other.root_data.names.borrow();
+        scopes_structurally_equal(
+            (&scopes, &scope_names, &names, 0),
+            (&other_scopes, &other_scope_names, &other_names, 0),
+        )
+    }
+
+    /// Create a handle to the scope at the given arena index.
+    pub(crate) fn scope_at_index(&self, index: usize) -> Scope {
+        debug_assert!(index < self.root_data.scopes.borrow().len());
+        Scope {
+            root: self.clone(),
+            index,
+        }
+    }
+
+    /// Create a handle to the name at the given arena index.
+    pub(crate) fn name_at(&self, index: usize) -> Name {
+        let names = self.root_data.names.borrow();
+        let name_data = &names[index];
+        Name {
+            root: self.clone(),
+            data: name_data.data.clone(),
+            index,
+        }
+    }
+
+    /// Add a name to the arena, without registering it in its scope.
+    fn push_name(&self, scope_index: usize, data: InputName) -> Name {
+        self.assert_unsealed(scope_index);
+        if let Err(exceeded) = self.check_name_limits(scope_index) {
+            panic!("{}", exceeded);
+        }
+        self.mark_dirty(scope_index);
+        let mut names = self.root_data.names.borrow_mut();
+        backend::push(
+            &mut names,
+            NameData {
+                scope: scope_index,
+                data: data.clone(),
+                provenance: self.provenance(),
+                removed: false,
+                condition: None,
+                visibility: Visibility::default(),
+                deprecated: None,
+                declarations: 1,
+                external: None,
+                shadows: None,
+                emit_before: vec![],
+                ordinal_key: None,
+                unified: vec![],
+                derived_from: None,
+                output: None,
+            },
+        );
+        Name {
+            root: self.clone(),
+            data,
+            index: names.len() - 1,
+        }
+    }
+
     /// Look up a scope in the arena.
-    fn scope_data_at<T>(&self, index: usize, accessor: impl FnOnce(&mut ScopeData) -> T) -> T {
+    pub(crate) fn scope_data_at<T>(
+        &self,
+        index: usize,
+        accessor: impl FnOnce(&mut ScopeData) -> T,
+    ) -> T {
         accessor(&mut self.root_data.scopes.borrow_mut()[index])
     }
+
+    /// Read a scope in the arena. Unlike `scope_data_at`, this only borrows the arena
+    /// immutably, so reads may be nested.
+    pub(crate) fn read_scope_data_at<T>(
+        &self,
+        index: usize,
+        reader: impl FnOnce(&ScopeData) -> T,
+    ) -> T {
+        reader(&self.root_data.scopes.borrow()[index])
+    }
+
+    /// Look up the names of a scope in the arena. This does not borrow the `ScopeData`.
+    pub(crate) fn scope_names_at<T>(
+        &self,
+        index: usize,
+        accessor: impl FnOnce(&mut ScopeNames) -> T,
+    ) -> T {
+        accessor(&mut self.root_data.scope_names.borrow_mut()[index])
+    }
+
+    /// Read the names of a scope in the arena, like `read_scope_data_at`.
+    pub(crate) fn read_scope_names_at<T>(
+        &self,
+        index: usize,
+        reader: impl FnOnce(&ScopeNames) -> T,
+    ) -> T {
+        reader(&self.root_data.scope_names.borrow()[index])
+    }
+
+    /// The arena indices of the children of a scope, in order.
+    pub(crate) fn child_indices(&self, index: usize) -> Vec<usize> {
+        children_of(&self.root_data.scopes.borrow(), index).collect()
+    }
+
+    /// Add a scope to the children of the parent, before the given child, or at the end if
+    /// there is none. The scope should not be a child of any scope yet.
+    pub(crate) fn link_child(&self, parent: usize, child: usize, before: Option<usize>) {
+        self.assert_unsealed(parent);
+        let mut scopes = self.root_data.scopes.borrow_mut();
+        let prev = match before {
+            Some(before) => scopes[before].prev_sibling,
+            None => scopes[parent].last_child,
+        };
+        scopes[child].prev_sibling = prev;
+        scopes[child].next_sibling = before;
+        match prev {
+            Some(prev) => scopes[prev].next_sibling = Some(child),
+            None => scopes[parent].first_child = Some(child),
+        }
+        match before {
+            Some(before) => scopes[before].prev_sibling = Some(child),
+            None => scopes[parent].last_child = Some(child),
+        }
+    }
+
+    /// Remove a scope from the children of its parent. Its parent is not changed.
+    pub(crate) fn unlink_child(&self, parent: usize, child: usize) {
+        let mut scopes = self.root_data.scopes.borrow_mut();
+        let prev = scopes[child].prev_sibling.take();
+        let next = scopes[child].next_sibling.take();
+        match prev {
+            Some(prev) => scopes[prev].next_sibling = next,
+            None => scopes[parent].first_child = next,
+        }
+        match next {
+            Some(next) => scopes[next].prev_sibling = prev,
+            None => scopes[parent].last_child = prev,
+        }
+    }
+}
+
+/// The arena indices of the children of a scope, in order.
+pub(crate) fn children_of(
+    scopes: &Arena<ScopeData>,
+    parent: usize,
+) -> impl Iterator<Item = usize> + '_ {
+    successors(scopes[parent].first_child, move |&child| {
+        scopes[child].next_sibling
+    })
+}
+
+fn anon_prefix(name_data: &NameData) -> Option<&NameText> {
+    match &name_data.data {
+        InputName::Anonymous(anon) => Some(&anon.name),
+        InputName::Given(_) => None,
+    }
+}
+
+type ArenaPosition<'a> = (
+    &'a Arena<ScopeData>,
+    &'a Arena<ScopeNames>,
+    &'a Arena<NameData>,
+    usize,
+);
+
+fn scopes_structurally_equal(
+    (scopes, scope_names, names, index): ArenaPosition,
+    (other_scopes, other_scope_names, other_names, other_index): ArenaPosition,
+) -> bool {
+    let data = &scope_names[index];
+    let other = &other_scope_names[other_index];
+    scopes[index].kind == other_scopes[other_index].kind
+        && scopes[index].label == other_scopes[other_index].label
+        && data.given_names.len() == other.given_names.len()
+        && data
+            .given_names
+            .keys()
+            .all(|given| other.given_names.contains_key(given))
+        && data.anon_names.len() == other.anon_names.len()
+        && data
+            .anon_names
+            .iter()
+            .zip(&other.anon_names)
+            .all(|(&anon, &other_anon)| {
+                anon_prefix(&names[anon]) == anon_prefix(&other_names[other_anon])
+            })
+        && children_of(scopes, index).count() == children_of(other_scopes, other_index).count()
+        && children_of(scopes, index)
+            .zip(children_of(other_scopes, other_index))
+            .all(|(child, other_child)| {
+                scopes_structurally_equal(
+                    (scopes, scope_names, names, child),
+                    (other_scopes, other_scope_names, other_names, other_child),
+                )
+            })
+}
+
+impl PartialEq for RootScope {
+    fn eq(&self, other: &Self) -> bool {
+        // Handles to the same tree are almost always clones, so usually the pointers match.
+        Shared::ptr_eq(&self.root_data, &other.root_data) || self.root_data.id == other.root_data.id
+    }
+}
+
+impl Eq for RootScope {}
+
+impl hash::Hash for RootScope {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.root_data.id.hash(state)
+    }
 }
 
 impl PartialEq for RootScopeData {
     fn eq(&self, other: &Self) -> bool {
-        self.nr == other.nr
+        self.id == other.id
     }
 }
 
@@ -101,24 +478,114 @@ impl Eq for RootScopeData {}
 
 impl hash::Hash for RootScopeData {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        self.nr.hash(state)
+        self.id.hash(state)
     }
 }
 
-#[derive(Debug, Clone)]
+/// Lightweight handle to a scope, e.g. for keying side tables by plain integers.
+///
+/// It is only meaningful together with the root it came from, see `RootScope::scope`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScopeId(pub usize);
+
+#[derive(Clone)]
 pub struct Scope {
-    root: RootScope,
-    index: usize,
+    pub(crate) root: RootScope,
+    pub(crate) index: usize,
 }
 
 //TODO @mark: Use special UstrSet for faster hashing
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ScopeData {
-    parent: Option<usize>,
-    children: Vec<usize>,
-    given_names: HashSet<GivenName>,
-    anon_names: Vec<AnonName>,
+    pub(crate) parent: Option<usize>,
+    // The children are linked through their sibling fields, as arena indices, so that
+    // scopes do not need to allocate a list of them.
+    pub(crate) first_child: Option<usize>,
+    pub(crate) last_child: Option<usize>,
+    pub(crate) next_sibling: Option<usize>,
+    pub(crate) prev_sibling: Option<usize>,
+    // The source code range covered by this scope, if known.
+    pub(crate) span: Option<Range<usize>>,
+    // Name of the scope in paths, like a module or function name.
+    pub(crate) label: Option<Ustr>,
+    // Free-form description for debug output only, like the construct it was desugared from.
+    pub(crate) debug_label: Option<Ustr>,
+    pub(crate) kind: ScopeKind,
+    // Secondary parents whose names are visible here, in order of precedence.
+    pub(crate) mixins: Vec<usize>,
+    // Configuration flag that must be active for the scope to exist, if any.
+    pub(crate) condition: Option<Ustr>,
+    // Other scopes that may access the private names of this one.
+    pub(crate) grants: Vec<usize>,
+    // Overrides the case sensitivity inherited from the parent, if set.
+    pub(crate) case_insensitive: Option<bool>,
+    // Whether the scope was emptied by `RootScope::collect_garbage`.
+    pub(crate) collected: bool,
+    // Whether names and children can no longer be added or removed, see `Scope::seal`.
+    pub(crate) sealed: bool,
+    // Scopes that must be emitted after this one, as arena indices.
+    pub(crate) emit_before: Vec<usize>,
+    // The output this scope is emitted to, like a file name, if set on this scope itself.
+    pub(crate) target: Option<Ustr>,
+    // Prefix for anonymous names registered without one, if set on this scope itself.
+    pub(crate) anonymous_prefix: Option<Ustr>,
+}
+
+/// The names registered in a scope. These are stored apart from the `ScopeData`, in their
+/// own arena with the same indices, so that names can be registered while the structure
+/// of the tree is being read, and the other way around.
+#[derive(Debug, Clone, Default)]
+pub struct ScopeNames {
+    // Given names, with their index in the names arena.
+    pub(crate) given_names: HashMap<GivenName, usize>,
+    // Anonymous names, as indices in the names arena.
+    pub(crate) anon_names: Vec<usize>,
+    // Keys of overloaded given names, in registration order.
+    pub(crate) overloads: HashMap<NameText, Vec<OverloadKey>>,
+    // Given names that have been declared, but not defined yet.
+    pub(crate) undefined: HashSet<GivenName>,
+    // Given names imported from other scopes, with their index in the names arena.
+    pub(crate) imports: HashMap<GivenName, usize>,
+    // Outputs that generated names in this scope and below must avoid.
+    pub(crate) reserved: HashSet<NameText>,
+}
+
+impl ScopeData {
+    pub(crate) fn new(parent: Option<usize>) -> Self {
+        ScopeData {
+            parent,
+            first_child: None,
+            last_child: None,
+            next_sibling: None,
+            prev_sibling: None,
+            span: None,
+            label: None,
+            debug_label: None,
+            kind: ScopeKind::default(),
+            mixins: vec![],
+            condition: None,
+            grants: vec![],
+            case_insensitive: None,
+            collected: false,
+            sealed: false,
+            emit_before: vec![],
+            target: None,
+            anonymous_prefix: None,
+        }
+    }
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Scope");
+        debug.field("root", &self.root);
+        debug.field("index", &self.index);
+        if let Some(debug_label) = self.debug_label() {
+            debug.field("debug_label", &debug_label);
+        }
+        debug.finish()
+    }
 }
 
 impl PartialEq for Scope {
@@ -136,113 +603,524 @@ impl hash::Hash for Scope {
     }
 }
 
+/// Iterates over the children of a scope, see `Scope::children`.
+///
+/// Like all iterators over the tree, it holds no borrow of the tree between calls to
+/// `next`, so the tree can be changed while iterating. Children are followed lazily though,
+/// so moving or removing the child that was just returned changes which come next; use
+/// `Scope::into_children` to iterate over the children as they were at the start.
 #[derive(Debug)]
 pub struct ScopeChildrenIterator {
-    scope: Scope,
-    // Note: `child_nr` is the index within `.children`, not within the 'arena'.
-    child_nr: usize,
+    root: RootScope,
+    // Arena index of the child to return next.
+    next: Option<usize>,
 }
 
 impl Iterator for ScopeChildrenIterator {
     type Item = Scope;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Convert the .children-index into arena-index.
-        let child_index = self.scope.root.scope_data_at(self.scope.index, |data| {
-            data.children.get(self.child_nr).cloned()
-        });
-        match child_index {
-            Some(child_index) => {
-                // Create a Scope for that index.
-                let scope = Scope {
-                    root: self.scope.root.clone(),
-                    index: child_index,
-                };
-                // Make sure the next iteration returns the next child.
-                self.child_nr += 1;
-                Some(scope)
-            }
-            None => None,
+        let index = self.next?;
+        // Make sure the next iteration returns the next child.
+        self.next = self
+            .root
+            .read_scope_data_at(index, |data| data.next_sibling);
+        Some(self.root.scope_at_index(index))
+    }
+}
+
+/// Iterates over all scopes below a scope, parents before children, see `Scope::descendants`.
+///
+/// Holds no borrow of the tree between calls to `next`, but the next scope is decided when
+/// the previous one is returned, so the subtree should not change shape while iterating.
+#[derive(Debug)]
+pub struct ScopeDescendantsIterator {
+    root: RootScope,
+    // Arena index of the scope whose descendants are visited.
+    start: usize,
+    // Arena index of the scope to return next.
+    next: Option<usize>,
+}
+
+impl Iterator for ScopeDescendantsIterator {
+    type Item = Scope;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next?;
+        // Go to the first child, or else the next sibling of the nearest scope that has one.
+        let scopes = self.root.root_data.scopes.borrow();
+        let mut current = index;
+        self.next = scopes[index].first_child;
+        while self.next.is_none() && current != self.start {
+            self.next = scopes[current].next_sibling;
+            current = scopes[current].parent.expect("descendant without parent");
         }
+        drop(scopes);
+        Some(self.root.scope_at_index(index))
     }
 }
 
 #[derive(Debug)]
 pub struct AlreadyExists();
 
+#[derive(Debug)]
+pub struct InvalidOrder();
+
 impl Scope {
+    pub fn id(&self) -> ScopeId {
+        ScopeId(self.index)
+    }
+
+    /// The root of the tree that this scope is part of.
+    pub fn root(&self) -> &RootScope {
+        &self.root
+    }
+
+    /// The scope this one is a child of, or None for the root and for detached scopes.
+    pub fn parent(&self) -> Option<Scope> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.parent)
+            .map(|index| Scope {
+                root: self.root.clone(),
+                index,
+            })
+    }
+
     pub fn children(&self) -> ScopeChildrenIterator {
         ScopeChildrenIterator {
-            scope: self.clone(),
-            child_nr: 0,
+            root: self.root.clone(),
+            next: self
+                .root
+                .read_scope_data_at(self.index, |data| data.first_child),
+        }
+    }
+
+    /// All scopes below this one (not including itself), depth-first, parents before children.
+    pub fn descendants(&self) -> ScopeDescendantsIterator {
+        ScopeDescendantsIterator {
+            root: self.root.clone(),
+            start: self.index,
+            next: self
+                .root
+                .read_scope_data_at(self.index, |data| data.first_child),
+        }
+    }
+
+    /// The children as they are now, so that the tree can be restructured freely while
+    /// iterating, e.g. moving each child elsewhere.
+    pub fn into_children(self) -> IntoIter<Scope> {
+        self.children().collect::<Vec<_>>().into_iter()
+    }
+
+    /// The names as they are now, like `names`, so that they can be moved or removed while
+    /// iterating.
+    pub fn into_names(self) -> IntoIter<Name> {
+        self.names().into_iter()
+    }
+
+    /// The position of this scope among the children of its parent, or None for the root
+    /// and detached scopes. Children are kept in an explicit order, which only changes
+    /// through calls like `insert_child_at`, `reorder_children` or `reparent`, and which
+    /// is kept by `Scope::to_spec`, so emitted code can follow it.
+    pub fn child_position(&self) -> Option<usize> {
+        let parent = self
+            .root
+            .read_scope_data_at(self.index, |data| data.parent)?;
+        self.root
+            .child_indices(parent)
+            .iter()
+            .position(|&child| child == self.index)
+    }
+
+    /// The child after this one in the parent's children, if any.
+    pub fn next_sibling(&self) -> Option<Scope> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.next_sibling)
+            .map(|index| self.root.scope_at_index(index))
+    }
+
+    /// The child before this one in the parent's children, if any.
+    pub fn prev_sibling(&self) -> Option<Scope> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.prev_sibling)
+            .map(|index| self.root.scope_at_index(index))
+    }
+
+    /// The other children of the parent, in order. Empty for the root and detached scopes.
+    pub fn siblings(&self) -> impl Iterator<Item = Scope> {
+        let this = self.clone();
+        self.parent()
+            .into_iter()
+            .flat_map(|parent| parent.children())
+            .filter(move |sibling| *sibling != this)
+    }
+
+    /// The given names registered directly in this scope, in registration order.
+    pub fn given_names(&self) -> Vec<GivenName> {
+        let mut given_names: Vec<(usize, GivenName)> =
+            self.root.read_scope_names_at(self.index, |data| {
+                data.given_names
+                    .iter()
+                    .map(|(given, &index)| (index, given.clone()))
+                    .collect()
+            });
+        given_names.sort_unstable_by_key(|(index, _)| *index);
+        given_names.into_iter().map(|(_, given)| given).collect()
+    }
+
+    /// The anonymous names registered directly in this scope, in registration order.
+    pub fn anonymous_names(&self) -> Vec<AnonName> {
+        let indices = self
+            .root
+            .read_scope_names_at(self.index, |data| data.anon_names.clone());
+        let names = self.root.root_data.names.borrow();
+        indices
+            .into_iter()
+            .map(|index| match &names[index].data {
+                InputName::Anonymous(anon) => anon.clone(),
+                InputName::Given(_) => unreachable!("given name in list of anonymous names"),
+            })
+            .collect()
+    }
+
+    /// All names registered directly in this scope, given and anonymous, in registration order.
+    pub fn names(&self) -> Vec<Name> {
+        let mut indices = self.root.read_scope_names_at(self.index, |data| {
+            let mut indices: Vec<usize> = data.given_names.values().cloned().collect();
+            indices.extend(&data.anon_names);
+            indices
+        });
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            .map(|index| self.root.name_at(index))
+            .collect()
+    }
+
+    /// The anonymous names registered directly in this scope with the given prefix, in
+    /// ordinal order: registration order, except that names registered with an ordinal key
+    /// come after the others, by key. An empty prefix finds names registered without prefix.
+    pub fn anon_names_with_prefix(&self, prefix: &str) -> Vec<Name> {
+        let prefix = NameText::from(prefix);
+        let indices = self
+            .root
+            .read_scope_names_at(self.index, |data| data.anon_names.clone());
+        let mut names: Vec<Name> = indices
+            .into_iter()
+            .map(|index| self.root.name_at(index))
+            .filter(|name| match &name.data {
+                InputName::Anonymous(anon) => anon.name == prefix,
+                InputName::Given(_) => false,
+            })
+            .collect();
+        self.root.sort_by_ordinal_key(&mut names);
+        names
+    }
+
+    /// Like `anon_names_with_prefix`, but for this scope and all scopes below it, in
+    /// depth-first order.
+    pub fn subtree_anon_names_with_prefix(&self, prefix: &str) -> Vec<Name> {
+        let mut names = vec![];
+        let mut pending = vec![self.clone()];
+        while let Some(scope) = pending.pop() {
+            names.extend(scope.anon_names_with_prefix(prefix));
+            let mut children: Vec<Scope> = scope.children().collect();
+            children.reverse();
+            pending.extend(children);
         }
+        names
+    }
+
+    /// Every name declared in this scope or below it, with the scope that declares it.
+    /// Scopes are visited depth-first, parents before children, and names in each scope in
+    /// registration order.
+    pub fn subtree_names(&self) -> impl Iterator<Item = (Name, Scope)> {
+        once(self.clone())
+            .chain(self.descendants())
+            .flat_map(|scope| {
+                scope
+                    .names()
+                    .into_iter()
+                    .map(move |name| (name, scope.clone()))
+            })
     }
 
     /// Connect a child scope to this one.
     pub fn add_child(&self) -> Self {
+        let _exclusive = self.root.root_data.exclusive.lock();
         // During this method, the state is not consistent.
         // Step 1: add the new scope data to the root 'arena'.
-        let child_scope = {
-            self.root.add_scope(ScopeData {
-                parent: Some(self.index),
-                children: vec![],
-                given_names: HashSet::new(),
-                anon_names: vec![],
-            })
-        };
+        let child_scope = self.root.add_scope(ScopeData::new(Some(self.index)));
         // Step 2: register that this is a child.
-        self.root
-            .scope_data_at(self.index, |data| data.children.push(child_scope.index));
+        self.root.link_child(self.index, child_scope.index, None);
+        self.root.shape_changed();
+        self.root.mark_dirty(self.index);
+        child_scope
+    }
+
+    /// Connect a child scope with the given kind and label to this one. Like `add_child`
+    /// followed by `set_kind` and `set_label`, but the scope is never seen without them.
+    pub fn add_child_with(&self, kind: ScopeKind, label: &str) -> Self {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        // During this method, the state is not consistent.
+        // Step 1: add the new scope data to the root 'arena'.
+        let child_scope = self.root.add_scope(ScopeData {
+            kind,
+            label: Some(Ustr::from(label)),
+            ..ScopeData::new(Some(self.index))
+        });
+        // Step 2: register that this is a child.
+        self.root.link_child(self.index, child_scope.index, None);
+        self.root.shape_changed();
+        self.root.mark_dirty(self.index);
+        child_scope
+    }
+
+    /// Create a new child scope at the given position among the existing children.
+    ///
+    /// Panics if the position is larger than the number of children.
+    pub fn insert_child_at(&self, position: usize) -> Self {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        let children = self.root.child_indices(self.index);
+        let child_count = children.len();
+        assert!(
+            position <= child_count,
+            "child position {} out of bounds for {} children",
+            position,
+            child_count
+        );
+        // During this method, the state is not consistent.
+        // Step 1: add the new scope data to the root 'arena'.
+        let child_scope = self.root.add_scope(ScopeData::new(Some(self.index)));
+        // Step 2: register that this is a child, at the requested position.
+        self.root.link_child(
+            self.index,
+            child_scope.index,
+            children.get(position).cloned(),
+        );
+        self.root.shape_changed();
+        self.root.mark_dirty(self.index);
         child_scope
     }
 
-    /// Register a named identifier in this scope, failing if it is already registered.
+    /// Change the order of the children. The n-th element of `order` is the current
+    /// position of the child that should become the n-th child.
+    ///
+    /// Fails, without changing anything, if `order` is not a permutation of the positions.
+    pub fn reorder_children(&self, order: &[usize]) -> Result<(), InvalidOrder> {
+        let _exclusive = self.root.root_data.exclusive.lock();
+        let children = self.root.child_indices(self.index);
+        let mut seen = vec![false; children.len()];
+        if order.len() != seen.len() {
+            return Err(InvalidOrder());
+        }
+        for &position in order {
+            match seen.get_mut(position) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(InvalidOrder()),
+            }
+        }
+        // Moving each child to the end, in the new order, leaves them in that order.
+        for &position in order {
+            self.root.unlink_child(self.index, children[position]);
+            self.root.link_child(self.index, children[position], None);
+        }
+        self.root.shape_changed();
+        self.root.mark_dirty(self.index);
+        Ok(())
+    }
+
+    /// Find the nearest declaration of a given name, starting from this scope and moving
+    /// outwards. Overloaded names are not found this way, see `resolve_overloads`.
+    ///
+    /// In each scope, its own names are checked first, then imported names, and then those
+    /// of its mixins. Uses of deprecated names are recorded as diagnostics.
+    pub fn resolve(&self, name: &str) -> Option<Name> {
+        let found = self.resolve_silently(name);
+        if let Some(found) = &found {
+            self.root.note_use(found, self);
+        }
+        found
+    }
+
+    /// Like `resolve` for each of the names, but walking the ancestors only once, for passes
+    /// that resolve many identifiers in the same scope.
+    pub fn resolve_many(&self, names: &[&str]) -> Vec<Option<Name>> {
+        let given_names: Vec<GivenName> = names
+            .iter()
+            .map(|name| self.root.given_name(name))
+            .collect();
+        let mut found: Vec<Option<Name>> = vec![None; names.len()];
+        let mut unresolved = names.len();
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            if unresolved == 0 {
+                break;
+            }
+            for (given_name, slot) in given_names.iter().zip(&mut found) {
+                if slot.is_none() {
+                    *slot = scope.find_visible_given(given_name);
+                    if slot.is_some() {
+                        unresolved -= 1;
+                    }
+                }
+            }
+            current = scope.parent();
+        }
+        for name in found.iter().flatten() {
+            self.root.note_use(name, self);
+        }
+        found
+    }
+
+    /// Like `resolve`, but without recording diagnostics, for lookups that are not uses.
+    pub(crate) fn resolve_silently(&self, name: &str) -> Option<Name> {
+        let given_name = self.root.given_name(name);
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            if let Some(name) = scope.find_visible_given(&given_name) {
+                return Some(name);
+            }
+            current = scope.parent();
+        }
+        None
+    }
+
+    /// All declarations of a given name from this scope up to the root, nearest first. The
+    /// first one is what `resolve` returns, and it shadows all the others.
+    pub fn shadow_chain(&self, name: &str) -> Vec<Name> {
+        self.resolve_all(name).collect()
+    }
+
+    /// Like `shadow_chain`, but lazily, so that e.g. only the first few can be inspected
+    /// without looking further outwards.
+    pub fn resolve_all(&self, name: &str) -> impl Iterator<Item = Name> {
+        let given_name = self.root.given_name(name);
+        successors(Some(self.clone()), Scope::parent)
+            .filter_map(move |scope| scope.find_visible_given(&given_name))
+    }
+
+    /// Register a named identifier in this scope, failing if it is already registered
+    /// (unless the root merges duplicates, see `RootScope::set_merge_duplicates`). Panics
+    /// if a limit would be exceeded, see `try_add_named`.
     pub fn add_named(&self, name: &str) -> Result<Name, AlreadyExists> {
+        match self.add_given(GivenName::new(NameText::from(name))) {
+            Ok(name) => Ok(name),
+            Err(RegisterError::AlreadyExists) => Err(AlreadyExists()),
+            Err(RegisterError::LimitExceeded(exceeded)) => panic!("{}", exceeded),
+        }
+    }
+
+    /// Register a given name of any kind in this scope, after bringing its text in canonical
+    /// form and checking that it does not conflict (see `has_conflicting`) and that the
+    /// scope has room for it. All adders of given names go through here, so that the checks
+    /// and the registration happen under one lock. Merged duplicates (see
+    /// `RootScope::set_merge_duplicates`) do not count against the limits.
+    pub(crate) fn add_given(&self, given_name: GivenName) -> Result<Name, RegisterError> {
+        let _exclusive = self.root.root_data.exclusive.lock();
         // During this method, the state is not consistent.
         // Create the name instance.
         let given_name = GivenName {
-            name: Ustr::from(name),
+            name: NameText::from(self.root.canonical(given_name.as_str()).as_ref()),
+            ..given_name
         };
+        // Check that the name is new, and not already used for overloads.
+        if self.has_conflicting(&given_name) {
+            return self
+                .merge_duplicate(&given_name)
+                .ok_or(RegisterError::AlreadyExists);
+        }
+        self.check_name_limits()?;
         // Register this name on the scope.
-        let is_new = self.root.scope_data_at(self.index, |data| {
-            data.given_names.insert(given_name.clone())
-        });
-        // Return the name only if it was a new name.
-        if !is_new {
-            return Err(AlreadyExists());
+        Ok(self.register_any(given_name))
+    }
+
+    /// Whether registering the given name here would conflict with an existing name: the
+    /// same name, or, in case-insensitive scopes, one that differs only in case. Overloads
+    /// also conflict with the ordinary name of the same text, and the other way around.
+    pub(crate) fn has_conflicting(&self, given: &GivenName) -> bool {
+        if self.find_given(given).is_some() {
+            return true;
         }
-        Ok(Name {
-            scope: (*self).clone(),
-            data: InputName::Given(given_name),
+        if given.overload.is_some() {
+            let ordinary = GivenName {
+                overload: None,
+                ..given.clone()
+            };
+            return self.find_given(&ordinary).is_some();
+        }
+        let is_ordinary =
+            given.arity.is_none() && given.version.is_none() && given.namespace.is_none();
+        is_ordinary && self.has_overloads(&given.name)
+    }
+
+    // Whether the text has overloads here, ignoring case if this scope does.
+    fn has_overloads(&self, text: &NameText) -> bool {
+        let is_case_insensitive = self.is_case_insensitive();
+        self.root.read_scope_names_at(self.index, |data| {
+            data.overloads.contains_key(text)
+                || (is_case_insensitive
+                    && data
+                        .overloads
+                        .keys()
+                        .any(|other| other.as_str().to_lowercase() == text.as_str().to_lowercase()))
         })
     }
 
     /// Register an anonymous identifier with a prefix in this scope.
     pub fn add_prefixed(&self, prefix: &str) -> Name {
+        let _exclusive = self.root.root_data.exclusive.lock();
         // During this method, the state is not consistent.
         // Create the name instance.
         let anon_name = AnonName {
-            name: Ustr::from(prefix),
+            name: NameText::from(prefix),
+            root: self.root.root_data.id,
+            index: self.root.root_data.names.borrow().len(),
         };
         // Register this name on the scope.
+        let name = self
+            .root
+            .push_name(self.index, InputName::Anonymous(anon_name));
         self.root
-            .scope_data_at(self.index, |data| data.anon_names.push(anon_name.clone()));
-        // Wrap into Name and return.
-        Name {
-            scope: (*self).clone(),
-            data: InputName::Anonymous(anon_name),
-        }
+            .scope_names_at(self.index, |data| data.anon_names.push(name.index));
+        name
     }
 
-    /// Register an anonymous identifier without a prefix in this scope.
+    /// Register an anonymous identifier in this scope, with the default prefix of the scope
+    /// if it has one (see `Scope::set_anonymous_prefix`), or else without a prefix.
     pub fn add_anonymous(&self) -> Name {
-        self.add_prefixed("")
+        match self.anonymous_prefix() {
+            Some(prefix) => self.add_prefixed(prefix.as_str()),
+            None => self.add_prefixed(""),
+        }
+    }
+
+    /// Add a given name to the arena and register it in this scope. The caller should
+    /// have checked that it does not exist yet.
+    pub(crate) fn register_given(&self, given_name: GivenName) -> Name {
+        let name = self
+            .root
+            .push_name(self.index, InputName::Given(given_name.clone()));
+        self.root.scope_names_at(self.index, |data| {
+            data.given_names.insert(given_name, name.index)
+        });
+        name
+    }
+
+    /// Look up a given name registered directly in this scope, ignoring case if the scope
+    /// is case-insensitive.
+    pub(crate) fn find_given(&self, given_name: &GivenName) -> Option<Name> {
+        self.root
+            .read_scope_names_at(self.index, |data| data.given_names.get(given_name).cloned())
+            .or_else(|| self.find_given_ignoring_case(given_name))
+            .map(|index| self.root.name_at(index))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_trees::build_tree;
 
     //TODO @mark: test children
 
@@ -255,6 +1133,138 @@ mod tests {
         child2.add_child();
     }
 
+    #[test]
+    fn root_scope_roundtrip() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        assert_eq!(child.root().root_scope(), root);
+        assert_eq!(root.root().root_scope().parent(), None);
+    }
+
+    #[test]
+    fn lookup_by_id() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let name = child.add_named("hello").unwrap();
+        let anon = child.add_anonymous();
+        assert_eq!(root.root().scope(child.id()), Some(child.clone()));
+        assert_eq!(root.root().name(name.id()), Some(name.clone()));
+        assert_eq!(root.root().name(anon.id()).unwrap().id(), anon.id());
+        assert_eq!(root.root().scope(ScopeId(99)), None);
+        assert_eq!(root.root().name(NameId(99)), None);
+        assert_eq!(child.names().len(), 2);
+    }
+
+    #[test]
+    fn child_positions() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let last = root.add_child();
+        let middle = root.insert_child_at(1);
+        let front = root.insert_child_at(0);
+        let children: Vec<_> = root.children().collect();
+        assert_eq!(
+            children,
+            vec![front.clone(), first.clone(), middle.clone(), last.clone()]
+        );
+        assert_eq!(middle.parent(), Some(root.clone()));
+        root.reorder_children(&[3, 2, 1, 0]).unwrap();
+        let children: Vec<_> = root.children().collect();
+        assert_eq!(children, vec![last.clone(), middle, first, front]);
+        assert_eq!(last.child_position(), Some(0));
+        assert_eq!(root.child_position(), None);
+        root.reorder_children(&[0, 0, 1, 2]).unwrap_err();
+        root.reorder_children(&[0, 1, 2]).unwrap_err();
+        root.reorder_children(&[0, 1, 2, 4]).unwrap_err();
+    }
+
+    #[test]
+    fn child_with_kind_and_label() {
+        let root = RootScope::new_root();
+        let function = root.add_child_with(ScopeKind::Function, "main");
+        assert_eq!(function.kind(), ScopeKind::Function);
+        assert_eq!(function.label().unwrap().as_str(), "main");
+        assert_eq!(function.parent(), Some(root.clone()));
+        assert_eq!(root.children().collect::<Vec<_>>(), vec![function]);
+    }
+
+    #[test]
+    fn sibling_navigation() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let second = root.add_child();
+        let third = root.add_child();
+        assert_eq!(second.next_sibling(), Some(third.clone()));
+        assert_eq!(second.prev_sibling(), Some(first.clone()));
+        assert_eq!(first.prev_sibling(), None);
+        assert_eq!(third.next_sibling(), None);
+        assert_eq!(second.siblings().collect::<Vec<_>>(), vec![first, third]);
+        assert_eq!(root.siblings().count(), 0);
+        assert_eq!(root.next_sibling(), None);
+    }
+
+    #[test]
+    fn names_in_subtree() {
+        let root = RootScope::new_root();
+        let outside = root.add_named("outside").unwrap();
+        let function = root.add_child();
+        let x = function.add_named("x").unwrap();
+        let block = function.add_child();
+        let tmp = block.add_prefixed("tmp");
+        let y = function.add_named("y").unwrap();
+        let names: Vec<_> = function.subtree_names().collect();
+        assert_eq!(
+            names,
+            vec![(x, function.clone()), (y, function.clone()), (tmp, block)]
+        );
+        assert_eq!(root.subtree_names().next(), Some((outside, root.clone())));
+    }
+
+    #[test]
+    fn descendants_stay_in_subtree() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let nested = first.add_child();
+        let deeper = nested.add_child();
+        let after_nested = first.add_child();
+        let second = root.add_child();
+        second.add_child();
+        assert_eq!(
+            first.descendants().collect::<Vec<_>>(),
+            vec![nested.clone(), deeper, after_nested]
+        );
+        assert_eq!(nested.descendants().count(), 1);
+        assert_eq!(root.descendants().count(), 6);
+    }
+
+    #[test]
+    fn snapshot_is_independent() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let x = child.add_named("x").unwrap();
+        let snapshot = root.root().snapshot();
+        child.add_named("y").unwrap();
+        root.add_child();
+        let old_child = snapshot.scope(child.id()).unwrap();
+        assert_eq!(old_child.resolve("x").unwrap().id(), x.id());
+        assert!(old_child.resolve("y").is_none());
+        assert_eq!(snapshot.root_scope().children().count(), 1);
+        assert_ne!(old_child, child);
+        old_child.add_named("z").unwrap();
+        assert!(child.resolve("z").is_none());
+    }
+
+    #[test]
+    fn root_ids() {
+        let first = RootScope::new_root();
+        let second = RootScope::new_root();
+        assert_ne!(first.root().id(), second.root().id());
+        let id = RootId(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let loaded = RootScope::new_root_with_id(id);
+        assert_eq!(loaded.root().id(), id);
+        assert_ne!(first.root().snapshot().id(), first.root().id());
+    }
+
     #[test]
     fn add_named_unique() {
         let root = RootScope::new_root();
@@ -264,6 +1274,62 @@ mod tests {
         child1.add_named("nihao").unwrap();
     }
 
+    #[test]
+    fn resolve_shadowed() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("hello").unwrap();
+        let child = root.add_child();
+        let grandchild = child.add_child();
+        assert_eq!(grandchild.resolve("hello"), Some(outer));
+        let inner = child.add_named("hello").unwrap();
+        assert_eq!(grandchild.resolve("hello"), Some(inner));
+        assert_eq!(grandchild.resolve("bye"), None);
+        assert_eq!(root.add_child().resolve("hello").unwrap().scope(), root);
+    }
+
+    #[test]
+    fn shadow_chain_nearest_first() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let child = root.add_child();
+        let grandchild = child.add_child();
+        let inner = grandchild.add_named("x").unwrap();
+        assert_eq!(grandchild.add_child().shadow_chain("x"), vec![inner, outer]);
+        assert!(grandchild.shadow_chain("y").is_empty());
+    }
+
+    #[test]
+    fn resolve_all_lazily() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let child = root.add_child();
+        let grandchild = child.add_child();
+        let inner = grandchild.add_named("x").unwrap();
+        let mut all = grandchild.resolve_all("x");
+        assert_eq!(all.next(), Some(inner));
+        assert_eq!(all.next(), Some(outer));
+        assert_eq!(all.next(), None);
+        assert_eq!(child.resolve_all("y").count(), 0);
+    }
+
+    #[test]
+    fn anonymous_by_prefix() {
+        let root = RootScope::new_root();
+        root.add_prefixed("tmp");
+        root.add_prefixed("other");
+        root.add_anonymous();
+        let child = root.add_child();
+        child.add_prefixed("tmp");
+        child.add_child().add_prefixed("tmp");
+        root.add_child().add_prefixed("tmp");
+        assert_eq!(root.anon_names_with_prefix("tmp").len(), 1);
+        assert_eq!(root.anon_names_with_prefix("").len(), 1);
+        let subtree = root.subtree_anon_names_with_prefix("tmp");
+        assert_eq!(subtree.len(), 4);
+        assert_eq!(subtree[1].scope(), child);
+        assert_eq!(child.subtree_anon_names_with_prefix("tmp").len(), 2);
+    }
+
     #[test]
     fn add_named_duplicate() {
         let root = RootScope::new_root();
@@ -274,4 +1340,140 @@ mod tests {
         // This is a duplicate (in the same scope) and should fail:
         child1.add_named("hello").unwrap_err();
     }
+
+    #[test]
+    fn structurally_equal_rebuilt() {
+        let root1 = build_tree();
+        let root2 = build_tree();
+        assert_ne!(root1.root(), root2.root());
+        assert!(root1.root().structurally_equal(root2.root()));
+        assert!(root1.root().structurally_equal(root1.root()));
+    }
+
+    #[test]
+    fn structurally_equal_differences() {
+        let root1 = build_tree();
+        let root2 = build_tree();
+        root2.add_named("extra").unwrap();
+        assert!(!root1.root().structurally_equal(root2.root()));
+        let root3 = build_tree();
+        root3.children().next().unwrap().add_prefixed("tmp");
+        assert!(!root1.root().structurally_equal(root3.root()));
+        let root4 = build_tree();
+        root4.add_child();
+        assert!(!root1.root().structurally_equal(root4.root()));
+        let root5 = build_tree();
+        root5
+            .children()
+            .next()
+            .unwrap()
+            .set_kind(ScopeKind::Function);
+        assert!(!root1.root().structurally_equal(root5.root()));
+        let root6 = build_tree();
+        root6.children().next().unwrap().set_label("f");
+        assert!(!root1.root().structurally_equal(root6.root()));
+    }
+
+    #[test]
+    fn list_names() {
+        let root = RootScope::new_root();
+        root.add_named("hello").unwrap();
+        root.add_prefixed("tmp");
+        root.add_anonymous();
+        let child = root.add_child();
+        child.add_named("bye").unwrap();
+        let given = root.given_names();
+        assert_eq!(given.len(), 1);
+        assert_eq!(given[0].as_str(), "hello");
+        let anon = root.anonymous_names();
+        assert_eq!(anon.len(), 2);
+        assert_eq!(anon[0].prefix(), Some("tmp"));
+        assert_eq!(anon[1].prefix(), None);
+    }
+
+    #[test]
+    fn register_names_while_reading_structure() {
+        let root = RootScope::new_root();
+        let child = root.add_child();
+        let x = root.root().read_scope_data_at(child.index, |data| {
+            assert_eq!(data.parent, Some(0));
+            child.add_named("x").unwrap()
+        });
+        assert_eq!(child.resolve("x"), Some(x));
+    }
+
+    #[test]
+    fn resolve_batch() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let child = root.add_child();
+        let y = child.add_named("y").unwrap();
+        let inner_x = child.add_child().add_named("x").unwrap();
+        let grandchild = inner_x.scope();
+        assert_eq!(
+            grandchild.resolve_many(&["x", "missing", "y", "x"]),
+            vec![Some(inner_x.clone()), None, Some(y), Some(inner_x)]
+        );
+        assert_eq!(child.resolve_many(&["x"]), vec![Some(x)]);
+        assert!(child.resolve_many(&[]).is_empty());
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn register_from_threads() {
+        let root = RootScope::new_root();
+        let added: Vec<[bool; 4]> = ::std::thread::scope(|threads| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let root = root.clone();
+                    threads.spawn(move || {
+                        let unit = root.add_child();
+                        unit.add_named("x").unwrap();
+                        unit.add_prefixed("tmp");
+                        // Every kind of given name is checked and registered atomically.
+                        [
+                            root.add_named("global").is_ok(),
+                            root.add_with_arity("f", 1).is_ok(),
+                            root.namespace("css").add_named("main").is_ok(),
+                            root.declare("g").unwrap().define().is_ok(),
+                        ]
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        for kind in 0..4 {
+            assert_eq!(added.iter().filter(|added| added[kind]).count(), 1);
+        }
+        assert_eq!(root.children().count(), 8);
+        assert!(root
+            .children()
+            .all(|unit| unit.resolve("x").unwrap().scope() == unit));
+        assert_eq!(root.root().names_in_order(Default::default()).len(), 20);
+    }
+
+    #[test]
+    fn change_tree_while_iterating() {
+        let root = RootScope::new_root();
+        let old = root.add_child();
+        let new = root.add_child();
+        for _ in 0..3 {
+            old.add_child().add_named("x").unwrap();
+        }
+        for child in old.clone().into_children() {
+            child.reparent(&new).unwrap();
+        }
+        assert_eq!(old.children().count(), 0);
+        assert_eq!(new.children().count(), 3);
+        for child in new.children() {
+            for name in child.clone().into_names() {
+                child.remove_name(&name);
+                root.add_prefixed("removed");
+            }
+        }
+        assert_eq!(root.names().len(), 3);
+    }
 }
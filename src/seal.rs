@@ -0,0 +1,86 @@
+/// Sealing scopes, so that finished parts of the tree (e.g. a function that was fully
+/// parsed) can be handed to the emit phase, while the front-end keeps adding to the rest.
+///
+/// The names and children of a sealed scope cannot be added, removed or moved, and its
+/// imports and mixins cannot change; trying to do so panics, since it is a bug in the
+/// caller, except for `Scope::merge_sibling`, which reports it. Descriptive data like labels
+/// and spans can still be set. Sealing is permanent.
+use crate::scope::{RootScope, Scope};
+
+impl Scope {
+    /// Make the names and children of this scope immutable. Its descendants are not sealed,
+    /// see `seal_subtree`.
+    pub fn seal(&self) {
+        self.root
+            .scope_data_at(self.index, |data| data.sealed = true);
+    }
+
+    /// Seal this scope and all its descendants.
+    pub fn seal_subtree(&self) {
+        self.seal();
+        for descendant in self.descendants() {
+            descendant.seal();
+        }
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.root.read_scope_data_at(self.index, |data| data.sealed)
+    }
+}
+
+impl RootScope {
+    /// Panic if the names or children of the scope are about to change while it is sealed.
+    pub(crate) fn assert_unsealed(&self, index: usize) {
+        assert!(
+            !self.read_scope_data_at(index, |data| data.sealed),
+            "cannot change sealed scope {}",
+            index
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use crate::scope::{RootScope, ScopeId};
+
+    #[test]
+    fn sealed_scope_is_immutable() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        let x = function.add_named("x").unwrap();
+        let block = function.add_child();
+        function.seal();
+        assert!(function.is_sealed());
+        assert!(!block.is_sealed());
+        assert!(catch_unwind(AssertUnwindSafe(|| function.add_named("y"))).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| function.add_child())).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| function.remove_name(&x))).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| block.reparent(&root))).is_err());
+        // Failed changes leave nothing behind, in the sealed scope or elsewhere.
+        let outside = root.add_named("w").unwrap();
+        assert!(catch_unwind(AssertUnwindSafe(|| outside.move_to(&function))).is_err());
+        assert_eq!(outside.scope(), root);
+        assert!(!x.is_removed());
+        assert!(root.root().scope(ScopeId(3)).is_none());
+        assert_eq!(root.root().freeze().len(), 1 + root.descendants().count());
+        // The rest of the tree, including children of the sealed scope, can still change.
+        block.add_named("y").unwrap();
+        root.add_named("z").unwrap();
+        root.add_child();
+        assert_eq!(function.names(), vec![x]);
+        assert_eq!(function.children().count(), 1);
+    }
+
+    #[test]
+    fn seal_subtree() {
+        let root = RootScope::new_root();
+        let function = root.add_child();
+        let block = function.add_child();
+        function.seal_subtree();
+        assert!(block.is_sealed());
+        assert!(!root.is_sealed());
+        assert!(catch_unwind(AssertUnwindSafe(|| block.add_anonymous())).is_err());
+    }
+}
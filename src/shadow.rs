@@ -0,0 +1,148 @@
+/// Intentional shadowing, like `let x = x.trim();` in Rust, registered explicitly with a
+/// link to the outer name, so that it can be told apart from accidental reuse of a name.
+use ::std::iter::successors;
+
+use crate::name::{GivenName, InputName, Name};
+use crate::scope::{RootScope, Scope};
+use crate::text::NameText;
+
+/// A name registered with `Scope::add_shadowing`, together with the name it shadows.
+#[derive(Debug, Clone)]
+pub struct ShadowedName {
+    name: Name,
+    shadowed: Name,
+}
+
+#[derive(Debug)]
+pub enum NotShadowing {
+    /// No ancestor declares the name, so there is nothing to shadow.
+    NoOuterName,
+    /// The name is already registered in this scope itself.
+    AlreadyExists,
+}
+
+impl ShadowedName {
+    /// The new, inner name.
+    pub fn name(&self) -> Name {
+        self.name.clone()
+    }
+
+    /// The outer name that is shadowed.
+    pub fn shadowed(&self) -> Name {
+        self.shadowed.clone()
+    }
+}
+
+impl Scope {
+    /// Register a given name that intentionally shadows the nearest declaration of the same
+    /// name in an ancestor. Fails if there is no such declaration, or if the name is already
+    /// registered in this scope.
+    pub fn add_shadowing(&self, name: &str) -> Result<ShadowedName, NotShadowing> {
+        let shadowed = self
+            .parent()
+            .and_then(|parent| parent.resolve_silently(name))
+            .ok_or(NotShadowing::NoOuterName)?;
+        let name = self
+            .add_named(name)
+            .map_err(|_| NotShadowing::AlreadyExists)?;
+        self.root.root_data.names.borrow_mut()[name.index].shadows = Some(shadowed.index);
+        Ok(ShadowedName { name, shadowed })
+    }
+}
+
+impl Name {
+    /// The name this one was registered to shadow with `Scope::add_shadowing`, if any.
+    pub fn shadows(&self) -> Option<Name> {
+        let shadows = self.root.root_data.names.borrow()[self.index].shadows;
+        shadows.map(|index| self.root.name_at(index))
+    }
+
+    /// How many scope levels separate this name from the nearest outer declaration it
+    /// shadows, intentionally or not: 1 if the parent declares it. None if it does not shadow
+    /// anything, which is always the case for anonymous names.
+    pub fn shadow_depth(&self) -> Option<usize> {
+        let given = match &self.data {
+            InputName::Given(given) => GivenName::new(NameText::from(given.as_str())),
+            InputName::Anonymous(_) => return None,
+        };
+        successors(self.scope().parent(), Scope::parent)
+            .position(|scope| scope.find_visible_given(&given).is_some())
+            .map(|levels| levels + 1)
+    }
+}
+
+impl RootScope {
+    /// Given names that shadow a name of an ancestor without being registered to do so with
+    /// `Scope::add_shadowing`, each with the nearest name it shadows, e.g. to warn about.
+    pub fn accidental_shadowing(&self) -> Vec<(Name, Name)> {
+        let root = self.root_scope();
+        root.subtree_names()
+            .filter(|(name, _)| name.shadows().is_none())
+            .filter_map(|(name, scope)| {
+                let text = match &name.data {
+                    InputName::Given(given) => given.as_str().to_owned(),
+                    InputName::Anonymous(_) => return None,
+                };
+                let outer = scope.parent()?.resolve_silently(&text)?;
+                Some((name, outer))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    use super::NotShadowing;
+
+    #[test]
+    fn explicit_shadowing() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("line").unwrap();
+        let block = root.add_child();
+        let shadowing = block.add_shadowing("line").unwrap();
+        assert_eq!(shadowing.shadowed(), outer);
+        assert_eq!(shadowing.name().shadows(), Some(outer.clone()));
+        assert_eq!(block.resolve("line"), Some(shadowing.name()));
+        assert!(matches!(
+            block.add_shadowing("line"),
+            Err(NotShadowing::AlreadyExists)
+        ));
+        assert!(matches!(
+            block.add_shadowing("other"),
+            Err(NotShadowing::NoOuterName)
+        ));
+    }
+
+    #[test]
+    fn report_accidental_shadowing() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("count").unwrap();
+        let first = root.add_child();
+        first.add_shadowing("count").unwrap();
+        let second = root.add_child();
+        let accidental = second.add_named("count").unwrap();
+        second.add_named("unrelated").unwrap();
+        assert_eq!(
+            root.root().accidental_shadowing(),
+            vec![(accidental, outer)]
+        );
+    }
+
+    #[test]
+    fn shadow_depth() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let function = root.add_child();
+        let block = function.add_child().add_child();
+        let inner = block.add_named("x").unwrap();
+        let parameter = function.add_named("x").unwrap();
+        let other = block.add_named("y").unwrap();
+        assert_eq!(outer.shadow_depth(), None);
+        assert_eq!(parameter.shadow_depth(), Some(1));
+        assert_eq!(inner.shadow_depth(), Some(2));
+        assert_eq!(other.shadow_depth(), None);
+        assert_eq!(block.add_anonymous().shadow_depth(), None);
+    }
+}
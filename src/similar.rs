@@ -0,0 +1,147 @@
+/// Finding visible names that are similar to an unknown one, so that a front-end can report
+/// "did you mean `count`?" when resolving fails, or that complete a prefix, for IDEs.
+use ::std::collections::HashSet;
+use ::std::iter::successors;
+
+use crate::name::{InputName, Name};
+use crate::scope::Scope;
+
+impl Scope {
+    /// The visible given names within `max_distance` edits (insertions, deletions or
+    /// substitutions of characters) of the text, closest first, and nearer scopes first
+    /// among names at the same distance. Shadowed names are not included.
+    pub fn find_similar(&self, text: &str, max_distance: usize) -> Vec<Name> {
+        let text: Vec<char> = text.chars().collect();
+        let mut similar: Vec<(usize, Name)> = self
+            .visible_given_names()
+            .into_iter()
+            .filter_map(|name| {
+                let candidate: Vec<char> = given_text(&name).chars().collect();
+                let distance = edit_distance(&text, &candidate);
+                if distance <= max_distance {
+                    Some((distance, name))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        similar.sort_by_key(|(distance, _)| *distance);
+        similar.into_iter().map(|(_, name)| name).collect()
+    }
+
+    /// The visible given names that start with the prefix, nearest scope first, and by text
+    /// within each scope. Shadowed names are not included. For many completions on an
+    /// unchanging tree, `FrozenScopes::complete` is faster.
+    pub fn complete(&self, prefix: &str) -> Vec<Name> {
+        let mut completions: Vec<Name> = self
+            .visible_given_names()
+            .into_iter()
+            .filter(|name| given_text(name).starts_with(prefix))
+            .collect();
+        for scope_completions in
+            completions.chunk_by_mut(|first, second| first.scope() == second.scope())
+        {
+            scope_completions.sort_by(|first, second| given_text(first).cmp(given_text(second)));
+        }
+        completions
+    }
+
+    /// The ordinary given names declared in this scope and its ancestors that are not
+    /// shadowed, nearest scope first, and in registration order within each scope. Imported
+    /// names and those of mixins are not included.
+    pub(crate) fn visible_given_names(&self) -> Vec<Name> {
+        let mut seen = HashSet::new();
+        successors(Some(self.clone()), Scope::parent)
+            .flat_map(|scope| scope.names())
+            .filter(|name| match &name.data {
+                InputName::Given(given) => {
+                    given.namespace().is_none()
+                        && given.overload_key().is_none()
+                        && given.arity().is_none()
+                        && given.version().is_none()
+                }
+                InputName::Anonymous(_) => false,
+            })
+            .filter(|name| seen.insert(given_text(name).to_owned()))
+            .collect()
+    }
+}
+
+pub(crate) fn given_text(name: &Name) -> &str {
+    match &name.data {
+        InputName::Given(given) => given.as_str(),
+        InputName::Anonymous(_) => "",
+    }
+}
+
+/// The Levenshtein distance between two texts.
+fn edit_distance(first: &[char], second: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=second.len()).collect();
+    for (row, first_char) in first.iter().enumerate() {
+        let mut current = vec![row + 1];
+        for (column, second_char) in second.iter().enumerate() {
+            let substitution = previous[column] + usize::from(first_char != second_char);
+            let insertion = current[column] + 1;
+            let deletion = previous[column + 1] + 1;
+            current.push(substitution.min(insertion).min(deletion));
+        }
+        previous = current;
+    }
+    previous[second.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    use super::edit_distance;
+
+    #[test]
+    fn did_you_mean() {
+        let root = RootScope::new_root();
+        let count = root.add_named("count").unwrap();
+        root.add_named("amount").unwrap();
+        let child = root.add_child();
+        let counter = child.add_named("counter").unwrap();
+        let shadow = child.add_named("amount").unwrap();
+        child.add_prefixed("cont");
+        assert_eq!(child.find_similar("cont", 1), vec![count.clone()]);
+        assert_eq!(child.find_similar("countr", 1), vec![counter, count]);
+        assert_eq!(child.find_similar("amnt", 2), vec![shadow]);
+    }
+
+    #[test]
+    fn distances() {
+        let chars = |text: &str| text.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(edit_distance(&chars("same"), &chars("same")), 0);
+    }
+
+    #[test]
+    fn complete_prefix() {
+        let root = RootScope::new_root();
+        let counter = root.add_named("counter").unwrap();
+        let count = root.add_named("count").unwrap();
+        root.add_named("total").unwrap();
+        let child = root.add_child();
+        let inner_counter = child.add_named("counter").unwrap();
+        let country = child.add_named("country").unwrap();
+        assert_eq!(
+            child.complete("cou"),
+            vec![inner_counter.clone(), country.clone(), count.clone()]
+        );
+        assert_eq!(root.complete("cou"), vec![count.clone(), counter]);
+        let frozen = root.root().freeze();
+        let position = frozen.position(child.id()).unwrap();
+        let completions: Vec<_> = frozen
+            .complete(position, "cou")
+            .into_iter()
+            .map(|name| frozen.name_id(name))
+            .collect();
+        assert_eq!(
+            completions,
+            vec![inner_counter.id(), country.id(), count.id()]
+        );
+    }
+}
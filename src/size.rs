@@ -0,0 +1,73 @@
+/// Estimating the size of the generated code that is due to identifiers, so that strategies
+/// can be compared, e.g. for minifying, without emitting the code.
+///
+/// Each name is counted once for its declaration and once for every bound reference recorded
+/// with `Scope::reference`, so `RootScope::finalize` should be called first for accurate
+/// numbers. Lengths are in bytes of the UTF-8 output, including quotes.
+use ::std::collections::{BTreeMap, HashMap};
+
+use crate::generate::NameMap;
+
+impl NameMap {
+    /// The number of bytes of all declarations and uses of the generated outputs.
+    pub fn estimated_output_bytes(&self) -> usize {
+        let mut uses: HashMap<usize, usize> = HashMap::new();
+        for (_, binding) in self.root.reference_sites() {
+            if let Some(index) = binding {
+                *uses.entry(index).or_default() += 1;
+            }
+        }
+        self.iter()
+            .map(|(name, output)| {
+                output.as_str().len() * (1 + uses.get(&name.index).cloned().unwrap_or(0))
+            })
+            .sum()
+    }
+
+    /// The number of names per output length in bytes, for names that were not removed.
+    pub fn length_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for (_, output) in self.iter() {
+            *histogram.entry(output.as_str().len()).or_default() += 1;
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::std::collections::BTreeMap;
+
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+    use crate::strategy::Strategy;
+
+    #[test]
+    fn estimate_sizes() {
+        let root = RootScope::new_root();
+        root.add_named("counter").unwrap();
+        root.add_named("x").unwrap();
+        let function = root.add_child();
+        function.add_named("x").unwrap();
+        function.reference("counter");
+        function.reference("counter");
+        function.reference("x");
+        root.root().finalize().unwrap();
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.estimated_output_bytes(), 7 * 3 + 1 + 2 * 2);
+        assert_eq!(
+            map.length_histogram(),
+            BTreeMap::from([(1, 1), (2, 1), (7, 1)])
+        );
+        let minified = OutputPolicy {
+            strategy: Strategy::Minify,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&minified).unwrap();
+        assert_eq!(map.estimated_output_bytes(), 3 + 1 + 2);
+        assert_eq!(map.length_histogram(), BTreeMap::from([(1, 3)]));
+    }
+}
@@ -0,0 +1,70 @@
+/// Source ranges of scopes, so that e.g. a language server can find the scope at the cursor,
+/// and from there which names are visible.
+///
+/// Positions are plain offsets, typically bytes into the source file; the crate does not
+/// interpret them. The range of a scope is expected to contain the ranges of its children.
+use ::std::ops::Range;
+
+use crate::scope::{RootScope, Scope};
+
+impl Scope {
+    /// Record the source range that this scope covers.
+    pub fn set_span(&self, span: Range<usize>) {
+        self.root
+            .scope_data_at(self.index, |data| data.span = Some(span));
+    }
+
+    /// The source range of this scope, if it was set.
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.span.clone())
+    }
+}
+
+impl RootScope {
+    /// The innermost scope whose range contains the position, or None if no scope does.
+    ///
+    /// Scopes without a range are searched through, so their children can still be found.
+    pub fn scope_at(&self, position: usize) -> Option<Scope> {
+        self.innermost_at(0, position)
+            .map(|index| self.scope_at_index(index))
+    }
+
+    fn innermost_at(&self, index: usize, position: usize) -> Option<usize> {
+        let span = self.scope_data_at(index, |data| data.span.clone());
+        let is_covered = match span {
+            Some(span) if !span.contains(&position) => return None,
+            Some(_) => true,
+            None => false,
+        };
+        self.child_indices(index)
+            .into_iter()
+            .find_map(|child| self.innermost_at(child, position))
+            .or(if is_covered { Some(index) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn innermost_scope() {
+        let root = RootScope::new_root();
+        root.set_span(0..100);
+        let function = root.add_child();
+        function.set_span(10..50);
+        let block = function.add_child();
+        block.set_span(20..30);
+        // A scope without range, e.g. a synthesized one.
+        let synthetic = root.add_child();
+        let nested = synthetic.add_child();
+        nested.set_span(60..70);
+        assert_eq!(root.root().scope_at(25), Some(block));
+        assert_eq!(root.root().scope_at(40), Some(function.clone()));
+        assert_eq!(root.root().scope_at(65), Some(nested));
+        assert_eq!(root.root().scope_at(80), Some(root.clone()));
+        assert_eq!(root.root().scope_at(100), None);
+        assert_eq!(function.span(), Some(10..50));
+    }
+}
@@ -0,0 +1,201 @@
+/// Building scope trees from plain data, e.g. test fixtures or trees supplied by external
+/// tools. With the `serde` feature, the description can be read from JSON, TOML and so on:
+///
+/// ```json
+/// {"names": ["x", "y"], "anonymous": ["tmp", ""], "children": [{"names": ["x"]}]}
+/// ```
+///
+/// where anonymous entries are prefixes, and an empty prefix means no prefix.
+///
+/// A tree can be turned back into a spec with `Scope::to_spec`, keeping the order of the
+/// children, so that a round trip through serialization gives the same order.
+///
+/// Specs may come from untrusted tools, so building one never panics on limits: to bound
+/// the size, create a root with limits (see `RootScope::builder`) and use `Scope::add_spec`.
+#[cfg(feature = "serde")]
+use ::serde::{Deserialize, Serialize};
+
+use crate::kind::ScopeKind;
+use crate::limit::{LimitExceeded, RegisterError};
+use crate::scope::{RootScope, Scope};
+
+/// A description of a scope and, recursively, its children.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ScopeSpec {
+    pub kind: ScopeKind,
+    pub label: Option<String>,
+    pub debug_label: Option<String>,
+    /// Given names, in registration order.
+    pub names: Vec<String>,
+    /// Prefixes of anonymous names; empty for no prefix.
+    pub anonymous: Vec<String>,
+    pub children: Vec<ScopeSpec>,
+}
+
+/// A given name that would appear twice in the same scope, e.g. of a `ScopeSpec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateName(pub String);
+
+/// Why a tree could not be built from a `ScopeSpec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecError {
+    /// A given name appears twice in the same scope.
+    DuplicateName(DuplicateName),
+    /// The tree would exceed a limit, see `RootScope::set_limits`.
+    LimitExceeded(LimitExceeded),
+}
+
+impl From<LimitExceeded> for SpecError {
+    fn from(exceeded: LimitExceeded) -> Self {
+        SpecError::LimitExceeded(exceeded)
+    }
+}
+
+impl RootScope {
+    /// Create a new tree as described by the spec, which describes the root scope.
+    pub fn from_spec(spec: &ScopeSpec) -> Result<Scope, SpecError> {
+        let root = RootScope::new_root();
+        root.add_spec(spec)?;
+        Ok(root)
+    }
+}
+
+impl Scope {
+    /// Give this scope the kind and labels of the spec, and add its names and children.
+    /// On failure, what was added before the offending item is kept.
+    pub fn add_spec(&self, spec: &ScopeSpec) -> Result<(), SpecError> {
+        self.set_kind(spec.kind);
+        if let Some(label) = &spec.label {
+            self.set_label(label);
+        }
+        if let Some(debug_label) = &spec.debug_label {
+            self.set_debug_label(debug_label);
+        }
+        for name in &spec.names {
+            self.try_add_named(name).map_err(|error| match error {
+                RegisterError::AlreadyExists => {
+                    SpecError::DuplicateName(DuplicateName(name.clone()))
+                }
+                RegisterError::LimitExceeded(exceeded) => SpecError::LimitExceeded(exceeded),
+            })?;
+        }
+        for prefix in &spec.anonymous {
+            self.try_add_prefixed(prefix)?;
+        }
+        for child_spec in &spec.children {
+            self.try_add_child()?.add_spec(child_spec)?;
+        }
+        Ok(())
+    }
+
+    /// Describe this scope and its descendants, with children in order. Only what a spec
+    /// can describe is included: given names are described by their text, so overloads and
+    /// names in namespaces become ordinary names.
+    pub fn to_spec(&self) -> ScopeSpec {
+        ScopeSpec {
+            kind: self.kind(),
+            label: self.label().map(|label| label.to_string()),
+            debug_label: self.debug_label().map(|label| label.to_string()),
+            names: self
+                .given_names()
+                .iter()
+                .map(|given| given.as_str().to_owned())
+                .collect(),
+            anonymous: self
+                .anonymous_names()
+                .iter()
+                .map(|anon| anon.prefix().unwrap_or("").to_owned())
+                .collect(),
+            children: self.children().map(|child| child.to_spec()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kind::ScopeKind;
+    use crate::limit::{LimitExceeded, Limits};
+    use crate::scope::RootScope;
+    use crate::spec::{DuplicateName, ScopeSpec, SpecError};
+
+    #[test]
+    fn tree_from_spec() {
+        let spec = ScopeSpec {
+            names: vec!["x".to_owned(), "f".to_owned()],
+            children: vec![ScopeSpec {
+                kind: ScopeKind::Function,
+                label: Some("f".to_owned()),
+                names: vec!["x".to_owned()],
+                anonymous: vec!["tmp".to_owned(), "".to_owned()],
+                ..ScopeSpec::default()
+            }],
+            ..ScopeSpec::default()
+        };
+        let root = RootScope::from_spec(&spec).unwrap();
+        assert_eq!(root.names().len(), 2);
+        let function = root.children().next().unwrap();
+        assert_eq!(function.kind(), ScopeKind::Function);
+        assert_eq!(function.label().unwrap().as_str(), "f");
+        assert_eq!(function.resolve("x").unwrap().scope(), function);
+        assert_eq!(function.anonymous_names().len(), 2);
+        let duplicate = ScopeSpec {
+            names: vec!["x".to_owned(), "x".to_owned()],
+            ..ScopeSpec::default()
+        };
+        assert_eq!(
+            RootScope::from_spec(&duplicate).unwrap_err(),
+            SpecError::DuplicateName(DuplicateName("x".to_owned()))
+        );
+        let limited = RootScope::builder()
+            .limits(Limits {
+                max_depth: Some(0),
+                ..Limits::default()
+            })
+            .build();
+        assert_eq!(
+            limited.add_spec(&spec),
+            Err(SpecError::LimitExceeded(LimitExceeded::Depth(0)))
+        );
+        assert_eq!(limited.names().len(), 2);
+    }
+
+    #[test]
+    fn spec_round_trip_keeps_order() {
+        let root = RootScope::new_root();
+        root.add_named("x").unwrap();
+        for label in ["a", "b", "c"] {
+            root.add_child().set_label(label);
+        }
+        root.reorder_children(&[2, 0, 1]).unwrap();
+        root.insert_child_at(1).add_prefixed("tmp");
+        let spec = root.to_spec();
+        let copy = RootScope::from_spec(&spec).unwrap();
+        assert_eq!(copy.to_spec(), spec);
+        let labels: Vec<_> = copy
+            .children()
+            .map(|child| child.label().map(|label| label.to_string()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                Some("c".to_owned()),
+                None,
+                Some("a".to_owned()),
+                Some("b".to_owned())
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "serde", feature = "serde_json"))]
+    #[test]
+    fn spec_from_json() {
+        let json = r#"{"names": ["x"], "children": [{"kind": "Function", "anonymous": ["tmp"]}]}"#;
+        let spec: ScopeSpec = ::serde_json::from_str(json).unwrap();
+        let root = RootScope::from_spec(&spec).unwrap();
+        let function = root.children().next().unwrap();
+        assert_eq!(function.kind(), ScopeKind::Function);
+        assert_eq!(function.anonymous_names().len(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+/// Summary numbers about subtrees, e.g. for reporting or for balancing work between threads.
+///
+/// Memory usage is an estimate: it counts the scope and name records, and the capacity of
+/// their collections, but not allocator overhead or interned texts, which are shared.
+use ::std::mem::size_of;
+
+use crate::name::{GivenName, NameData};
+use crate::scope::{RootScope, Scope, ScopeData, ScopeNames};
+
+/// The result of `Scope::subtree_stats`. Counts include the scope itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SubtreeStats {
+    /// Number of scopes below this one, not counting itself.
+    pub descendants: usize,
+    /// Number of names, given and anonymous.
+    pub names: usize,
+    pub anonymous_names: usize,
+    /// The number of levels below this scope, so 0 for a scope without children.
+    pub max_depth: usize,
+}
+
+impl Scope {
+    /// Count the scopes and names in the subtree of this scope.
+    pub fn subtree_stats(&self) -> SubtreeStats {
+        let mut stats = SubtreeStats::default();
+        let mut pending = vec![(self.index, 0)];
+        while let Some((index, depth)) = pending.pop() {
+            self.root.scope_names_at(index, |data| {
+                stats.names += data.given_names.len() + data.anon_names.len();
+                stats.anonymous_names += data.anon_names.len();
+            });
+            pending.extend(
+                self.root
+                    .child_indices(index)
+                    .into_iter()
+                    .map(|child| (child, depth + 1)),
+            );
+            if depth > 0 {
+                stats.descendants += 1;
+            }
+            stats.max_depth = stats.max_depth.max(depth);
+        }
+        stats
+    }
+
+    /// The estimated number of bytes used by the scopes and names in the subtree of this scope.
+    pub fn memory_usage(&self) -> usize {
+        let mut bytes = 0;
+        let mut pending = vec![self.index];
+        while let Some(index) = pending.pop() {
+            bytes += self.root.read_scope_data_at(index, scope_data_memory);
+            bytes += self.root.read_scope_names_at(index, scope_names_memory);
+            pending.extend(self.root.child_indices(index));
+        }
+        bytes
+    }
+
+    /// The number of names that can be used from this scope: its own names, and those of
+    /// its ancestors that are not shadowed. This is how many distinct outputs are needed
+    /// here. See also `FrozenScopes::visible_name_count`, which is cached.
+    pub fn visible_name_count(&self) -> usize {
+        let own = self.root.scope_names_at(self.index, |data| {
+            data.given_names.len() + data.anon_names.len()
+        });
+        match self.parent() {
+            Some(parent) => own + parent.visible_name_count() - self.shadowed_count(),
+            None => own,
+        }
+    }
+
+    /// The number of given names in this scope that shadow a name of an ancestor.
+    pub(crate) fn shadowed_count(&self) -> usize {
+        let parent = match self.parent() {
+            Some(parent) => parent,
+            None => return 0,
+        };
+        self.given_names()
+            .iter()
+            .filter(|given_name| {
+                let mut current = Some(parent.clone());
+                while let Some(scope) = current {
+                    if scope.find_given(given_name).is_some() {
+                        return true;
+                    }
+                    current = scope.parent();
+                }
+                false
+            })
+            .count()
+    }
+}
+
+impl RootScope {
+    /// The estimated memory usage of each top-level child of the root, e.g. each module when
+    /// many modules are compiled into one tree, with the largest first.
+    pub fn memory_per_module(&self) -> Vec<(Scope, usize)> {
+        let mut modules: Vec<(Scope, usize)> = self
+            .root_scope()
+            .children()
+            .map(|module| {
+                let bytes = module.memory_usage();
+                (module, bytes)
+            })
+            .collect();
+        modules.sort_by_key(|(_, bytes)| ::std::cmp::Reverse(*bytes));
+        modules
+    }
+}
+
+fn scope_data_memory(data: &ScopeData) -> usize {
+    let links = data.mixins.capacity() + data.grants.capacity() + data.emit_before.capacity();
+    size_of::<ScopeData>() + links * size_of::<usize>()
+}
+
+fn scope_names_memory(data: &ScopeNames) -> usize {
+    let names = data.given_names.len() + data.anon_names.len();
+    let given_entry = size_of::<GivenName>() + size_of::<usize>();
+    size_of::<ScopeNames>()
+        + names * size_of::<NameData>()
+        + (data.given_names.capacity() + data.imports.capacity()) * given_entry
+        + data.undefined.capacity() * size_of::<GivenName>()
+        + data.anon_names.capacity() * size_of::<usize>()
+        + data
+            .overloads
+            .values()
+            .map(|keys| keys.capacity() * size_of::<usize>())
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn count_subtree() {
+        let root = RootScope::new_root();
+        root.add_named("a").unwrap();
+        let child = root.add_child();
+        child.add_named("b").unwrap();
+        child.add_anonymous();
+        child.add_child().add_child().add_prefixed("tmp");
+        assert_eq!(
+            root.subtree_stats(),
+            SubtreeStats {
+                descendants: 3,
+                names: 4,
+                anonymous_names: 2,
+                max_depth: 3,
+            }
+        );
+        assert_eq!(child.subtree_stats().max_depth, 2);
+        assert_eq!(
+            RootScope::new_root().subtree_stats(),
+            SubtreeStats::default()
+        );
+    }
+
+    #[test]
+    fn count_visible() {
+        let root = RootScope::new_root();
+        root.add_named("a").unwrap();
+        root.add_named("b").unwrap();
+        let child = root.add_child();
+        child.add_named("a").unwrap();
+        child.add_anonymous();
+        let grandchild = child.add_child();
+        grandchild.add_named("c").unwrap();
+        assert_eq!(root.visible_name_count(), 2);
+        assert_eq!(child.visible_name_count(), 3);
+        assert_eq!(grandchild.visible_name_count(), 4);
+        let frozen = root.root().freeze();
+        let position = frozen.position(grandchild.id()).unwrap();
+        assert_eq!(frozen.visible_name_count(position), 4);
+    }
+
+    #[test]
+    fn memory_per_module() {
+        let root = RootScope::new_root();
+        let small = root.add_child();
+        small.add_named("a").unwrap();
+        let large = root.add_child();
+        for index in 0..100 {
+            large.add_named(&format!("name{}", index)).unwrap();
+        }
+        large.add_child().add_anonymous();
+        let modules = root.root().memory_per_module();
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].0, large);
+        assert_eq!(modules[1].0, small);
+        assert!(modules[0].1 > modules[1].1);
+        assert!(root.memory_usage() > modules[0].1 + modules[1].1);
+    }
+}
@@ -0,0 +1,286 @@
+/// Naming strategies, which decide which outputs `RootScope::generate_names` tries for a
+/// name, and in which order; the first one that is free is used.
+///
+/// The default strategy bases outputs on the given name or prefix, which keeps generated
+/// code readable. Minifying ignores the text and picks the shortest free output instead,
+/// built from the alphabets of the policy, so that e.g. `$` can be used for JavaScript, or
+/// only letters for languages that do not allow digits in identifiers.
+///
+/// Preserving keeps given names as they are wherever possible, and is the most readable.
+///
+/// Compressing is like minifying, but prefers the outputs that were used most so far, which
+/// are often free again in sibling scopes. Repeating a few identifiers throughout the code,
+/// rather than spreading over the alphabet, makes it compress better with gzip or brotli.
+///
+/// Strategies can be combined, e.g. to preserve exported names, minify the rest and prefix
+/// temporaries: `Strategy::Preserve.filtered(is_exported).or_fallback(Strategy::Minify)`.
+/// Names that no part of a combined strategy applies to get descriptive outputs.
+use ::std::cmp::Reverse;
+
+use crate::generate::OutputPolicy;
+use crate::name::Name;
+
+/// How `RootScope::generate_names` picks outputs, see `OutputPolicy::strategy`.
+#[derive(Debug, Clone, Default)]
+pub enum Strategy {
+    /// Outputs resemble the given name or prefix, with a number appended on collisions.
+    #[default]
+    Descriptive,
+    /// The shortest free outputs: the characters of `OutputPolicy::start_alphabet`, then
+    /// those followed by a character of `OutputPolicy::alphabet`, etc., in alphabet order,
+    /// or heaviest first if `OutputPolicy::alphabet_weights` are set. Names in each scope
+    /// are assigned in order of their references, most first, see `RootScope::heat_map`.
+    Minify,
+    /// Like `Minify`, but first the outputs that were assigned most often so far, shortest
+    /// first for the same count. Lazy generation has no counts, and minifies instead.
+    Compress,
+    /// Given names keep their text unless it collides with the output of a name visible in
+    /// the same scope, or is a keyword or reserved; then a suffix is appended like for
+    /// `Descriptive`. These names get their outputs before all others, so outputs of other
+    /// names, e.g. anonymous ones in outer scopes, never force them to change, and derived
+    /// names keep their text even if their origin was renamed.
+    Preserve,
+    /// The first strategy for names it applies to, the second for the others.
+    Fallback(Box<Strategy>, Box<Strategy>),
+    /// The strategy only applies to names for which the predicate is true.
+    Filtered(Box<Strategy>, fn(&Name) -> bool),
+    /// The outputs of the strategy with the prefix in front.
+    Prefixed(Box<Strategy>, String),
+}
+
+impl Strategy {
+    /// Use this strategy for the names it applies to, and the fallback for the others.
+    pub fn or_fallback(self, fallback: Strategy) -> Self {
+        Strategy::Fallback(Box::new(self), Box::new(fallback))
+    }
+
+    /// Only use this strategy for names for which the predicate is true.
+    pub fn filtered(self, predicate: fn(&Name) -> bool) -> Self {
+        Strategy::Filtered(Box::new(self), predicate)
+    }
+
+    /// Put the prefix in front of the outputs of this strategy.
+    pub fn with_prefix(self, prefix: &str) -> Self {
+        Strategy::Prefixed(Box::new(self), prefix.to_owned())
+    }
+
+    /// The strategy without combinators that applies to the name, and the prefix to put in
+    /// front of its outputs.
+    pub(crate) fn for_name(&self, name: &Name) -> (&Strategy, String) {
+        self.applied(name)
+            .unwrap_or((&Strategy::Descriptive, String::new()))
+    }
+
+    fn applied(&self, name: &Name) -> Option<(&Strategy, String)> {
+        match self {
+            Strategy::Fallback(first, second) => {
+                first.applied(name).or_else(|| second.applied(name))
+            }
+            Strategy::Filtered(strategy, predicate) => match predicate(name) {
+                true => strategy.applied(name),
+                false => None,
+            },
+            Strategy::Prefixed(strategy, prefix) => strategy
+                .applied(name)
+                .map(|(strategy, rest)| (strategy, format!("{}{}", prefix, rest))),
+            _ => Some((self, String::new())),
+        }
+    }
+
+    /// The outputs to try, in order, for a name whose sanitized text or prefix is `base`,
+    /// for strategies without combinators, see `for_name`.
+    pub(crate) fn candidates<'a>(
+        &self,
+        base: &'a str,
+        policy: &OutputPolicy,
+    ) -> Box<dyn Iterator<Item = String> + 'a> {
+        match self {
+            Strategy::Descriptive | Strategy::Preserve => policy.suffix.candidates(base),
+            Strategy::Minify | Strategy::Compress => {
+                let weighted = |alphabet: &str| {
+                    let mut chars: Vec<char> = alphabet.chars().collect();
+                    chars.sort_by_key(|c| Reverse(policy.alphabet_weights.get(c).cloned()));
+                    chars.into_iter().collect::<String>()
+                };
+                Box::new(minified(
+                    &weighted(&policy.start_alphabet),
+                    &weighted(&policy.alphabet),
+                ))
+            }
+            Strategy::Fallback(..) | Strategy::Filtered(..) | Strategy::Prefixed(..) => {
+                unreachable!("combined strategies have no candidates of their own")
+            }
+        }
+    }
+}
+
+// All texts that start with a character of `start`, followed by characters of `rest`,
+// shortest first, and in alphabet order for the same length.
+pub(crate) fn minified(start: &str, rest: &str) -> impl Iterator<Item = String> {
+    let start: Vec<char> = start.chars().collect();
+    let rest: Vec<char> = rest.chars().collect();
+    let count = {
+        let (start_count, rest_count) = (start.len(), rest.len());
+        move |length: u32| {
+            rest_count
+                .checked_pow(length - 1)
+                .and_then(|count| count.checked_mul(start_count))
+                .filter(|&count| count > 0)
+        }
+    };
+    (1..)
+        .map_while(move |length| Some((length, count(length)?)))
+        .flat_map(move |(length, count)| {
+            let (start, rest) = (start.clone(), rest.clone());
+            (0..count).map(move |mut number| {
+                let mut text = vec![];
+                for _ in 1..length {
+                    text.push(rest[number % rest.len()]);
+                    number /= rest.len();
+                }
+                text.push(start[number]);
+                text.into_iter().rev().collect()
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::explain::RenameReason;
+    use crate::generate::OutputPolicy;
+    use crate::name::InputName;
+    use crate::scope::RootScope;
+    use crate::strategy::{minified, Strategy};
+
+    #[test]
+    fn minify_with_alphabet() {
+        let root = RootScope::new_root();
+        let first = root.add_named("first").unwrap();
+        let second = root.add_prefixed("tmp");
+        let child = root.add_child();
+        let inner = child.add_named("inner").unwrap();
+        let sibling = root.add_child().add_named("other").unwrap();
+        let policy = OutputPolicy {
+            strategy: Strategy::Minify,
+            start_alphabet: "$a".to_owned(),
+            alphabet: "$a1".to_owned(),
+            allowed: |c| c.is_ascii_alphanumeric() || c == '$' || c == '_',
+            ..OutputPolicy::default()
+        }
+        .with_keywords(&["a"]);
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&first).unwrap().as_str(), "$");
+        assert_eq!(map.get(&second).unwrap().as_str(), "$$");
+        assert_eq!(map.get(&inner).unwrap().as_str(), "$a");
+        assert_eq!(map.get(&sibling).unwrap().as_str(), "$a");
+        assert_eq!(
+            minified("ab", "ab1").take(9).collect::<Vec<_>>(),
+            vec!["a", "b", "aa", "ab", "a1", "ba", "bb", "b1", "aaa"]
+        );
+        assert_eq!(minified("", "ab").next(), None);
+        assert_eq!(minified("ab", "").count(), 2);
+    }
+
+    #[test]
+    fn weighted_alphabet() {
+        let root = RootScope::new_root();
+        let rare = root.add_named("rare").unwrap();
+        let common = root.add_named("common").unwrap();
+        let child = root.add_child();
+        child.reference("common");
+        child.reference("common");
+        child.reference("rare");
+        root.root().finalize().unwrap();
+        let policy = OutputPolicy {
+            strategy: Strategy::Minify,
+            alphabet_weights: [('e', 2), ('t', 1)].iter().cloned().collect(),
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&common).unwrap().as_str(), "e");
+        assert_eq!(map.get(&rare).unwrap().as_str(), "t");
+    }
+
+    #[test]
+    fn preserve_given_names() {
+        let root = RootScope::new_root();
+        let tmp = root.add_prefixed("x");
+        let outer = root.add_named("y").unwrap();
+        let function = root.add_child();
+        let x = function.add_named("x").unwrap();
+        let y = function.add_named("y").unwrap();
+        let getter = function.add_named("get_y").unwrap();
+        getter.derive_from(&y, "getter");
+        let descriptive = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(descriptive.get(&x).unwrap().as_str(), "x2");
+        assert_eq!(descriptive.get(&getter).unwrap().as_str(), "get_y2");
+        let policy = OutputPolicy {
+            strategy: Strategy::Preserve,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&x).unwrap().as_str(), "x");
+        assert_eq!(map.get(&tmp).unwrap().as_str(), "x2");
+        assert_eq!(map.get(&outer).unwrap().as_str(), "y");
+        assert_eq!(map.get(&y).unwrap().as_str(), "y2");
+        assert_eq!(map.get(&getter).unwrap().as_str(), "get_y");
+        assert_eq!(map.verify(), vec![]);
+    }
+
+    #[test]
+    fn combined_strategies() {
+        let root = RootScope::new_root();
+        let exported = root.add_named("exported").unwrap();
+        let function = root.add_child();
+        let local = function.add_named("local").unwrap();
+        let tmp = function.add_prefixed("tmp");
+        let strategy = Strategy::Preserve
+            .filtered(|name| name.scope().parent().is_none())
+            .or_fallback(
+                Strategy::Minify
+                    .with_prefix("t_")
+                    .filtered(|name| matches!(name.data, InputName::Anonymous(_))),
+            )
+            .or_fallback(Strategy::Minify);
+        let policy = OutputPolicy {
+            strategy,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&exported).unwrap().as_str(), "exported");
+        assert_eq!(map.get(&local).unwrap().as_str(), "a");
+        assert_eq!(map.get(&tmp).unwrap().as_str(), "t_a");
+        let prefixed = OutputPolicy {
+            strategy: Strategy::Descriptive.with_prefix("p_"),
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&prefixed).unwrap();
+        assert_eq!(map.get(&local).unwrap().as_str(), "p_local");
+        assert_eq!(map.renames()[0].reasons, vec![RenameReason::Strategy]);
+    }
+
+    #[test]
+    fn compress_reuses_outputs() {
+        let root = RootScope::new_root();
+        root.add_named("first").unwrap();
+        let module = root.add_child();
+        module.add_named("second").unwrap();
+        module.add_child().add_named("third").unwrap();
+        module.add_child().add_named("fourth").unwrap();
+        let other = root.add_child().add_named("fifth").unwrap();
+        let mut policy = OutputPolicy {
+            strategy: Strategy::Minify,
+            ..OutputPolicy::default()
+        };
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&other).unwrap().as_str(), "b");
+        // The output of the third and fourth name is used twice, that of the second once.
+        policy.strategy = Strategy::Compress;
+        let map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.get(&other).unwrap().as_str(), "c");
+        assert_eq!(map.length_histogram().get(&1), Some(&5));
+    }
+}
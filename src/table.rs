@@ -0,0 +1,123 @@
+/// Rename tables, with a row per name that has an output: the path of its scope, its text
+/// (see `Name::local_text`), its output and the reasons they differ (see `NameMap::renames`),
+/// so that build pipelines can archive and diff them with ordinary text tools.
+///
+/// Rows are in registration order, after a header row. CSV fields are quoted where needed,
+/// as in RFC 4180. TSV fields escape tabs, line breaks and backslashes as `\t`, `\n`, `\r`
+/// and `\\`.
+use ::std::collections::HashMap;
+use ::std::fmt;
+use ::std::io::{self, Write};
+
+use crate::explain::RenameReason;
+use crate::generate::NameMap;
+
+const HEADER: [&str; 4] = ["scope", "name", "output", "reason"];
+
+impl NameMap {
+    pub fn write_csv(&self, writer: impl Write) -> io::Result<()> {
+        self.write_table(writer, ',', |field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_owned()
+            }
+        })
+    }
+
+    pub fn write_tsv(&self, writer: impl Write) -> io::Result<()> {
+        self.write_table(writer, '\t', |field| {
+            field
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+        })
+    }
+
+    fn write_table(
+        &self,
+        mut writer: impl Write,
+        separator: char,
+        escape: impl Fn(&str) -> String,
+    ) -> io::Result<()> {
+        let reasons: HashMap<usize, String> = self
+            .renames()
+            .into_iter()
+            .map(|renamed| {
+                let reasons: Vec<String> = renamed
+                    .reasons
+                    .iter()
+                    .map(RenameReason::to_string)
+                    .collect();
+                (renamed.name.index, reasons.join("; "))
+            })
+            .collect();
+        let mut write_row = |fields: [&str; 4]| {
+            let fields: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+            writeln!(writer, "{}", fields.join(&separator.to_string()))
+        };
+        write_row(HEADER)?;
+        for (name, output) in self.iter() {
+            let reason = reasons.get(&name.index).map_or("", String::as_str);
+            write_row([
+                &name.scope().path_text(),
+                &name.local_text(),
+                output.as_str(),
+                reason,
+            ])?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RenameReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameReason::Derived(origin) => write!(f, "derived from {}", origin),
+            RenameReason::Sanitized => f.write_str("sanitized"),
+            RenameReason::Strategy => f.write_str("strategy"),
+            RenameReason::Formatted => f.write_str("formatted"),
+            RenameReason::Shortened => f.write_str("shortened"),
+            RenameReason::Keyword => f.write_str("keyword"),
+            RenameReason::Reserved => f.write_str("reserved"),
+            RenameReason::Conflict(other) => write!(f, "conflicts with {}", other),
+            RenameReason::Elsewhere => f.write_str("taken elsewhere"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+
+    #[test]
+    fn write_rename_tables() {
+        let root = RootScope::new_root();
+        root.add_named("x").unwrap();
+        let function = root.add_child();
+        function.set_label("main");
+        function.add_named("x").unwrap();
+        function.add_named("a,b").unwrap();
+        function.add_prefixed("tmp");
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        let mut csv = vec![];
+        map.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "scope,name,output,reason\n\
+             <root>,x,x,\n\
+             main,x,x2,conflicts with x\n\
+             main,\"a,b\",a_b,sanitized\n\
+             main,tmp#0,tmp,\n"
+        );
+        let mut tsv = vec![];
+        map.write_tsv(&mut tsv).unwrap();
+        let tsv = String::from_utf8(tsv).unwrap();
+        assert_eq!(tsv.lines().nth(2), Some("main\tx\tx2\tconflicts with x"));
+    }
+}
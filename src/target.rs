@@ -0,0 +1,74 @@
+/// Emit targets: which output a scope is written to, like "header" or "source", or a file
+/// name, for generators that split one tree over several files. Since names are generated
+/// for the whole tree at once, they stay consistent across the files.
+///
+/// A target applies to the scope and its descendants, unless a descendant sets its own.
+use ::std::collections::BTreeMap;
+use ::std::iter::once;
+
+use ::ustr::Ustr;
+
+use crate::scope::{RootScope, Scope};
+
+impl Scope {
+    /// Emit this scope and its descendants to the target, unless they set their own.
+    pub fn set_target(&self, target: &str) {
+        self.root
+            .scope_data_at(self.index, |data| data.target = Some(Ustr::from(target)));
+    }
+
+    /// The target of this scope, set on it or inherited from the nearest ancestor that
+    /// has one, or None if neither has.
+    pub fn target(&self) -> Option<Ustr> {
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            if let Some(target) = scope
+                .root
+                .read_scope_data_at(scope.index, |data| data.target)
+            {
+                return Some(target);
+            }
+            current = scope.parent();
+        }
+        None
+    }
+}
+
+impl RootScope {
+    /// The attached scopes grouped by target, sorted by target, with the scopes in each group
+    /// in preorder. Scopes without target are grouped under None, which comes first.
+    pub fn scopes_by_target(&self) -> BTreeMap<Option<String>, Vec<Scope>> {
+        let root = self.root_scope();
+        let mut groups: BTreeMap<Option<String>, Vec<Scope>> = BTreeMap::new();
+        for scope in once(root.clone()).chain(root.descendants()) {
+            let target = scope.target().map(|target| target.as_str().to_owned());
+            groups.entry(target).or_default().push(scope);
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn group_by_target() {
+        let root = RootScope::new_root();
+        let types = root.add_child();
+        types.set_target("header");
+        let nested = types.add_child();
+        let inline = nested.add_child();
+        inline.set_target("source");
+        let function = root.add_child();
+        function.set_target("source");
+        assert_eq!(nested.target().unwrap().as_str(), "header");
+        assert_eq!(root.target(), None);
+        let groups = root.root().scopes_by_target();
+        let keys: Vec<Option<&str>> = groups.keys().map(|key| key.as_deref()).collect();
+        assert_eq!(keys, vec![None, Some("header"), Some("source")]);
+        assert_eq!(groups[&None], vec![root.clone()]);
+        assert_eq!(groups[&Some("header".to_owned())], vec![types, nested]);
+        assert_eq!(groups[&Some("source".to_owned())], vec![inline, function]);
+    }
+}
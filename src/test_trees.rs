@@ -0,0 +1,16 @@
+/// Trees shared by the tests of several modules.
+use crate::scope::{RootScope, Scope};
+
+/// A root with given and prefixed names, a child with a given and an anonymous name, and
+/// an empty child.
+pub(crate) fn build_tree() -> Scope {
+    let root = RootScope::new_root();
+    root.add_named("hello").unwrap();
+    root.add_named("world").unwrap();
+    root.add_prefixed("tmp");
+    let child = root.add_child();
+    child.add_named("bye").unwrap();
+    child.add_anonymous();
+    root.add_child();
+    root
+}
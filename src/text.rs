@@ -0,0 +1,91 @@
+/// The text of given names. Most identifiers are short, so those are stored inline, which
+/// avoids a lookup in the global interner when resolving, and a pointer to follow when
+/// comparing. Longer names are interned as before, or, with the `owned-names` feature,
+/// stored in their own allocation, so that they are freed with the tree instead of staying
+/// in the global interner until the process exits.
+///
+/// Each text has exactly one representation, so the derived equality and hashing are
+/// consistent with comparing the strings.
+use ::std::fmt;
+use ::std::str;
+
+#[cfg(not(feature = "owned-names"))]
+use ::ustr::Ustr;
+
+/// Texts up to this many bytes are stored inline.
+const INLINE_CAPACITY: usize = 22;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) enum NameText {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    #[cfg(not(feature = "owned-names"))]
+    Interned(Ustr),
+    #[cfg(feature = "owned-names")]
+    Owned(Box<str>),
+}
+
+impl NameText {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            NameText::Inline { len, bytes } => {
+                str::from_utf8(&bytes[..*len as usize]).expect("inline text is not utf8")
+            }
+            #[cfg(not(feature = "owned-names"))]
+            NameText::Interned(text) => text.as_str(),
+            #[cfg(feature = "owned-names")]
+            NameText::Owned(text) => text,
+        }
+    }
+}
+
+impl From<&str> for NameText {
+    fn from(text: &str) -> Self {
+        if text.len() > INLINE_CAPACITY {
+            #[cfg(not(feature = "owned-names"))]
+            return NameText::Interned(Ustr::from(text));
+            #[cfg(feature = "owned-names")]
+            return NameText::Owned(text.into());
+        }
+        let mut bytes = [0; INLINE_CAPACITY];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        NameText::Inline {
+            len: text.len() as u8,
+            bytes,
+        }
+    }
+}
+
+impl fmt::Debug for NameText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for NameText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NameText;
+
+    #[test]
+    fn short_and_long_texts() {
+        let short = NameText::from("counter");
+        let long = NameText::from("a_rather_long_identifier_name");
+        assert!(matches!(short, NameText::Inline { .. }));
+        #[cfg(not(feature = "owned-names"))]
+        assert!(matches!(long, NameText::Interned(_)));
+        #[cfg(feature = "owned-names")]
+        assert!(matches!(long, NameText::Owned(_)));
+        assert_eq!(short.as_str(), "counter");
+        assert_eq!(long.as_str(), "a_rather_long_identifier_name");
+        assert_eq!(short, NameText::from("counter"));
+        assert_ne!(short, NameText::from("count"));
+    }
+}
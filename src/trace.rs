@@ -0,0 +1,170 @@
+/// Tracing name resolution, to debug why an identifier bound to an unexpected declaration:
+/// the trace lists every scope that was searched, in order, and what was found there.
+///
+/// Tracing is opt-in, by calling `Scope::resolve_traced` instead of `Scope::resolve`. It
+/// follows the same rules and finds the same declaration, but does not record diagnostics.
+use ::std::collections::HashSet;
+use ::std::fmt;
+
+use crate::name::{GivenName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+/// Every step of a resolution, see `Scope::resolve_traced`.
+#[derive(Debug, Clone)]
+pub struct ResolutionTrace {
+    /// The name that was looked up, after canonicalization.
+    pub text: String,
+    pub steps: Vec<TraceStep>,
+    /// The declaration that was found, if any; the same as that of the last step.
+    pub found: Option<Name>,
+}
+
+/// One scope that was searched during resolution.
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    pub scope: Scope,
+    /// The scope that the searched scope is a mixin of, or None for the scope where
+    /// resolution started and its ancestors.
+    pub mixin_of: Option<Scope>,
+    pub outcome: StepOutcome,
+}
+
+/// What was found in a scope during resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The scope declares the name; resolution stops here.
+    Declared(Name),
+    /// The scope imports the name; resolution stops here.
+    Imported(Name),
+    /// The scope declares overloads of the name, which plain resolution skips.
+    OnlyOverloads(Vec<Name>),
+    NotDeclared,
+}
+
+impl Scope {
+    /// Like `resolve`, but return a trace of the scopes that were searched. Does not record
+    /// diagnostics.
+    pub fn resolve_traced(&self, name: &str) -> ResolutionTrace {
+        let given_name = self.root.given_name(name);
+        let text = given_name.as_str().to_owned();
+        let mut steps = vec![];
+        let mut found = None;
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            found = trace_visible_given(&scope, &given_name, &mut steps);
+            if found.is_some() {
+                break;
+            }
+            current = scope.parent();
+        }
+        ResolutionTrace { text, steps, found }
+    }
+}
+
+// Search a scope and its mixins like `Scope::find_visible_given`, recording each step.
+fn trace_visible_given(
+    start: &Scope,
+    given_name: &GivenName,
+    steps: &mut Vec<TraceStep>,
+) -> Option<Name> {
+    let mut visited = HashSet::new();
+    let mut pending = vec![(start.clone(), None)];
+    while let Some((scope, mixin_of)) = pending.pop() {
+        if !visited.insert(scope.index) {
+            continue;
+        }
+        let outcome = if let Some(name) = scope.find_given(given_name) {
+            StepOutcome::Declared(name)
+        } else if let Some(name) = scope.find_imported(given_name) {
+            StepOutcome::Imported(name)
+        } else {
+            let overloads = scope.overloads(given_name.as_str());
+            if overloads.is_empty() {
+                StepOutcome::NotDeclared
+            } else {
+                StepOutcome::OnlyOverloads(overloads)
+            }
+        };
+        let found = match &outcome {
+            StepOutcome::Declared(name) | StepOutcome::Imported(name) => Some(name.clone()),
+            _ => None,
+        };
+        steps.push(TraceStep {
+            scope: scope.clone(),
+            mixin_of,
+            outcome,
+        });
+        if found.is_some() {
+            return found;
+        }
+        let mut mixins = scope.mixins();
+        mixins.reverse();
+        pending.extend(mixins.into_iter().map(|mixin| (mixin, Some(scope.clone()))));
+    }
+    None
+}
+
+impl fmt::Display for ResolutionTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "resolving `{}`", self.text)?;
+        for step in &self.steps {
+            write!(f, "  {}", step.scope.path_text())?;
+            if let Some(mixin_of) = &step.mixin_of {
+                write!(f, " (mixin of {})", mixin_of.path_text())?;
+            }
+            match &step.outcome {
+                StepOutcome::Declared(name) => writeln!(f, ": declared as {}", name)?,
+                StepOutcome::Imported(name) => writeln!(f, ": imported from {}", name)?,
+                StepOutcome::OnlyOverloads(overloads) => {
+                    writeln!(f, ": only {} overloads, skipped", overloads.len())?
+                }
+                StepOutcome::NotDeclared => writeln!(f, ": not declared")?,
+            }
+        }
+        match &self.found {
+            Some(name) => writeln!(f, "found {}", name),
+            None => writeln!(f, "not found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+    use crate::trace::StepOutcome;
+
+    #[test]
+    fn trace_through_mixins() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("x").unwrap();
+        let base = root.add_child();
+        base.set_label("Base");
+        let inherited = base.add_named("x").unwrap();
+        let class = root.add_child();
+        class.set_label("Class");
+        class.add_mixin(&base).unwrap();
+        let method = class.add_child();
+        method.set_label("method");
+        let trace = method.resolve_traced("x");
+        assert_eq!(trace.found, Some(inherited.clone()));
+        assert_eq!(method.resolve("x"), trace.found);
+        assert_eq!(trace.steps.len(), 3);
+        assert_eq!(trace.steps[2].mixin_of, Some(class.clone()));
+        assert_eq!(trace.steps[2].outcome, StepOutcome::Declared(inherited));
+        assert_eq!(
+            trace.to_string(),
+            concat!(
+                "resolving `x`\n",
+                "  Class::method: not declared\n",
+                "  Class: not declared\n",
+                "  Base (mixin of Class): declared as Base::x\n",
+                "found Base::x\n",
+            )
+        );
+        let unrelated = root.add_child().resolve_traced("x");
+        assert_eq!(unrelated.found, Some(outer));
+        assert_eq!(unrelated.steps.len(), 2);
+        assert!(root.resolve_traced("y").found.is_none());
+    }
+}
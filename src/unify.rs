@@ -0,0 +1,206 @@
+/// Unified names: registrations that must get the same generated output, like a parameter
+/// and the argument slot it aliases. Unification is transitive, so it groups names into
+/// classes, and `RootScope::generate_names` assigns one output per class.
+///
+/// Names can only share an output if no region of `RootScope::visibility_regions` contains
+/// two of them, i.e. none is declared in the scope of another or below it, and they are in
+/// the same namespace. Removed names are ignored.
+use ::std::collections::HashSet;
+
+use crate::generate::output_namespace;
+use crate::name::Name;
+use crate::scope::RootScope;
+
+/// Two unified names that cannot share an output, because one is visible where the other
+/// is declared, or they are in different namespaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotUnifiable {
+    // Boxed, since names are large and this is returned in results.
+    names: Box<(Name, Name)>,
+}
+
+impl NotUnifiable {
+    pub(crate) fn new(first: Name, second: Name) -> Self {
+        NotUnifiable {
+            names: Box::new((first, second)),
+        }
+    }
+
+    /// The name registered first.
+    pub fn first(&self) -> &Name {
+        &self.names.0
+    }
+
+    pub fn second(&self) -> &Name {
+        &self.names.1
+    }
+}
+
+impl RootScope {
+    /// Require that the two names get the same output. Fails, without unifying anything,
+    /// if they or the names already unified with them cannot share an output.
+    ///
+    /// Panics if either name is from another tree.
+    pub fn unify(&self, first: &Name, second: &Name) -> Result<(), NotUnifiable> {
+        assert!(
+            first.root == *self && second.root == *self,
+            "cannot unify names of other trees"
+        );
+        let mut members = first.unified_names();
+        for member in second.unified_names() {
+            if !members.contains(&member) {
+                members.push(member);
+            }
+        }
+        check_unifiable(&members)?;
+        let mut names = self.root_data.names.borrow_mut();
+        names[first.index].unified.push(second.index);
+        names[second.index].unified.push(first.index);
+        Ok(())
+    }
+
+    /// The arena indices of each class of at least two unified names that are not removed,
+    /// each in registration order.
+    pub(crate) fn unified_classes(&self) -> Vec<Vec<usize>> {
+        let mut seen = HashSet::new();
+        let mut classes = vec![];
+        let count = self.root_data.names.borrow().len();
+        for index in 0..count {
+            if seen.contains(&index) || self.root_data.names.borrow()[index].unified.is_empty() {
+                continue;
+            }
+            let class = self.unified_indices(index);
+            seen.extend(class.iter().cloned());
+            if class.len() > 1 {
+                classes.push(class);
+            }
+        }
+        classes
+    }
+
+    // The names connected to the name through unification, including itself, in
+    // registration order, skipping removed names.
+    fn unified_indices(&self, start: usize) -> Vec<usize> {
+        let names = self.root_data.names.borrow();
+        let mut class = vec![start];
+        let mut pending = vec![start];
+        while let Some(index) = pending.pop() {
+            for &other in &names[index].unified {
+                if !class.contains(&other) {
+                    class.push(other);
+                    pending.push(other);
+                }
+            }
+        }
+        class.retain(|&index| !names[index].removed);
+        class.sort_unstable();
+        class
+    }
+}
+
+impl Name {
+    /// This name and the names unified with it, directly or through others, in
+    /// registration order.
+    pub fn unified_names(&self) -> Vec<Name> {
+        let mut names: Vec<Name> = self
+            .root
+            .unified_indices(self.index)
+            .into_iter()
+            .map(|index| self.root.name_at(index))
+            .collect();
+        if !names.contains(self) {
+            names.insert(0, self.clone());
+        }
+        names
+    }
+}
+
+/// Check that the names can share an output.
+pub(crate) fn check_unifiable(members: &[Name]) -> Result<(), NotUnifiable> {
+    for (position, first) in members.iter().enumerate() {
+        for second in &members[position + 1..] {
+            let (first_scope, second_scope) = (first.scope(), second.scope());
+            let is_visible = first_scope.is_in_subtree_of(&second_scope)
+                || second_scope.is_in_subtree_of(&first_scope);
+            if is_visible || output_namespace(first) != output_namespace(second) {
+                return Err(NotUnifiable::new(first.clone(), second.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{GenerateError, OutputPolicy};
+    use crate::scope::RootScope;
+    use crate::unify::NotUnifiable;
+
+    #[test]
+    fn unified_names_share_output() {
+        let root = RootScope::new_root();
+        let caller = root.add_child();
+        let argument = caller.add_prefixed("arg");
+        let callee = root.add_child();
+        callee.add_named("value2").unwrap();
+        let parameter = callee.add_named("value").unwrap();
+        let block = caller.add_child();
+        let inner = block.add_named("value").unwrap();
+        let other = root.add_child().add_named("value").unwrap();
+        root.root().unify(&parameter, &argument).unwrap();
+        assert_eq!(
+            argument.unified_names(),
+            vec![argument.clone(), parameter.clone()]
+        );
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        // The argument is reached first and takes "arg", which the parameter then shares.
+        assert_eq!(map.get(&argument).unwrap().as_str(), "arg");
+        assert_eq!(map.get(&parameter).unwrap().as_str(), "arg");
+        assert_eq!(map.get(&inner).unwrap().as_str(), "value");
+        assert_eq!(map.get(&other).unwrap().as_str(), "value");
+    }
+
+    #[test]
+    fn pinned_outputs_are_avoided() {
+        let root = RootScope::new_root();
+        let first = root.add_child();
+        let x = first.add_named("x").unwrap();
+        let second = root.add_child();
+        let blocker = second.add_named("y").unwrap();
+        let inner = second.add_child();
+        let y = inner.add_named("y").unwrap();
+        root.root().unify(&x, &y).unwrap();
+        let map = root
+            .root()
+            .generate_names(&OutputPolicy::default())
+            .unwrap();
+        assert_eq!(map.get(&x).unwrap().as_str(), "x");
+        assert_eq!(map.get(&y).unwrap().as_str(), "x");
+        assert_eq!(map.get(&blocker).unwrap().as_str(), "y");
+    }
+
+    #[test]
+    fn visible_names_cannot_unify() {
+        let root = RootScope::new_root();
+        let outer = root.add_named("outer").unwrap();
+        let child = root.add_child();
+        let inner = child.add_named("inner").unwrap();
+        let sibling = root.add_child().add_named("sibling").unwrap();
+        root.root().unify(&inner, &sibling).unwrap();
+        assert_eq!(
+            root.root().unify(&outer, &sibling),
+            Err(NotUnifiable::new(outer.clone(), inner.clone()))
+        );
+        // Moving a name after unifying can make it impossible.
+        sibling.move_to(&child).unwrap();
+        assert_eq!(
+            root.root()
+                .generate_names(&OutputPolicy::default())
+                .unwrap_err(),
+            GenerateError::NotUnifiable(NotUnifiable::new(inner, sibling))
+        );
+    }
+}
@@ -0,0 +1,164 @@
+/// Validating given names against an `OutputPolicy` when they are added, so that a name
+/// the target language cannot express is reported where it enters the tree, with the
+/// offending character, rather than silently sanitized during generation.
+///
+/// Validation is opt-in: `Scope::add_named` accepts any text, `Scope::add_validated` checks
+/// it first. The canonical form of the name is checked, see `RootScope::set_canonicalization`.
+use ::std::fmt;
+
+use crate::generate::OutputPolicy;
+use crate::limit::{LimitExceeded, RegisterError};
+use crate::name::Name;
+use crate::scope::Scope;
+
+/// Why a text is not a valid identifier under an `OutputPolicy`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidIdentifier {
+    Empty,
+    /// The text has more characters than the policy allows.
+    TooLong {
+        length: usize,
+        max_length: usize,
+    },
+    /// The character at this byte offset is not allowed there.
+    Character {
+        character: char,
+        position: usize,
+    },
+    /// The text is a keyword of the policy.
+    Keyword,
+}
+
+impl fmt::Display for InvalidIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidIdentifier::Empty => write!(f, "identifier is empty"),
+            InvalidIdentifier::TooLong { length, max_length } => write!(
+                f,
+                "identifier has {} characters, at most {} are allowed",
+                length, max_length
+            ),
+            InvalidIdentifier::Character {
+                character,
+                position,
+            } => write!(
+                f,
+                "character {:?} at {} is not allowed",
+                character, position
+            ),
+            InvalidIdentifier::Keyword => write!(f, "identifier is a keyword"),
+        }
+    }
+}
+
+/// Why `Scope::add_validated` did not add a name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidName {
+    Identifier(InvalidIdentifier),
+    AlreadyExists,
+    /// The scope cannot have more names, see `RootScope::set_limits`.
+    LimitExceeded(LimitExceeded),
+}
+
+impl From<InvalidIdentifier> for InvalidName {
+    fn from(invalid: InvalidIdentifier) -> Self {
+        InvalidName::Identifier(invalid)
+    }
+}
+
+impl From<RegisterError> for InvalidName {
+    fn from(error: RegisterError) -> Self {
+        match error {
+            RegisterError::AlreadyExists => InvalidName::AlreadyExists,
+            RegisterError::LimitExceeded(exceeded) => InvalidName::LimitExceeded(exceeded),
+        }
+    }
+}
+
+impl OutputPolicy {
+    /// Check that the text can be used as an output as it is, without sanitizing.
+    /// Keywords are matched ignoring case if `is_case_insensitive` is set.
+    pub fn validate(&self, text: &str, is_case_insensitive: bool) -> Result<(), InvalidIdentifier> {
+        if text.is_empty() {
+            return Err(InvalidIdentifier::Empty);
+        }
+        if let Some(max_length) = self.max_length {
+            let length = text.chars().count();
+            if length > max_length {
+                return Err(InvalidIdentifier::TooLong { length, max_length });
+            }
+        }
+        for (position, character) in text.char_indices() {
+            let is_allowed = if position == 0 {
+                (self.allowed_start)(character)
+            } else {
+                (self.allowed)(character)
+            };
+            if !is_allowed {
+                return Err(InvalidIdentifier::Character {
+                    character,
+                    position,
+                });
+            }
+        }
+        if self.is_keyword(text, is_case_insensitive) {
+            return Err(InvalidIdentifier::Keyword);
+        }
+        Ok(())
+    }
+}
+
+impl Scope {
+    /// Like `add_named`, but first check that the name is a valid identifier under the
+    /// policy. Nothing is registered if it is not.
+    pub fn add_validated(&self, name: &str, policy: &OutputPolicy) -> Result<Name, InvalidName> {
+        let canonical = self.root.canonical(name);
+        policy.validate(&canonical, self.is_case_insensitive())?;
+        Ok(self.try_add_named(name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::OutputPolicy;
+    use crate::scope::RootScope;
+    use crate::validate::{InvalidIdentifier, InvalidName};
+
+    #[test]
+    fn reject_invalid_names() {
+        let root = RootScope::new_root();
+        let mut policy = OutputPolicy::default().with_keywords(&["if"]);
+        policy.max_length = Some(8);
+        assert!(root.add_validated("counter", &policy).is_ok());
+        assert_eq!(
+            root.add_validated("counter", &policy),
+            Err(InvalidName::AlreadyExists)
+        );
+        assert_eq!(
+            root.add_validated("my-name", &policy),
+            Err(InvalidName::Identifier(InvalidIdentifier::Character {
+                character: '-',
+                position: 2
+            }))
+        );
+        assert_eq!(
+            root.add_validated("2x", &policy),
+            Err(InvalidName::Identifier(InvalidIdentifier::Character {
+                character: '2',
+                position: 0
+            }))
+        );
+        assert_eq!(
+            root.add_validated("iterations", &policy),
+            Err(InvalidName::Identifier(InvalidIdentifier::TooLong {
+                length: 10,
+                max_length: 8
+            }))
+        );
+        assert_eq!(
+            root.add_validated("if", &policy),
+            Err(InvalidName::Identifier(InvalidIdentifier::Keyword))
+        );
+        assert_eq!(root.names().len(), 1);
+    }
+}
@@ -0,0 +1,88 @@
+/// Verifying generated outputs against the guarantees of `RootScope::generate_names`, as a
+/// safety net for custom strategies and hand-picked overrides.
+///
+/// Each output is checked the way `NameMap::override_output` checks a new one, which is
+/// independent of how it was picked: it must not be a keyword or reserved where the name is
+/// visible, and must differ from the outputs of the names declared in its scope, its
+/// ancestors and its descendants. Sibling scopes may share outputs.
+use ::std::collections::HashSet;
+
+use crate::generate::{check_output, InvalidOverride, NameMap};
+use crate::name::Name;
+
+/// An output that breaks a guarantee, see `NameMap::verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// The output of the name is a keyword or reserved where the name is visible.
+    Reserved(Name),
+    /// The names have the same output, and one is visible where the other is declared. The
+    /// name registered first comes first.
+    Conflict(Name, Name),
+}
+
+impl NameMap {
+    /// The violations of the guarantees by the outputs in this map, in registration order of
+    /// the names that find them, each reported once. A name reports at most one conflict of its own, so fixing
+    /// the reported ones may reveal more. Names that were removed since generation are
+    /// skipped.
+    pub fn verify(&self) -> Vec<Violation> {
+        let mut violations = vec![];
+        let mut reported = HashSet::new();
+        for (name, output) in self.iter() {
+            if name.scope().preorder_index().is_none() {
+                continue;
+            }
+            let unified = name.unified_names();
+            match check_output(&self.policy, &name, output.as_str(), &unified, |other| {
+                self.get(other).cloned()
+            }) {
+                Ok(()) | Err(InvalidOverride::NotGenerated) => {}
+                Err(InvalidOverride::Conflict(other)) => {
+                    let pair = if other.index < name.index {
+                        (other, name)
+                    } else {
+                        (name, other)
+                    };
+                    if reported.insert((pair.0.index, pair.1.index)) {
+                        violations.push(Violation::Conflict(pair.0, pair.1));
+                    }
+                }
+                Err(_) => violations.push(Violation::Reserved(name)),
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{OutputName, OutputPolicy};
+    use crate::scope::RootScope;
+    use crate::verify::Violation;
+
+    #[test]
+    fn verify_outputs() {
+        let root = RootScope::new_root();
+        let x = root.add_named("x").unwrap();
+        let y = root.add_named("y").unwrap();
+        let function = root.add_child();
+        let inner = function.add_named("x").unwrap();
+        let sibling = root.add_child();
+        let other = sibling.add_named("x").unwrap();
+        let policy = OutputPolicy::default().with_keywords(&["fn"]);
+        let mut map = root.root().generate_names(&policy).unwrap();
+        assert_eq!(map.verify(), vec![]);
+        // Outputs from a faulty strategy, which overrides would reject.
+        map.outputs.insert(inner.index, OutputName("x".to_owned()));
+        map.outputs.insert(other.index, OutputName("x".to_owned()));
+        map.outputs.insert(y.index, OutputName("fn".to_owned()));
+        assert_eq!(
+            map.verify(),
+            vec![
+                Violation::Conflict(x.clone(), inner),
+                Violation::Reserved(y),
+                Violation::Conflict(x, other),
+            ]
+        );
+    }
+}
@@ -0,0 +1,88 @@
+/// Versioned declarations, for trees that describe several editions of an API at once, e.g.
+/// to generate compatibility shims for multiple target versions.
+///
+/// A versioned declaration applies from its version onwards, until a later version of the
+/// same name replaces it. Versioned declarations are distinct from the ordinary name, which
+/// acts as the fallback for versions before the first versioned one.
+use ::ustr::Ustr;
+
+use crate::limit::RegisterError;
+use crate::name::{GivenName, Name};
+use crate::scope::Scope;
+use crate::text::NameText;
+
+impl Scope {
+    /// Register the declaration of `name` that applies from version `since` onwards, failing
+    /// if that version of the name is already registered.
+    pub fn add_versioned(&self, name: &str, since: u32) -> Result<Name, RegisterError> {
+        self.add_given(GivenName {
+            version: Some(since),
+            ..self.root.given_name(name)
+        })
+    }
+
+    /// Find the nearest declaration of `name` that applies in the active version, starting
+    /// from this scope and moving outwards.
+    ///
+    /// In each scope, the latest versioned declaration that is not newer than the active
+    /// version is used, or otherwise the ordinary name.
+    pub fn resolve_versioned(&self, name: &str, active: u32) -> Option<Name> {
+        let text = self.root.given_name(name).name;
+        let ordinary_name = GivenName::new(text.clone());
+        let mut current = Some(self.clone());
+        while let Some(scope) = current {
+            let latest = scope.root.scope_names_at(scope.index, |data| {
+                data.given_names
+                    .keys()
+                    .filter(|given| given.name == text && given.overload.is_none())
+                    .filter(|given| given.arity.is_none() && given.namespace.is_none())
+                    .filter_map(|given| given.version)
+                    .filter(|since| *since <= active)
+                    .max()
+            });
+            let found = match latest {
+                Some(since) => scope.find_given(&GivenName {
+                    version: Some(since),
+                    ..ordinary_name.clone()
+                }),
+                None => scope.find_given(&ordinary_name),
+            };
+            if found.is_some() {
+                return found;
+            }
+            current = scope.parent();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    #[test]
+    fn resolve_by_version() {
+        let root = RootScope::new_root();
+        let legacy = root.add_named("open").unwrap();
+        let v2 = root.add_versioned("open", 2).unwrap();
+        let v5 = root.add_versioned("open", 5).unwrap();
+        root.add_versioned("open", 2).unwrap_err();
+        let child = root.add_child();
+        assert_eq!(child.resolve_versioned("open", 1), Some(legacy.clone()));
+        assert_eq!(child.resolve_versioned("open", 3), Some(v2.clone()));
+        assert_eq!(child.resolve_versioned("open", 7), Some(v5));
+        assert_eq!(v2.unwrap_given().version(), Some(2));
+        assert_eq!(child.resolve("open"), Some(legacy));
+    }
+
+    #[test]
+    fn inner_versions_shadow() {
+        let root = RootScope::new_root();
+        let outer = root.add_versioned("open", 1).unwrap();
+        let child = root.add_child();
+        let inner = child.add_versioned("open", 3).unwrap();
+        assert_eq!(child.resolve_versioned("open", 2), Some(outer));
+        assert_eq!(child.resolve_versioned("open", 3), Some(inner));
+        assert!(child.resolve_versioned("open", 0).is_none());
+    }
+}
@@ -0,0 +1,181 @@
+/// Visibility of names outside their scope, access-checked resolution, and extraction of
+/// what each scope exports, e.g. to generate module interface files or `.d.ts`-style
+/// declarations.
+///
+/// Private names can be used in their scope and the scopes nested in it. Crate names can be
+/// used anywhere in the tree, and public names can also be used outside of it, so only those
+/// are exported. A scope can grant other scopes access to its private names, like friend
+/// classes in C++.
+use ::std::iter::once;
+
+use ::ustr::Ustr;
+
+use crate::name::{GivenName, Name};
+use crate::scope::{RootScope, Scope};
+use crate::text::NameText;
+
+/// Whether a name can be used from outside the scope that declares it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Visibility {
+    Public,
+    Crate,
+    #[default]
+    Private,
+}
+
+/// Why a member could not be resolved from the requesting scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessError {
+    /// The scope does not declare the name.
+    NotFound,
+    /// The name exists, but it is private to a scope that does not contain the requester.
+    NotVisible(Name),
+}
+
+impl Name {
+    pub fn set_visibility(&self, visibility: Visibility) {
+        self.root.root_data.names.borrow_mut()[self.index].visibility = visibility;
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.root.root_data.names.borrow()[self.index].visibility
+    }
+}
+
+impl Scope {
+    /// The public names declared directly in this scope, in registration order.
+    pub fn exported_names(&self) -> Vec<Name> {
+        self.names()
+            .into_iter()
+            .filter(|name| name.visibility() == Visibility::Public)
+            .collect()
+    }
+
+    /// Whether the name can be used from this scope, based on its visibility.
+    pub fn can_access(&self, name: &Name) -> bool {
+        match name.visibility() {
+            Visibility::Public => true,
+            Visibility::Crate => self.root == name.root,
+            Visibility::Private => {
+                let owner = name.scope();
+                self.is_in_subtree_of(&owner)
+                    || owner
+                        .grants()
+                        .iter()
+                        .any(|friend| self.is_in_subtree_of(friend))
+            }
+        }
+    }
+
+    /// Allow the friend scope, and the scopes nested in it, to access the private names of
+    /// this scope.
+    pub fn grant_access(&self, friend: &Scope) {
+        assert!(
+            self.root == friend.root,
+            "cannot grant access to a scope of another tree"
+        );
+        self.root
+            .scope_data_at(self.index, |data| data.grants.push(friend.index));
+    }
+
+    /// The scopes that were granted access to the private names of this scope.
+    pub fn grants(&self) -> Vec<Scope> {
+        self.root
+            .read_scope_data_at(self.index, |data| data.grants.clone())
+            .into_iter()
+            .map(|index| self.root.scope_at_index(index))
+            .collect()
+    }
+
+    /// Look up a name visible in the target scope itself, like `target::name`, on behalf
+    /// of code in this scope, and check that this scope may access it.
+    pub fn resolve_member(&self, target: &Scope, name: &str) -> Result<Name, AccessError> {
+        let name = target
+            .find_visible_given(&target.root.given_name(name))
+            .ok_or(AccessError::NotFound)?;
+        if !self.can_access(&name) {
+            return Err(AccessError::NotVisible(name));
+        }
+        Ok(name)
+    }
+}
+
+impl RootScope {
+    /// The exported names of every scope that exports any, with parents before children.
+    pub fn public_api(&self) -> Vec<(Scope, Vec<Name>)> {
+        let root = self.root_scope();
+        once(root.clone())
+            .chain(root.descendants())
+            .map(|scope| {
+                let exported = scope.exported_names();
+                (scope, exported)
+            })
+            .filter(|(_, exported)| !exported.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scope::RootScope;
+
+    use super::{AccessError, Visibility};
+
+    #[test]
+    fn export_list() {
+        let root = RootScope::new_root();
+        let main = root.add_named("main").unwrap();
+        root.add_named("helper").unwrap();
+        let module = root.add_child();
+        module.add_child().add_named("local").unwrap();
+        let api = module.add_named("api").unwrap();
+        main.set_visibility(Visibility::Public);
+        api.set_visibility(Visibility::Public);
+        assert_eq!(module.exported_names(), vec![api.clone()]);
+        assert_eq!(
+            root.root().public_api(),
+            vec![(root.clone(), vec![main]), (module, vec![api])]
+        );
+    }
+
+    #[test]
+    fn access_checked_members() {
+        let root = RootScope::new_root();
+        let module = root.add_child();
+        let inner = module.add_child();
+        let private = module.add_named("private").unwrap();
+        let internal = module.add_named("internal").unwrap();
+        internal.set_visibility(Visibility::Crate);
+        let outside = root.add_child();
+        assert_eq!(
+            inner.resolve_member(&module, "private"),
+            Ok(private.clone())
+        );
+        assert_eq!(
+            outside.resolve_member(&module, "private"),
+            Err(AccessError::NotVisible(private))
+        );
+        assert_eq!(outside.resolve_member(&module, "internal"), Ok(internal));
+        assert_eq!(
+            outside.resolve_member(&module, "missing"),
+            Err(AccessError::NotFound)
+        );
+    }
+
+    #[test]
+    fn friend_grants() {
+        let root = RootScope::new_root();
+        let class = root.add_child();
+        let secret = class.add_named("secret").unwrap();
+        let friend = root.add_child();
+        let method = friend.add_child();
+        let stranger = root.add_child();
+        class.grant_access(&friend);
+        assert_eq!(method.resolve_member(&class, "secret"), Ok(secret.clone()));
+        assert_eq!(
+            stranger.resolve_member(&class, "secret"),
+            Err(AccessError::NotVisible(secret))
+        );
+        assert_eq!(class.grants(), vec![friend]);
+    }
+}
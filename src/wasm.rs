@@ -0,0 +1,144 @@
+/// JavaScript bindings through wasm-bindgen, enabled with the `wasm` feature.
+///
+/// Root ids are made from the std hasher keys and an atomic counter, without reading the
+/// clock, which is unavailable on `wasm32-unknown-unknown`. That target has no source of
+/// randomness, so ids are unique within a module instance, but not across instances. The
+/// interner is global, which works there since there is only a single thread.
+///
+/// Outputs are generated with the default policy by `Scope.generateNames`, for the whole
+/// tree of the scope.
+use ::wasm_bindgen::prelude::*;
+
+use crate::generate::{NameMap, OutputPolicy};
+use crate::name::{InputName, Name};
+use crate::scope::{RootScope, Scope};
+
+/// Return the scope of a newly created root.
+#[wasm_bindgen(js_name = newRoot)]
+pub fn new_root() -> JsScope {
+    JsScope {
+        inner: RootScope::new_root(),
+    }
+}
+
+#[wasm_bindgen(js_name = Scope)]
+pub struct JsScope {
+    inner: Scope,
+}
+
+#[wasm_bindgen(js_class = Scope)]
+impl JsScope {
+    pub fn children(&self) -> Vec<JsScope> {
+        self.inner
+            .children()
+            .map(|inner| JsScope { inner })
+            .collect()
+    }
+
+    #[wasm_bindgen(js_name = addChild)]
+    pub fn add_child(&self) -> JsScope {
+        JsScope {
+            inner: self.inner.add_child(),
+        }
+    }
+
+    /// Throws if the name is already registered in this scope.
+    #[wasm_bindgen(js_name = addNamed)]
+    pub fn add_named(&self, name: &str) -> Result<JsName, JsError> {
+        match self.inner.add_named(name) {
+            Ok(inner) => Ok(JsName { inner }),
+            Err(_) => Err(JsError::new(&format!(
+                "name '{}' already exists in this scope",
+                name
+            ))),
+        }
+    }
+
+    #[wasm_bindgen(js_name = addPrefixed)]
+    pub fn add_prefixed(&self, prefix: &str) -> JsName {
+        JsName {
+            inner: self.inner.add_prefixed(prefix),
+        }
+    }
+
+    #[wasm_bindgen(js_name = addAnonymous)]
+    pub fn add_anonymous(&self) -> JsName {
+        JsName {
+            inner: self.inner.add_anonymous(),
+        }
+    }
+
+    /// The nearest declaration of the name, from this scope outwards, or undefined.
+    pub fn resolve(&self, name: &str) -> Option<JsName> {
+        self.inner.resolve(name).map(|inner| JsName { inner })
+    }
+
+    /// Throws if no valid outputs could be generated.
+    #[wasm_bindgen(js_name = generateNames)]
+    pub fn generate_names(&self) -> Result<JsNameMap, JsError> {
+        match self.inner.root().generate_names(&OutputPolicy::default()) {
+            Ok(inner) => Ok(JsNameMap { inner }),
+            Err(error) => Err(JsError::new(&format!(
+                "could not generate names: {:?}",
+                error
+            ))),
+        }
+    }
+
+    pub fn equals(&self, other: &JsScope) -> bool {
+        self.inner == other.inner
+    }
+}
+
+#[wasm_bindgen(js_name = Name)]
+pub struct JsName {
+    inner: Name,
+}
+
+#[wasm_bindgen(js_class = Name)]
+impl JsName {
+    /// The given name, or the prefix for anonymous names.
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> String {
+        match &self.inner.data {
+            InputName::Given(given) => given.name.as_str().to_owned(),
+            InputName::Anonymous(anon) => anon.name.as_str().to_owned(),
+        }
+    }
+
+    #[wasm_bindgen(getter, js_name = isAnonymous)]
+    pub fn is_anonymous(&self) -> bool {
+        matches!(self.inner.data, InputName::Anonymous(_))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn scope(&self) -> JsScope {
+        JsScope {
+            inner: self.inner.scope(),
+        }
+    }
+
+    pub fn equals(&self, other: &JsName) -> bool {
+        self.inner == other.inner
+    }
+}
+
+#[wasm_bindgen(js_name = NameMap)]
+pub struct JsNameMap {
+    inner: NameMap,
+}
+
+#[wasm_bindgen(js_class = NameMap)]
+impl JsNameMap {
+    /// The output of the name, or undefined if it has none in this map.
+    pub fn get(&self, name: &JsName) -> Option<String> {
+        self.inner
+            .get(&name.inner)
+            .map(|output| output.as_str().to_owned())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.inner.len()
+    }
+}
@@ -0,0 +1,94 @@
+/// Runs the `scoped-name` binary on a small tree, to check the options of the `names`
+/// command and the exit status on invalid ones.
+use ::std::io::Write;
+use ::std::process::{Command, Stdio};
+
+const TREE: &str = r#"{"names": ["select", "con", "class", "Total", "total"]}"#;
+
+/// Run `scoped-name names <options> -` with the tree on stdin, returning the exit code,
+/// stdout and stderr.
+fn names(options: &[&str]) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scoped-name"))
+        .arg("names")
+        .args(options)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(TREE.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn names_with_profile() {
+    let cases: &[(&[&str], &str)] = &[
+        (
+            &[],
+            "select -> select\ncon -> con\nclass -> class\nTotal -> Total\ntotal -> total\n",
+        ),
+        (
+            &["--profile", "javascript"],
+            "select -> select\ncon -> con\nclass -> class2\nTotal -> Total\ntotal -> total\n",
+        ),
+        (
+            &["--profile", "filesystem"],
+            "select -> select\ncon -> con2\nclass -> class\nTotal -> Total\ntotal -> total2\n",
+        ),
+        (
+            &["--profile", "sql"],
+            "select -> select2\ncon -> con\nclass -> class\nTotal -> Total\ntotal -> total2\n",
+        ),
+    ];
+    for (options, expected) in cases {
+        assert_eq!(names(options), (0, (*expected).to_owned(), String::new()));
+    }
+}
+
+#[test]
+fn names_with_strategy() {
+    let minified = "select -> a\ncon -> b\nclass -> c\nTotal -> d\ntotal -> e\n";
+    let preserved =
+        "select -> select\ncon -> con\nclass -> class\nTotal -> Total\ntotal -> total\n";
+    let cases: &[(&str, &str)] = &[
+        ("minify", minified),
+        ("compress", minified),
+        ("preserve", preserved),
+        ("descriptive", preserved),
+    ];
+    for (strategy, expected) in cases {
+        assert_eq!(
+            names(&["--strategy", strategy]),
+            (0, (*expected).to_owned(), String::new())
+        );
+    }
+    let (code, out, _) = names(&["--profile", "sql", "--strategy", "minify"]);
+    assert_eq!(code, 0);
+    assert!(out.starts_with("select -> a\n"));
+}
+
+#[test]
+fn names_with_invalid_option() {
+    for options in &[
+        &["--profile", "cobol"][..],
+        &["--strategy", "shortest"],
+        &["--strategy"],
+        &["--verbose", "yes"],
+    ] {
+        let (code, out, err) = names(options);
+        assert_eq!(code, 2);
+        assert_eq!(out, "");
+        assert!(err.starts_with(&format!("invalid option: {}\nusage: ", options.join(" "))));
+    }
+}